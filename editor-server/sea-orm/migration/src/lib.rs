@@ -1,12 +1,20 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20260131_000001_create_table;
+mod m20261019_000001_add_position_quaternion;
+mod m20261019_000002_create_gradient;
+mod m20261019_000003_create_palette;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20260131_000001_create_table::Migration)]
+        vec![
+            Box::new(m20260131_000001_create_table::Migration),
+            Box::new(m20261019_000001_add_position_quaternion::Migration),
+            Box::new(m20261019_000002_create_gradient::Migration),
+            Box::new(m20261019_000003_create_palette::Migration),
+        ]
     }
 }
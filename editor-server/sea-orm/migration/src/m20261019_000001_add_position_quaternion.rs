@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PositionData::Table)
+                    .add_column(ColumnDef::new(PositionData::Qw).double().null())
+                    .add_column(ColumnDef::new(PositionData::Qx).double().null())
+                    .add_column(ColumnDef::new(PositionData::Qy).double().null())
+                    .add_column(ColumnDef::new(PositionData::Qz).double().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PositionData::Table)
+                    .drop_column(PositionData::Qw)
+                    .drop_column(PositionData::Qx)
+                    .drop_column(PositionData::Qy)
+                    .drop_column(PositionData::Qz)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum PositionData {
+    #[iden = "PositionData"]
+    Table,
+    Qw,
+    Qx,
+    Qy,
+    Qz,
+}
@@ -0,0 +1,156 @@
+use sea_orm_migration::prelude::*;
+use sea_query::Alias;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut index_gradient_name = Index::create().unique().col(Gradient::Name).to_owned();
+        manager
+            .create_table(
+                Table::create()
+                    .table(Gradient::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Gradient::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Gradient::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(Gradient::Space)
+                            .custom(Alias::new("ENUM('RGB','HSV')"))
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Gradient::Easing)
+                            .custom(Alias::new(
+                                "ENUM('LINEAR','EASE_IN','EASE_OUT','EASE_IN_OUT')",
+                            ))
+                            .not_null(),
+                    )
+                    .index(&mut index_gradient_name)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(GradientStop::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GradientStop::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(GradientStop::GradientId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(GradientStop::Position).double().not_null())
+                    .col(ColumnDef::new(GradientStop::ColorId).integer().not_null())
+                    .col(ColumnDef::new(GradientStop::Alpha).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-gradient_stop-gradient_id")
+                            .from(GradientStop::Table, GradientStop::GradientId)
+                            .to(Gradient::Table, Gradient::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-gradient_stop-color_id")
+                            .from(GradientStop::Table, GradientStop::ColorId)
+                            .to(Color::Table, Color::Id)
+                            .on_delete(ForeignKeyAction::Restrict)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // the bulbs of gradient control data stay as they are when the gradient is deleted
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ControlData::Table)
+                    .add_column(ColumnDef::new(ControlData::GradientId).integer().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk-control_data-gradient_id")
+                            .from_tbl(ControlData::Table)
+                            .from_col(ControlData::GradientId)
+                            .to_tbl(Gradient::Table)
+                            .to_col(Gradient::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ControlData::Table)
+                    .drop_foreign_key(Alias::new("fk-control_data-gradient_id"))
+                    .drop_column(ControlData::GradientId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(GradientStop::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Gradient::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Color {
+    #[iden = "Color"]
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+pub enum ControlData {
+    #[iden = "ControlData"]
+    Table,
+    GradientId,
+}
+
+#[derive(Iden)]
+pub enum Gradient {
+    #[iden = "Gradient"]
+    Table,
+    Id,
+    Name,
+    Space,
+    Easing,
+}
+
+#[derive(Iden)]
+pub enum GradientStop {
+    #[iden = "GradientStop"]
+    Table,
+    Id,
+    GradientId,
+    Position,
+    ColorId,
+    Alpha,
+}
@@ -0,0 +1,151 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut index_palette_name = Index::create().unique().col(Palette::Name).to_owned();
+        manager
+            .create_table(
+                Table::create()
+                    .table(Palette::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Palette::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Palette::Name).string().not_null())
+                    .index(&mut index_palette_name)
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut index_palette_color = Index::create()
+            .unique()
+            .col(PaletteColor::PaletteId)
+            .col(PaletteColor::Role)
+            .to_owned();
+        manager
+            .create_table(
+                Table::create()
+                    .table(PaletteColor::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PaletteColor::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PaletteColor::PaletteId).integer().not_null())
+                    .col(ColumnDef::new(PaletteColor::Role).string().not_null())
+                    .col(ColumnDef::new(PaletteColor::Position).integer().not_null())
+                    .col(ColumnDef::new(PaletteColor::ColorId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-palette_color-palette_id")
+                            .from(PaletteColor::Table, PaletteColor::PaletteId)
+                            .to(Palette::Table, Palette::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-palette_color-color_id")
+                            .from(PaletteColor::Table, PaletteColor::ColorId)
+                            .to(Color::Table, Color::Id)
+                            .on_delete(ForeignKeyAction::Restrict)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .index(&mut index_palette_color)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PaletteSection::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PaletteSection::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PaletteSection::PaletteId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PaletteSection::Start).integer().not_null())
+                    .col(ColumnDef::new(PaletteSection::End).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-palette_section-palette_id")
+                            .from(PaletteSection::Table, PaletteSection::PaletteId)
+                            .to(Palette::Table, Palette::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PaletteSection::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(PaletteColor::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Palette::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Color {
+    #[iden = "Color"]
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+pub enum Palette {
+    #[iden = "Palette"]
+    Table,
+    Id,
+    Name,
+}
+
+#[derive(Iden)]
+pub enum PaletteColor {
+    #[iden = "PaletteColor"]
+    Table,
+    Id,
+    PaletteId,
+    Role,
+    Position,
+    ColorId,
+}
+
+#[derive(Iden)]
+pub enum PaletteSection {
+    #[iden = "PaletteSection"]
+    Table,
+    Id,
+    PaletteId,
+    Start,
+    End,
+}
@@ -18,6 +18,10 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::control_data::Entity")]
     ControlData,
+    #[sea_orm(has_many = "super::gradient_stop::Entity")]
+    GradientStop,
+    #[sea_orm(has_many = "super::palette_color::Entity")]
+    PaletteColor,
 }
 
 impl Related<super::control_data::Entity> for Entity {
@@ -26,4 +30,16 @@ impl Related<super::control_data::Entity> for Entity {
     }
 }
 
+impl Related<super::gradient_stop::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GradientStop.def()
+    }
+}
+
+impl Related<super::palette_color::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PaletteColor.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
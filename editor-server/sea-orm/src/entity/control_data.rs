@@ -19,6 +19,7 @@ pub struct Model {
     pub color_id: Option<i32>,
     pub effect_id: Option<i32>,
     pub alpha: Option<i32>,
+    pub gradient_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -47,6 +48,14 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Dancer,
+    #[sea_orm(
+        belongs_to = "super::gradient::Entity",
+        from = "Column::GradientId",
+        to = "super::gradient::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Gradient,
     #[sea_orm(has_many = "super::led_bulb::Entity")]
     LedBulb,
     #[sea_orm(
@@ -85,6 +94,12 @@ impl Related<super::dancer::Entity> for Entity {
     }
 }
 
+impl Related<super::gradient::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Gradient.def()
+    }
+}
+
 impl Related<super::led_bulb::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::LedBulb.def()
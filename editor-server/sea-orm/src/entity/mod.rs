@@ -10,11 +10,16 @@ pub mod editing_control_frame;
 pub mod editing_led_effect;
 pub mod editing_position_frame;
 pub mod effect_list_data;
+pub mod gradient;
+pub mod gradient_stop;
 pub mod led_bulb;
 pub mod led_effect;
 pub mod led_effect_state;
 pub mod logger;
 pub mod model;
+pub mod palette;
+pub mod palette_color;
+pub mod palette_section;
 pub mod part;
 pub mod position_data;
 pub mod position_frame;
@@ -23,6 +23,14 @@ pub struct Model {
     pub ry: f64,
     #[sea_orm(column_type = "Double")]
     pub rz: f64,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub qw: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub qx: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub qy: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub qz: Option<f64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
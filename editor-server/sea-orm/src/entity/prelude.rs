@@ -8,11 +8,16 @@ pub use super::editing_control_frame::Entity as EditingControlFrame;
 pub use super::editing_led_effect::Entity as EditingLedEffect;
 pub use super::editing_position_frame::Entity as EditingPositionFrame;
 pub use super::effect_list_data::Entity as EffectListData;
+pub use super::gradient::Entity as Gradient;
+pub use super::gradient_stop::Entity as GradientStop;
 pub use super::led_bulb::Entity as LedBulb;
 pub use super::led_effect::Entity as LedEffect;
 pub use super::led_effect_state::Entity as LedEffectState;
 pub use super::logger::Entity as Logger;
 pub use super::model::Entity as Model;
+pub use super::palette::Entity as Palette;
+pub use super::palette_color::Entity as PaletteColor;
+pub use super::palette_section::Entity as PaletteSection;
 pub use super::part::Entity as Part;
 pub use super::position_data::Entity as PositionData;
 pub use super::position_frame::Entity as PositionFrame;
@@ -10,3 +10,23 @@ pub enum Type {
     #[sea_orm(string_value = "NO_EFFECT")]
     NoEffect,
 }
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "easing")]
+pub enum Easing {
+    #[sea_orm(string_value = "LINEAR")]
+    Linear,
+    #[sea_orm(string_value = "EASE_IN")]
+    EaseIn,
+    #[sea_orm(string_value = "EASE_OUT")]
+    EaseOut,
+    #[sea_orm(string_value = "EASE_IN_OUT")]
+    EaseInOut,
+}
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "space")]
+pub enum Space {
+    #[sea_orm(string_value = "RGB")]
+    Rgb,
+    #[sea_orm(string_value = "HSV")]
+    Hsv,
+}
@@ -0,0 +1,42 @@
+//! Gradient data types.
+
+use async_graphql::Enum;
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+
+/// Color space the stops of a gradient are interpolated in.
+#[derive(Type, Enum, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Debug, Default)]
+#[sqlx(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GradientSpace {
+    #[default]
+    Rgb,
+    /// Hue takes the shorter way around the color wheel.
+    Hsv,
+}
+
+/// Easing applied between every pair of adjacent stops.
+#[derive(Type, Enum, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Debug, Default)]
+#[sqlx(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GradientEasing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct GradientData {
+    pub id: i32,
+    pub name: String,
+    pub space: GradientSpace,
+    pub easing: GradientEasing,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct GradientStopData {
+    pub gradient_id: i32,
+    pub position: f64,
+    pub color_id: i32,
+    pub alpha: i32,
+}
@@ -7,6 +7,7 @@ pub mod dancer;
 pub mod editing_control_frame;
 pub mod editing_led_effect;
 pub mod editing_position_frame;
+pub mod gradient;
 pub mod led_effect;
 pub mod model;
 pub mod part;
@@ -15,4 +15,8 @@ pub struct PositionData {
     pub rx: Option<f64>,
     pub ry: Option<f64>,
     pub rz: Option<f64>,
+    pub qw: Option<f64>,
+    pub qx: Option<f64>,
+    pub qy: Option<f64>,
+    pub qz: Option<f64>,
 }
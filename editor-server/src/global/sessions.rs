@@ -0,0 +1,29 @@
+//! Users connected through websocket.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// user_id => number of open connections
+static SESSIONS: Lazy<Mutex<HashMap<i32, usize>>> = Lazy::new(Default::default);
+
+/// Count a new connection of a user
+pub fn connect(user_id: i32) {
+    *SESSIONS.lock().unwrap().entry(user_id).or_default() += 1;
+}
+
+/// Count a closed connection of a user
+pub fn disconnect(user_id: i32) {
+    let mut sessions = SESSIONS.lock().unwrap();
+    if let Some(count) = sessions.get_mut(&user_id) {
+        *count -= 1;
+        if *count == 0 {
+            sessions.remove(&user_id);
+        }
+    }
+}
+
+/// Check if a user has an open connection to this server
+pub fn is_connected(user_id: i32) -> bool {
+    SESSIONS.lock().unwrap().contains_key(&user_id)
+}
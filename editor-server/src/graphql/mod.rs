@@ -6,7 +6,7 @@ pub mod types;
 
 mod mutations;
 mod queries;
-mod subscriptions;
+pub(crate) mod subscriptions;
 
 use mutations::*;
 use queries::*;
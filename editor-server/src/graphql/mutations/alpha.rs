@@ -0,0 +1,261 @@
+//! Alpha mutation methods.
+
+use crate::graphql::subscriptions::control_map::ControlMapPayload;
+use crate::graphql::subscriptor::Subscriptor;
+use crate::graphql::types::control_data::{
+    ControlFramesSubDatScalar, ControlFramesSubData, RedisControlMandatory,
+};
+use crate::graphql::types::scope::ControlScopeInput;
+use crate::types::global::{PartType, UserContext};
+use crate::utils::data::{check_editing_control_frames, update_redis_controls};
+use crate::utils::revision::update_revision;
+
+use async_graphql::{
+    Context, Enum, Error as GQLError, InputObject, Object, Result as GQLResult, SimpleObject,
+};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Default, Debug)]
+pub enum AlphaMode {
+    /// Multiply the current alpha by the value.
+    #[default]
+    Multiply,
+    /// Replace the current alpha with the value.
+    Set,
+}
+
+impl AlphaMode {
+    fn apply(self, alpha: i32, value: f64) -> i32 {
+        let alpha = match self {
+            AlphaMode::Multiply => alpha as f64 * value,
+            AlphaMode::Set => value,
+        };
+
+        alpha.round().clamp(0.0, 255.0) as i32
+    }
+}
+
+#[derive(InputObject, Default)]
+pub struct EditAlphaInput {
+    pub mode: AlphaMode,
+    pub value: f64,
+    pub scope: ControlScopeInput,
+}
+
+#[derive(SimpleObject, Default)]
+pub struct EditAlphaResponse {
+    ok: bool,
+    msg: String,
+    control_count: i32,
+    bulb_count: i32,
+}
+
+#[derive(Default)]
+pub struct AlphaMutation;
+
+#[Object]
+impl AlphaMutation {
+    // Scale or set the alpha of the control data and LED bulbs in a scope of
+    // dancers, parts and frames, clamped to 0-255
+    async fn edit_alpha(
+        &self,
+        ctx: &Context<'_>,
+        input: EditAlphaInput,
+    ) -> GQLResult<EditAlphaResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+
+        let mysql = clients.mysql_pool();
+        let redis = clients.redis_client();
+
+        tracing::info!("Mutation: editAlpha");
+
+        let EditAlphaInput { mode, value, scope } = input;
+
+        if !value.is_finite() || value < 0.0 {
+            return Err(GQLError::new("Value must be a non-negative number"));
+        }
+
+        let (start, end) = scope.range()?;
+
+        // find the control data in scope
+        let control_data = sqlx::query!(
+            r#"
+                SELECT
+                    ControlData.id,
+                    ControlData.frame_id,
+                    ControlData.dancer_id,
+                    ControlData.alpha AS "alpha!",
+                    Part.name AS "part_name",
+                    Part.type AS "part_type: PartType"
+                FROM ControlData
+                INNER JOIN ControlFrame ON ControlData.frame_id = ControlFrame.id
+                INNER JOIN Part ON ControlData.part_id = Part.id
+                WHERE ControlFrame.start >= ? AND ControlFrame.start <= ?
+                    AND ControlData.alpha IS NOT NULL;
+            "#,
+            start,
+            end
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .filter(|data| scope.contains(data.dancer_id, &data.part_name, data.part_type))
+        .collect_vec();
+
+        let bulbs = sqlx::query!(
+            r#"
+                SELECT
+                    LEDBulb.id,
+                    LEDBulb.alpha,
+                    LEDBulb.control_id,
+                    ControlData.frame_id,
+                    ControlData.dancer_id,
+                    Part.name AS "part_name",
+                    Part.type AS "part_type: PartType"
+                FROM LEDBulb
+                INNER JOIN ControlData ON LEDBulb.control_id = ControlData.id
+                INNER JOIN ControlFrame ON ControlData.frame_id = ControlFrame.id
+                INNER JOIN Part ON ControlData.part_id = Part.id
+                WHERE ControlFrame.start >= ? AND ControlFrame.start <= ?
+                    AND ControlData.type = 'LED_BULBS';
+            "#,
+            start,
+            end
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .filter(|bulb| scope.contains(bulb.dancer_id, &bulb.part_name, bulb.part_type))
+        .collect_vec();
+
+        let frame_ids = control_data
+            .iter()
+            .map(|data| data.frame_id)
+            .chain(bulbs.iter().map(|bulb| bulb.frame_id))
+            .unique()
+            .collect_vec();
+
+        // check editing
+        check_editing_control_frames(mysql, context.user_id, &frame_ids).await?;
+
+        let mut tx = mysql.begin().await?;
+
+        let mut control_count = 0;
+        let mut bulb_count = 0;
+        let mut touched_frame_ids = Vec::new();
+        let mut touched_control_ids = Vec::new();
+
+        for data in &control_data {
+            let alpha = mode.apply(data.alpha, value);
+            if alpha == data.alpha {
+                continue;
+            }
+
+            sqlx::query!(
+                r#"
+                    UPDATE ControlData
+                    SET alpha = ?
+                    WHERE id = ?;
+                "#,
+                alpha,
+                data.id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            control_count += 1;
+            if !touched_frame_ids.contains(&data.frame_id) {
+                touched_frame_ids.push(data.frame_id);
+            }
+        }
+
+        for bulb in &bulbs {
+            let alpha = mode.apply(bulb.alpha, value);
+            if alpha == bulb.alpha {
+                continue;
+            }
+
+            sqlx::query!(
+                r#"
+                    UPDATE LEDBulb
+                    SET alpha = ?
+                    WHERE id = ?;
+                "#,
+                alpha,
+                bulb.id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            bulb_count += 1;
+            if !touched_control_ids.contains(&bulb.control_id) {
+                touched_control_ids.push(bulb.control_id);
+            }
+            if !touched_frame_ids.contains(&bulb.frame_id) {
+                touched_frame_ids.push(bulb.frame_id);
+            }
+        }
+
+        // the bulbs no longer follow the applied gradients
+        for control_id in &touched_control_ids {
+            sqlx::query!(
+                r#"
+                    UPDATE ControlData
+                    SET gradient_id = NULL
+                    WHERE id = ?;
+                "#,
+                control_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for frame_id in &touched_frame_ids {
+            sqlx::query!(
+                r#"
+                    UPDATE ControlFrame
+                    SET data_rev = data_rev + 1
+                    WHERE id = ?;
+                "#,
+                frame_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        // update redis and publish the control map
+        if !touched_frame_ids.is_empty() {
+            let update_frames = update_redis_controls(mysql, redis, &touched_frame_ids)
+                .await?
+                .into_iter()
+                .map(|(id, redis_control)| (id, RedisControlMandatory::from(redis_control)))
+                .collect();
+
+            let control_map_payload = ControlMapPayload {
+                edit_by: context.user_id,
+                frame: ControlFramesSubDatScalar(ControlFramesSubData {
+                    create_frames: HashMap::new(),
+                    delete_frames: Vec::new(),
+                    update_frames,
+                }),
+            };
+            Subscriptor::publish(control_map_payload);
+
+            update_revision(mysql).await?;
+        }
+
+        Ok(EditAlphaResponse {
+            ok: true,
+            msg: format!(
+                "Updated alpha of {} control data and {} bulbs",
+                control_count, bulb_count
+            ),
+            control_count,
+            bulb_count,
+        })
+    }
+}
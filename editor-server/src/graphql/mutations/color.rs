@@ -3,15 +3,27 @@ use crate::db::types::color::ColorData;
 use crate::graphql::types::led::{LEDEffectData, LEDEffectFrame};
 use crate::graphql::{
     subscriptions::color::{ColorMutationMode, ColorPayload},
+    subscriptions::control_map::ControlMapPayload,
+    subscriptions::gradient::{GradientMutationMode, GradientPayload},
     subscriptions::led::LEDPayload,
+    subscriptions::palette::{PaletteMutationMode, PalettePayload},
     subscriptor::Subscriptor,
-    types::color::Color,
+    types::color::{resolve_color_code, Color, HslInput, HsvInput},
+    types::control_data::{ControlFramesSubDatScalar, ControlFramesSubData, RedisControlMandatory},
+    types::gradient::Gradient,
 };
 use crate::types::global::UserContext;
+use crate::utils::data::{check_editing_control_frames, update_redis_controls};
+use crate::utils::gradient::get_gradient;
+use crate::utils::led::get_led_effect_data;
+use crate::utils::palette::get_palette;
 use crate::utils::revision::update_revision;
 
-use async_graphql::{Context, InputObject, Object, Result as GQLResult, SimpleObject};
+use async_graphql::{
+    Context, Error as GQLError, InputObject, Object, Result as GQLResult, SimpleObject,
+};
 use itertools::Itertools;
+use std::collections::HashMap;
 
 // TODO: Remove this after all done
 #[derive(InputObject, Default, Debug)]
@@ -21,7 +33,26 @@ pub struct StringFieldUpdateOperationsInput {
 
 #[derive(InputObject, Default, Debug)]
 pub struct ColorUpdateColorCodeInput {
-    pub set: Vec<i32>,
+    /// RGB from 0 to 255; exactly one of the color spaces must be given.
+    pub set: Option<Vec<i32>>,
+    pub hsv: Option<HsvInput>,
+    pub hsl: Option<HslInput>,
+    /// Hex code, e.g. `#ff8000`.
+    pub hex: Option<String>,
+    /// White of a color temperature in Kelvin.
+    pub kelvin: Option<f64>,
+}
+
+impl ColorUpdateColorCodeInput {
+    pub fn rgb(&self) -> Result<[i32; 3], String> {
+        resolve_color_code(
+            self.set.as_deref(),
+            self.hsv,
+            self.hsl,
+            self.hex.as_deref(),
+            self.kelvin,
+        )
+    }
 }
 
 #[derive(InputObject, Default)]
@@ -32,7 +63,26 @@ pub struct ColorUpdateInput {
 
 #[derive(InputObject, Default, Debug)]
 pub struct ColorCreateColorCodeInput {
-    pub set: Vec<i32>,
+    /// RGB from 0 to 255; exactly one of the color spaces must be given.
+    pub set: Option<Vec<i32>>,
+    pub hsv: Option<HsvInput>,
+    pub hsl: Option<HslInput>,
+    /// Hex code, e.g. `#ff8000`.
+    pub hex: Option<String>,
+    /// White of a color temperature in Kelvin.
+    pub kelvin: Option<f64>,
+}
+
+impl ColorCreateColorCodeInput {
+    pub fn rgb(&self) -> Result<[i32; 3], String> {
+        resolve_color_code(
+            self.set.as_deref(),
+            self.hsv,
+            self.hsl,
+            self.hex.as_deref(),
+            self.kelvin,
+        )
+    }
 }
 
 #[derive(InputObject, Default)]
@@ -49,6 +99,15 @@ pub struct ColorResponse {
     pub ok: bool,
 }
 
+#[derive(SimpleObject, Default)]
+pub struct MergeColorsResponse {
+    pub ok: bool,
+    pub msg: String,
+    pub control_count: i32,
+    pub bulb_count: i32,
+    pub effect_state_count: i32,
+}
+
 #[derive(Default)]
 pub struct ColorMutation;
 
@@ -67,6 +126,8 @@ impl ColorMutation {
 
         tracing::info!("Mutation: editColor");
 
+        let color_code = data.color_code.rgb()?;
+
         let led_effect = sqlx::query!(
             r#"
                 SELECT name FROM Color
@@ -83,9 +144,9 @@ impl ColorMutation {
                 WHERE id = ?;
             "#,
             &data.color.set,
-            data.color_code.set[0],
-            data.color_code.set[1],
-            data.color_code.set[2],
+            color_code[0],
+            color_code[1],
+            color_code[2],
             id
         )
         .execute(mysql)
@@ -108,7 +169,7 @@ impl ColorMutation {
             mutation: ColorMutationMode::Updated,
             id,
             color: Some(data.color.set.clone()),
-            color_code: Some(data.color_code.set.clone()),
+            color_code: Some(color_code.to_vec()),
             edit_by: context.user_id,
             // edit_by: 0,
         };
@@ -120,7 +181,7 @@ impl ColorMutation {
         let color = Color {
             id,
             color: data.color.set,
-            color_code: data.color_code.set,
+            color_code: color_code.to_vec(),
         };
 
         Ok(color)
@@ -132,15 +193,17 @@ impl ColorMutation {
 
         let mysql = clients.mysql_pool();
 
+        let color_code = color.color_code.rgb()?;
+
         let id = sqlx::query!(
             r#"
                 INSERT INTO Color (name, r, g, b)
                 VALUES (?, ?, ?, ?);
             "#,
             &color.color,
-            color.color_code.set[0],
-            color.color_code.set[1],
-            color.color_code.set[2]
+            color_code[0],
+            color_code[1],
+            color_code[2]
         )
         .execute(mysql)
         .await?
@@ -150,7 +213,7 @@ impl ColorMutation {
             mutation: ColorMutationMode::Created,
             id,
             color: Some(color.color.clone()),
-            color_code: Some(color.color_code.set.clone()),
+            color_code: Some(color_code.to_vec()),
             edit_by: context.user_id,
         };
 
@@ -261,7 +324,7 @@ impl ColorMutation {
         let color = Color {
             id,
             color: color.color,
-            color_code: color.color_code.set,
+            color_code: color_code.to_vec(),
         };
 
         Ok(color)
@@ -319,6 +382,56 @@ impl ColorMutation {
             });
         }
 
+        let gradients = sqlx::query!(
+            r#"
+                SELECT DISTINCT Gradient.name
+                FROM GradientStop
+                INNER JOIN Gradient
+                    ON GradientStop.gradient_id = Gradient.id
+                WHERE GradientStop.color_id = ?
+                ORDER BY Gradient.name;
+            "#,
+            id
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        if !gradients.is_empty() {
+            return Ok(ColorResponse {
+                id: 0,
+                msg: format!(
+                    "Color is used in gradients: {}.",
+                    gradients.iter().map(|gradient| &gradient.name).join(", ")
+                ),
+                ok: false,
+            });
+        }
+
+        let palettes = sqlx::query!(
+            r#"
+                SELECT DISTINCT Palette.name
+                FROM PaletteColor
+                INNER JOIN Palette
+                    ON PaletteColor.palette_id = Palette.id
+                WHERE PaletteColor.color_id = ?
+                ORDER BY Palette.name;
+            "#,
+            id
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        if !palettes.is_empty() {
+            return Ok(ColorResponse {
+                id: 0,
+                msg: format!(
+                    "Color is used in palettes: {}.",
+                    palettes.iter().map(|palette| &palette.name).join(", ")
+                ),
+                ok: false,
+            });
+        }
+
         let _ = sqlx::query!(
             r#"
                 DELETE FROM Color
@@ -398,4 +511,289 @@ impl ColorMutation {
             ok: true,
         })
     }
+
+    // Point every reference of a color to another one, then delete it
+    async fn merge_colors(
+        &self,
+        ctx: &Context<'_>,
+        from: i32,
+        into: i32,
+    ) -> GQLResult<MergeColorsResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+
+        let mysql = clients.mysql_pool();
+        let redis = clients.redis_client();
+
+        tracing::info!("Mutation: mergeColors");
+
+        if from == into {
+            return Err(GQLError::new("Cannot merge a color into itself"));
+        }
+
+        for color_id in [from, into] {
+            let color = sqlx::query!(
+                r#"
+                    SELECT id FROM Color
+                    WHERE id = ?;
+                "#,
+                color_id
+            )
+            .fetch_optional(mysql)
+            .await?;
+
+            if color.is_none() {
+                return Err(GQLError::new(format!("Color #{color_id} not found")));
+            }
+        }
+
+        let frame_ids = sqlx::query!(
+            r#"
+                SELECT DISTINCT ControlData.frame_id
+                FROM ControlData
+                LEFT JOIN LEDBulb ON LEDBulb.control_id = ControlData.id
+                WHERE ControlData.color_id = ? OR LEDBulb.color_id = ?;
+            "#,
+            from,
+            from
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .map(|data| data.frame_id)
+        .collect_vec();
+
+        let effect_ids = sqlx::query!(
+            r#"
+                SELECT DISTINCT effect_id FROM LEDEffectState
+                WHERE color_id = ?;
+            "#,
+            from
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .map(|state| state.effect_id)
+        .collect_vec();
+
+        let gradient_ids = sqlx::query!(
+            r#"
+                SELECT DISTINCT gradient_id FROM GradientStop
+                WHERE color_id = ?;
+            "#,
+            from
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .map(|stop| stop.gradient_id)
+        .collect_vec();
+
+        let palette_ids = sqlx::query!(
+            r#"
+                SELECT DISTINCT palette_id FROM PaletteColor
+                WHERE color_id = ?;
+            "#,
+            from
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .map(|color| color.palette_id)
+        .collect_vec();
+
+        // check editing
+        check_editing_control_frames(mysql, context.user_id, &frame_ids).await?;
+
+        let editing_effects = sqlx::query!(
+            r#"
+                SELECT led_effect_id AS "led_effect_id!", user_id
+                FROM EditingLEDEffect
+                WHERE led_effect_id IS NOT NULL AND user_id != ?;
+            "#,
+            context.user_id
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        if let Some(editing) = editing_effects
+            .iter()
+            .find(|editing| effect_ids.contains(&editing.led_effect_id))
+        {
+            return Err(GQLError::new(format!(
+                "LED effect #{} is being edited by user #{}",
+                editing.led_effect_id, editing.user_id
+            )));
+        }
+
+        let mut tx = mysql.begin().await?;
+
+        let control_count = sqlx::query!(
+            r#"
+                UPDATE ControlData
+                SET color_id = ?
+                WHERE color_id = ?;
+            "#,
+            into,
+            from
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() as i32;
+
+        let bulb_count = sqlx::query!(
+            r#"
+                UPDATE LEDBulb
+                SET color_id = ?
+                WHERE color_id = ?;
+            "#,
+            into,
+            from
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() as i32;
+
+        let effect_state_count = sqlx::query!(
+            r#"
+                UPDATE LEDEffectState
+                SET color_id = ?
+                WHERE color_id = ?;
+            "#,
+            into,
+            from
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() as i32;
+
+        // gradients and palettes would lose their stops and roles with the color
+        sqlx::query!(
+            r#"
+                UPDATE GradientStop
+                SET color_id = ?
+                WHERE color_id = ?;
+            "#,
+            into,
+            from
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                UPDATE PaletteColor
+                SET color_id = ?
+                WHERE color_id = ?;
+            "#,
+            into,
+            from
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for frame_id in &frame_ids {
+            sqlx::query!(
+                r#"
+                    UPDATE ControlFrame
+                    SET data_rev = data_rev + 1
+                    WHERE id = ?;
+                "#,
+                frame_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            r#"
+                DELETE FROM Color
+                WHERE id = ?;
+            "#,
+            from
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let color_payload = ColorPayload {
+            mutation: ColorMutationMode::Deleted,
+            id: from,
+            color: None,
+            color_code: None,
+            edit_by: context.user_id,
+        };
+        Subscriptor::publish(color_payload);
+
+        // update redis and publish the control map
+        if !frame_ids.is_empty() {
+            let update_frames = update_redis_controls(mysql, redis, &frame_ids)
+                .await?
+                .into_iter()
+                .map(|(id, redis_control)| (id, RedisControlMandatory::from(redis_control)))
+                .collect();
+
+            let control_map_payload = ControlMapPayload {
+                edit_by: context.user_id,
+                frame: ControlFramesSubDatScalar(ControlFramesSubData {
+                    create_frames: HashMap::new(),
+                    delete_frames: Vec::new(),
+                    update_frames,
+                }),
+            };
+            Subscriptor::publish(control_map_payload);
+        }
+
+        if !effect_ids.is_empty() {
+            let mut update_effects = Vec::new();
+            for effect_id in &effect_ids {
+                update_effects.push(get_led_effect_data(mysql, *effect_id).await?);
+            }
+
+            let led_payload = LEDPayload {
+                create_effects: Vec::new(),
+                update_effects,
+                delete_effects: Vec::new(),
+            };
+            Subscriptor::publish(led_payload);
+        }
+
+        for gradient_id in &gradient_ids {
+            let gradient = get_gradient(mysql, *gradient_id).await?;
+
+            let gradient_payload = GradientPayload {
+                mutation: GradientMutationMode::Updated,
+                id: *gradient_id,
+                gradient: Some(Gradient::from(gradient)),
+                edit_by: context.user_id,
+            };
+            Subscriptor::publish(gradient_payload);
+        }
+
+        for palette_id in &palette_ids {
+            let palette = get_palette(mysql, *palette_id).await?;
+
+            let palette_payload = PalettePayload {
+                mutation: PaletteMutationMode::Updated,
+                id: *palette_id,
+                palette: Some(palette),
+                edit_by: context.user_id,
+            };
+            Subscriptor::publish(palette_payload);
+        }
+
+        update_revision(mysql).await?;
+
+        Ok(MergeColorsResponse {
+            ok: true,
+            msg: format!(
+                "Merged color #{} into #{}: {} control data, {} bulbs and {} effect states",
+                from, into, control_count, bulb_count, effect_state_count
+            ),
+            control_count,
+            bulb_count,
+            effect_state_count,
+        })
+    }
 }
@@ -513,7 +513,7 @@ impl ControlMapMutation {
                                     sqlx::query!(
                                     r#"
                                         UPDATE ControlData
-                                        SET effect_id = NULL, alpha = ?, type = "LED_BULBS", fade = ?
+                                        SET effect_id = NULL, gradient_id = NULL, alpha = ?, type = "LED_BULBS", fade = ?
                                         WHERE frame_id = ? AND part_id = ? AND dancer_id = ?;
                                     "#,
                                     alpha,
@@ -533,7 +533,7 @@ impl ControlMapMutation {
                                     sqlx::query!(
                                         r#"
                                         UPDATE ControlData
-                                        SET effect_id = ?, alpha = ?, type = "EFFECT", fade = ?
+                                        SET effect_id = ?, gradient_id = NULL, alpha = ?, type = "EFFECT", fade = ?
                                         WHERE frame_id = ? AND part_id = ? AND dancer_id = ?;
                                     "#,
                                         effect_id,
@@ -549,7 +549,7 @@ impl ControlMapMutation {
                                     sqlx::query!(
                                         r#"
                                         UPDATE ControlData
-                                        SET effect_id = NULL, alpha = ?, type = "EFFECT", fade = ?
+                                        SET effect_id = NULL, gradient_id = NULL, alpha = ?, type = "EFFECT", fade = ?
                                         WHERE frame_id = ? AND part_id = ? AND dancer_id = ?;
                                     "#,
                                         alpha,
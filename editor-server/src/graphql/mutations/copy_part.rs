@@ -0,0 +1,435 @@
+//! CopyPart mutation methods.
+
+use crate::graphql::subscriptions::control_map::ControlMapPayload;
+use crate::graphql::subscriptor::Subscriptor;
+use crate::graphql::types::control_data::{
+    ControlFramesSubDatScalar, ControlFramesSubData, RedisControlMandatory,
+};
+use crate::types::global::{PartType, UserContext};
+use crate::utils::data::{check_editing_control_frames, update_redis_controls};
+use crate::utils::revision::update_revision;
+use crate::utils::vector::partition_by_field;
+
+use async_graphql::{
+    Context, Error as GQLError, InputObject, Object, Result as GQLResult, SimpleObject,
+};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+#[derive(InputObject, Default)]
+pub struct CopyPartInput {
+    /// The frame to copy the control state from.
+    pub source_frame_id: i32,
+    pub source_dancer_id: i32,
+    /// Copy a single part, or every part of the dancer if not given.
+    pub source_part_id: Option<i32>,
+    /// Copy onto every frame in [start, end]; defaults to the source frame only.
+    pub start: Option<i32>,
+    pub end: Option<i32>,
+    /// Copy onto the same parts (matched by name) of these dancers; defaults to the source dancer.
+    pub target_dancer_ids: Option<Vec<i32>>,
+}
+
+#[derive(SimpleObject, Default)]
+pub struct CopyPartResponse {
+    ok: bool,
+    msg: String,
+    frame_ids: Vec<i32>,
+}
+
+#[derive(Debug)]
+struct DancerPartData {
+    dancer_id: i32,
+    part_id: i32,
+    part_name: String,
+    part_type: PartType,
+    length: Option<i32>,
+}
+
+#[derive(Debug)]
+struct SourceControlData {
+    id: i32,
+    part_id: i32,
+    r#type: String,
+    fade: Option<bool>,
+    color_id: Option<i32>,
+    effect_id: Option<i32>,
+    alpha: Option<i32>,
+    gradient_id: Option<i32>,
+}
+
+#[derive(Default)]
+pub struct CopyPartMutation;
+
+#[Object]
+impl CopyPartMutation {
+    // Copy the control state of a part (or of a whole dancer) on one frame
+    // onto a range of frames and/or onto the same parts of other dancers
+    async fn copy_part(
+        &self,
+        ctx: &Context<'_>,
+        input: CopyPartInput,
+    ) -> GQLResult<CopyPartResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+
+        let mysql = clients.mysql_pool();
+        let redis = clients.redis_client();
+
+        tracing::info!("Mutation: copyPart");
+
+        let CopyPartInput {
+            source_frame_id,
+            source_dancer_id,
+            source_part_id,
+            start,
+            end,
+            target_dancer_ids,
+        } = input;
+
+        // find the frames to copy onto
+        let target_frame_ids = match (start, end) {
+            (Some(start), Some(end)) => {
+                if start > end {
+                    return Err(GQLError::new("Start must not be larger than end"));
+                }
+
+                sqlx::query!(
+                    r#"
+                        SELECT id FROM ControlFrame
+                        WHERE start >= ? AND start <= ?
+                        ORDER BY start ASC;
+                    "#,
+                    start,
+                    end
+                )
+                .fetch_all(mysql)
+                .await?
+                .into_iter()
+                .map(|frame| frame.id)
+                .collect_vec()
+            }
+            (None, None) => vec![source_frame_id],
+            _ => return Err(GQLError::new("Start and end must be given together")),
+        };
+
+        let target_dancer_ids = target_dancer_ids.unwrap_or_else(|| vec![source_dancer_id]);
+
+        // check editing
+        check_editing_control_frames(mysql, context.user_id, &target_frame_ids).await?;
+
+        // fetch parts of all dancers, grouped by dancer
+        let dancer_parts = sqlx::query!(
+            r#"
+                SELECT
+                    Dancer.id AS "dancer_id",
+                    Part.id AS "part_id",
+                    Part.name AS "part_name",
+                    Part.type AS "part_type: PartType",
+                    Part.length
+                FROM Dancer
+                INNER JOIN Part ON Part.model_id = Dancer.model_id
+                ORDER BY Dancer.id ASC, Part.id ASC;
+            "#,
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .map(|row| DancerPartData {
+            dancer_id: row.dancer_id,
+            part_id: row.part_id,
+            part_name: row.part_name,
+            part_type: row.part_type,
+            length: row.length,
+        })
+        .collect_vec();
+
+        let dancer_parts: HashMap<i32, Vec<DancerPartData>> =
+            partition_by_field(|part| part.dancer_id, dancer_parts)
+                .into_iter()
+                .map(|parts| (parts[0].dancer_id, parts))
+                .collect();
+
+        let source_parts = dancer_parts
+            .get(&source_dancer_id)
+            .ok_or(format!("Dancer #{source_dancer_id} not found"))?;
+
+        let source_control_data = sqlx::query!(
+            r#"
+                SELECT
+                    id,
+                    part_id,
+                    type,
+                    fade AS "fade: bool",
+                    color_id,
+                    effect_id,
+                    alpha,
+                    gradient_id
+                FROM ControlData
+                WHERE frame_id = ? AND dancer_id = ?
+                ORDER BY part_id ASC;
+            "#,
+            source_frame_id,
+            source_dancer_id
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .map(|row| SourceControlData {
+            id: row.id,
+            part_id: row.part_id,
+            r#type: row.r#type,
+            fade: row.fade,
+            color_id: row.color_id,
+            effect_id: row.effect_id,
+            alpha: row.alpha,
+            gradient_id: row.gradient_id,
+        })
+        .filter(|data| source_part_id.map_or(true, |part_id| data.part_id == part_id))
+        .collect_vec();
+
+        if source_control_data.is_empty() {
+            return Err(GQLError::new(format!(
+                "No control data of dancer #{source_dancer_id} found in frame #{source_frame_id}"
+            )));
+        }
+
+        // resolve every (source part, target part) pair before touching the database
+        let mut errors = Vec::new();
+        let mut copies = Vec::new();
+
+        for target_dancer_id in &target_dancer_ids {
+            let target_parts = match dancer_parts.get(target_dancer_id) {
+                Some(parts) => parts,
+                None => {
+                    errors.push(format!("Dancer #{target_dancer_id} not found"));
+                    continue;
+                }
+            };
+
+            for data in &source_control_data {
+                let source_part = source_parts
+                    .iter()
+                    .find(|part| part.part_id == data.part_id)
+                    .ok_or(format!("Part #{} not found", data.part_id))?;
+
+                let target_part = match target_parts
+                    .iter()
+                    .find(|part| part.part_name == source_part.part_name)
+                {
+                    Some(part) => part,
+                    None => {
+                        errors.push(format!(
+                            "Dancer #{} has no part named {}",
+                            target_dancer_id, source_part.part_name
+                        ));
+                        continue;
+                    }
+                };
+
+                if target_part.part_type != source_part.part_type {
+                    errors.push(format!(
+                        "Part {} of dancer #{} has a different type",
+                        target_part.part_name, target_dancer_id
+                    ));
+                    continue;
+                }
+
+                if data.r#type == "LED_BULBS" && target_part.length != source_part.length {
+                    errors.push(format!(
+                        "Part {} of dancer #{} has a different length",
+                        target_part.part_name, target_dancer_id
+                    ));
+                    continue;
+                }
+
+                // LED effects belong to a (model, part) pair, so look up the effect
+                // with the same name on the target part
+                let effect_id = match data.effect_id {
+                    Some(effect_id) if target_part.part_id != source_part.part_id => {
+                        let target_effect = sqlx::query!(
+                            r#"
+                                SELECT Target.id
+                                FROM LEDEffect AS Source
+                                INNER JOIN LEDEffect AS Target
+                                    ON Source.name = Target.name
+                                WHERE Source.id = ? AND Target.part_id = ?;
+                            "#,
+                            effect_id,
+                            target_part.part_id
+                        )
+                        .fetch_optional(mysql)
+                        .await?;
+
+                        match target_effect {
+                            Some(effect) => Some(effect.id),
+                            None => {
+                                errors.push(format!(
+                                    "LED effect #{} does not exist on part {} of dancer #{}",
+                                    effect_id, target_part.part_name, target_dancer_id
+                                ));
+                                continue;
+                            }
+                        }
+                    }
+                    effect_id => effect_id,
+                };
+
+                copies.push((*target_dancer_id, target_part.part_id, data, effect_id));
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(GQLError::new(errors.join("\n")));
+        }
+
+        let mut tx = mysql.begin().await?;
+
+        for (dancer_id, part_id, data, effect_id) in &copies {
+            // stale bulbs may remain on non-LED_BULBS control data, only copy the used ones
+            let bulbs = if data.r#type == "LED_BULBS" {
+                sqlx::query!(
+                    r#"
+                        SELECT position, color_id, alpha
+                        FROM LEDBulb
+                        WHERE control_id = ?
+                        ORDER BY position ASC;
+                    "#,
+                    data.id
+                )
+                .fetch_all(&mut *tx)
+                .await?
+            } else {
+                Vec::new()
+            };
+
+            for frame_id in &target_frame_ids {
+                // the source itself does not need to be copied
+                if *frame_id == source_frame_id && *dancer_id == source_dancer_id {
+                    continue;
+                }
+
+                let target = sqlx::query!(
+                    r#"
+                        SELECT id FROM ControlData
+                        WHERE frame_id = ? AND dancer_id = ? AND part_id = ?;
+                    "#,
+                    frame_id,
+                    dancer_id,
+                    part_id
+                )
+                .fetch_optional(&mut *tx)
+                .await?;
+
+                let control_id = match target {
+                    Some(target) => {
+                        sqlx::query!(
+                            r#"
+                                UPDATE ControlData
+                                SET type = ?, fade = ?, color_id = ?, effect_id = ?, alpha = ?,
+                                    gradient_id = ?
+                                WHERE id = ?;
+                            "#,
+                            data.r#type,
+                            data.fade,
+                            data.color_id,
+                            effect_id,
+                            data.alpha,
+                            data.gradient_id,
+                            target.id
+                        )
+                        .execute(&mut *tx)
+                        .await?;
+
+                        sqlx::query!(
+                            r#"
+                                DELETE FROM LEDBulb
+                                WHERE control_id = ?;
+                            "#,
+                            target.id
+                        )
+                        .execute(&mut *tx)
+                        .await?;
+
+                        target.id
+                    }
+                    None => sqlx::query!(
+                        r#"
+                            INSERT INTO ControlData
+                            (dancer_id, part_id, frame_id, type, fade, color_id, effect_id, alpha,
+                                gradient_id)
+                            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);
+                        "#,
+                        dancer_id,
+                        part_id,
+                        frame_id,
+                        data.r#type,
+                        data.fade,
+                        data.color_id,
+                        effect_id,
+                        data.alpha,
+                        data.gradient_id
+                    )
+                    .execute(&mut *tx)
+                    .await?
+                    .last_insert_id() as i32,
+                };
+
+                for bulb in &bulbs {
+                    sqlx::query!(
+                        r#"
+                            INSERT INTO LEDBulb (control_id, position, color_id, alpha)
+                            VALUES (?, ?, ?, ?);
+                        "#,
+                        control_id,
+                        bulb.position,
+                        bulb.color_id,
+                        bulb.alpha
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+
+        for frame_id in &target_frame_ids {
+            sqlx::query!(
+                r#"
+                    UPDATE ControlFrame
+                    SET data_rev = data_rev + 1
+                    WHERE id = ?;
+                "#,
+                frame_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        // update redis and publish the control map
+        let update_frames = update_redis_controls(mysql, redis, &target_frame_ids)
+            .await?
+            .into_iter()
+            .map(|(id, redis_control)| (id, RedisControlMandatory::from(redis_control)))
+            .collect();
+
+        let control_map_payload = ControlMapPayload {
+            edit_by: context.user_id,
+            frame: ControlFramesSubDatScalar(ControlFramesSubData {
+                create_frames: HashMap::new(),
+                delete_frames: Vec::new(),
+                update_frames,
+            }),
+        };
+        Subscriptor::publish(control_map_payload);
+
+        update_revision(mysql).await?;
+
+        Ok(CopyPartResponse {
+            ok: true,
+            msg: format!("Copied onto {} frames", target_frame_ids.len()),
+            frame_ids: target_frame_ids,
+        })
+    }
+}
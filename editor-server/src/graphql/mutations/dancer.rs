@@ -1,14 +1,19 @@
 //! Dancer mutation methods.
 use crate::db::types::dancer::DancerData;
 use crate::graphql::{
+    subscriptions::color::{ColorMutationMode, ColorPayload},
     subscriptions::dancer::{DancerMutationMode, DancerPayload},
     subscriptor::Subscriptor,
     types::dancer::Dancer,
 };
-use crate::types::global::UserContext;
+use crate::types::global::{PartType, UserContext};
+use crate::utils::color::{get_or_create_color, rgb_to_hex};
 use crate::utils::data::{init_redis_control, init_redis_position};
+use crate::utils::revision::update_revision;
 
 use async_graphql::{Context, InputObject, Object, Result as GQLResult, SimpleObject};
+use itertools::Itertools;
+use std::collections::HashMap;
 
 #[derive(InputObject, Default, Debug)]
 pub struct DancerUpdateInput {
@@ -22,6 +27,20 @@ pub struct DancerCreateInput {
     pub model: String,
 }
 
+#[derive(InputObject, Default, Debug)]
+pub struct DancerCloneInput {
+    /// The dancer to clone.
+    pub id: i32,
+    pub name: String,
+    /// Model of the new dancer, must have the parts and LED effects of the cloned dancer;
+    /// defaults to the model of the cloned dancer. Parts only on this model are turned off.
+    pub model: Option<String>,
+    /// Offset added to every position of the new dancer.
+    pub offset_x: Option<f64>,
+    pub offset_y: Option<f64>,
+    pub offset_z: Option<f64>,
+}
+
 #[derive(InputObject, Default, Debug)]
 pub struct DancerDeleteInput {
     pub id: i32,
@@ -246,4 +265,384 @@ impl DancerMutation {
             msg: "Dancer updated".to_string(),
         })
     }
+
+    // Create a new dancer with all the control and position data of an existing one
+    async fn clone_dancer(
+        &self,
+        ctx: &Context<'_>,
+        input: DancerCloneInput,
+    ) -> GQLResult<DancerMutationResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+
+        let mysql = clients.mysql_pool();
+        let redis = clients.redis_client();
+
+        tracing::info!("Mutation: cloneDancer");
+
+        let source = sqlx::query_as!(
+            DancerData,
+            r#"
+                SELECT * FROM Dancer WHERE id = ?;
+            "#,
+            input.id
+        )
+        .fetch_optional(mysql)
+        .await?;
+
+        let source = match source {
+            Some(dancer) => dancer,
+            None => {
+                return Ok(DancerMutationResponse {
+                    ok: false,
+                    msg: "Dancer not found.".to_string(),
+                })
+            }
+        };
+
+        let existing = sqlx::query!(
+            r#"
+                SELECT id FROM Dancer WHERE name = ?;
+            "#,
+            input.name
+        )
+        .fetch_optional(mysql)
+        .await?;
+
+        if existing.is_some() {
+            return Ok(DancerMutationResponse {
+                ok: false,
+                msg: "Dancer already exists.".to_string(),
+            });
+        }
+
+        let model_id = match &input.model {
+            Some(model) => {
+                let raw_model = sqlx::query!(
+                    r#"
+                        SELECT id FROM Model WHERE name = ?;
+                    "#,
+                    model
+                )
+                .fetch_optional(mysql)
+                .await?;
+
+                match raw_model {
+                    Some(model) => model.id,
+                    None => {
+                        return Ok(DancerMutationResponse {
+                            ok: false,
+                            msg: "Model not found.".to_string(),
+                        })
+                    }
+                }
+            }
+            None => source.model_id,
+        };
+
+        // map every part of the source model onto the part with the same name
+        let source_parts = sqlx::query!(
+            r#"
+                SELECT id, name, type AS "part_type: PartType", length
+                FROM Part
+                WHERE model_id = ?;
+            "#,
+            source.model_id
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        let target_parts = sqlx::query!(
+            r#"
+                SELECT id, name, type AS "part_type: PartType", length
+                FROM Part
+                WHERE model_id = ?;
+            "#,
+            model_id
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        let mut part_ids = Vec::new();
+        for source_part in &source_parts {
+            let target_part = target_parts.iter().find(|part| {
+                part.name == source_part.name
+                    && part.part_type == source_part.part_type
+                    && part.length == source_part.length
+            });
+
+            match target_part {
+                Some(target_part) => part_ids.push((source_part.id, target_part.id)),
+                None => {
+                    return Ok(DancerMutationResponse {
+                        ok: false,
+                        msg: format!("Model has no part compatible with {}.", source_part.name),
+                    })
+                }
+            }
+        }
+
+        // LED effects belong to a (model, part) pair, look up the ones with the same name
+        let mut effect_ids = HashMap::new();
+        if model_id != source.model_id {
+            let source_effects = sqlx::query!(
+                r#"
+                    SELECT DISTINCT
+                        LEDEffect.id,
+                        LEDEffect.name,
+                        LEDEffect.part_id,
+                        Part.name AS "part_name"
+                    FROM ControlData
+                    INNER JOIN LEDEffect ON ControlData.effect_id = LEDEffect.id
+                    INNER JOIN Part ON LEDEffect.part_id = Part.id
+                    WHERE ControlData.dancer_id = ?;
+                "#,
+                source.id
+            )
+            .fetch_all(mysql)
+            .await?;
+
+            for effect in &source_effects {
+                let part_id = match part_ids
+                    .iter()
+                    .find(|(source, _)| *source == effect.part_id)
+                {
+                    Some((_, target)) => *target,
+                    None => continue,
+                };
+
+                let target_effect = sqlx::query!(
+                    r#"
+                        SELECT id FROM LEDEffect
+                        WHERE name = ? AND part_id = ?;
+                    "#,
+                    effect.name,
+                    part_id
+                )
+                .fetch_optional(mysql)
+                .await?;
+
+                match target_effect {
+                    Some(target_effect) => {
+                        effect_ids.insert(effect.id, target_effect.id);
+                    }
+                    None => {
+                        return Ok(DancerMutationResponse {
+                            ok: false,
+                            msg: format!(
+                                "Model has no LED effect {} for part {}.",
+                                effect.name, effect.part_name
+                            ),
+                        })
+                    }
+                }
+            }
+        }
+
+        // parts only on the target model have no data to copy
+        let extra_parts = target_parts
+            .iter()
+            .filter(|part| !part_ids.iter().any(|(_, target)| *target == part.id))
+            .collect_vec();
+
+        let mut tx = mysql.begin().await?;
+
+        let dancer_id = sqlx::query!(
+            r#"
+                INSERT INTO Dancer (name, model_id) VALUES (?, ?);
+            "#,
+            input.name,
+            model_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .last_insert_id() as i32;
+
+        let control_data = sqlx::query!(
+            r#"
+                SELECT
+                    id, part_id, frame_id, type, fade AS "fade: bool",
+                    color_id, effect_id, gradient_id, alpha
+                FROM ControlData
+                WHERE dancer_id = ?;
+            "#,
+            source.id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for data in &control_data {
+            let part_id = match part_ids.iter().find(|(source, _)| *source == data.part_id) {
+                Some((_, target)) => *target,
+                None => continue,
+            };
+
+            let effect_id = data
+                .effect_id
+                .map(|effect_id| *effect_ids.get(&effect_id).unwrap_or(&effect_id));
+
+            let control_id = sqlx::query!(
+                r#"
+                    INSERT INTO ControlData
+                    (dancer_id, part_id, frame_id, type, fade, color_id, effect_id, gradient_id, alpha)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);
+                "#,
+                dancer_id,
+                part_id,
+                data.frame_id,
+                data.r#type,
+                data.fade,
+                data.color_id,
+                effect_id,
+                data.gradient_id,
+                data.alpha
+            )
+            .execute(&mut *tx)
+            .await?
+            .last_insert_id() as i32;
+
+            sqlx::query!(
+                r#"
+                    INSERT INTO LEDBulb (control_id, position, color_id, alpha)
+                    SELECT ?, position, color_id, alpha
+                    FROM LEDBulb
+                    WHERE control_id = ?;
+                "#,
+                control_id,
+                data.id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // turn the extra parts off on the first frame and keep them so
+        let mut off_color = None;
+        if !extra_parts.is_empty() {
+            let frame_ids = sqlx::query!(
+                r#"
+                    SELECT id FROM ControlFrame
+                    ORDER BY start ASC;
+                "#,
+            )
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .map(|frame| frame.id)
+            .collect_vec();
+
+            let (color_id, created) = get_or_create_color(&mut tx, [0, 0, 0]).await?;
+            if created {
+                off_color = Some(color_id);
+            }
+
+            for part in &extra_parts {
+                for (index, frame_id) in frame_ids.iter().enumerate() {
+                    let r#type = match (index, part.part_type) {
+                        (0, PartType::FIBER) => "COLOR",
+                        (0, PartType::LED) => "LED_BULBS",
+                        _ => "NO_EFFECT",
+                    };
+
+                    let control_id = sqlx::query!(
+                        r#"
+                            INSERT INTO ControlData
+                            (dancer_id, part_id, frame_id, type, color_id, alpha, fade)
+                            VALUES (?, ?, ?, ?, ?, ?, ?);
+                        "#,
+                        dancer_id,
+                        part.id,
+                        frame_id,
+                        r#type,
+                        (r#type == "COLOR").then_some(color_id),
+                        0,
+                        0
+                    )
+                    .execute(&mut *tx)
+                    .await?
+                    .last_insert_id() as i32;
+
+                    if r#type == "LED_BULBS" {
+                        for position in 0..part.length.unwrap_or(0) {
+                            sqlx::query!(
+                                r#"
+                                    INSERT INTO LEDBulb (control_id, position, color_id, alpha)
+                                    VALUES (?, ?, ?, ?);
+                                "#,
+                                control_id,
+                                position,
+                                color_id,
+                                0
+                            )
+                            .execute(&mut *tx)
+                            .await?;
+                        }
+                    }
+                }
+            }
+        }
+
+        sqlx::query!(
+            r#"
+                INSERT INTO PositionData (dancer_id, frame_id, type, x, y, z, rx, ry, rz, qw, qx, qy, qz)
+                SELECT
+                    ?, frame_id, type,
+                    CASE WHEN type = 'POSITION' THEN x + ? ELSE x END,
+                    CASE WHEN type = 'POSITION' THEN y + ? ELSE y END,
+                    CASE WHEN type = 'POSITION' THEN z + ? ELSE z END,
+                    rx, ry, rz, qw, qx, qy, qz
+                FROM PositionData
+                WHERE dancer_id = ?;
+            "#,
+            dancer_id,
+            input.offset_x.unwrap_or(0.0),
+            input.offset_y.unwrap_or(0.0),
+            input.offset_z.unwrap_or(0.0),
+            source.id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        init_redis_control(mysql, redis).await?;
+        init_redis_position(mysql, redis).await?;
+
+        if let Some(id) = off_color {
+            let code = [0, 0, 0];
+            let color_payload = ColorPayload {
+                mutation: ColorMutationMode::Created,
+                id,
+                color: Some(rgb_to_hex(code)),
+                color_code: Some(code.to_vec()),
+                edit_by: context.user_id,
+            };
+            Subscriptor::publish(color_payload);
+        }
+
+        let dancer_payload = DancerPayload {
+            mutation: DancerMutationMode::Created,
+            dancer_data: Some(Dancer {
+                id: dancer_id,
+                name: input.name.clone(),
+                parts: None,
+                position_datas: None,
+            }),
+            edit_by: context.user_id,
+        };
+
+        Subscriptor::publish(dancer_payload);
+
+        update_revision(mysql).await?;
+
+        let msg = if extra_parts.is_empty() {
+            "Dancer cloned".to_string()
+        } else {
+            format!(
+                "Dancer cloned, parts turned off: {}",
+                extra_parts.iter().map(|part| &part.name).join(", ")
+            )
+        };
+
+        Ok(DancerMutationResponse { ok: true, msg })
+    }
 }
@@ -0,0 +1,196 @@
+//! Formation mutation methods.
+
+use crate::graphql::mutations::motion::MotionAxis;
+use crate::graphql::subscriptions::position_map::PositionMapPayload;
+use crate::graphql::subscriptor::Subscriptor;
+use crate::graphql::types::pos_data::{FrameData, PosDataScalar};
+use crate::types::global::UserContext;
+use crate::utils::data::{check_editing_position_frames, update_redis_positions};
+use crate::utils::motion::{Pose, Transform};
+use crate::utils::revision::update_revision;
+
+use async_graphql::{
+    Context, Error as GQLError, InputObject, Object, Result as GQLResult, SimpleObject,
+};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+#[derive(InputObject, Default)]
+pub struct TransformFormationInput {
+    pub dancer_ids: Vec<i32>,
+    /// Frames with start in [start, end] are transformed; defaults to the whole show.
+    pub start: Option<i32>,
+    pub end: Option<i32>,
+    /// Center of mirroring, scaling and rotation; defaults to the origin.
+    pub pivot: Option<[f64; 3]>,
+    /// Mirror across the plane through the pivot normal to this axis.
+    pub mirror: Option<MotionAxis>,
+    /// Positive factor, defaults to 1.
+    pub scale: Option<f64>,
+    /// Rotate by this angle (in radians) about `rotate_axis` through the pivot.
+    pub rotate: Option<f64>,
+    pub rotate_axis: Option<MotionAxis>,
+    pub translate: Option<[f64; 3]>,
+}
+
+#[derive(SimpleObject, Default)]
+pub struct TransformFormationResponse {
+    ok: bool,
+    msg: String,
+    frame_ids: Vec<i32>,
+}
+
+#[derive(Default)]
+pub struct FormationMutation;
+
+#[Object]
+impl FormationMutation {
+    // Mirror, scale, rotate and translate the positions of a group of dancers
+    async fn transform_formation(
+        &self,
+        ctx: &Context<'_>,
+        input: TransformFormationInput,
+    ) -> GQLResult<TransformFormationResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+
+        let mysql = clients.mysql_pool();
+        let redis = clients.redis_client();
+
+        tracing::info!("Mutation: transformFormation");
+
+        let start = input.start.unwrap_or(0);
+        let end = input.end.unwrap_or(i32::MAX);
+
+        if start > end {
+            return Err(GQLError::new("Start must not be larger than end"));
+        }
+
+        let scale = input.scale.unwrap_or(1.0);
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(GQLError::new("Scale must be a positive number"));
+        }
+
+        let transform = Transform {
+            pivot: input.pivot.unwrap_or_default(),
+            mirror: input.mirror.map(usize::from),
+            scale,
+            rotate: input
+                .rotate
+                .map(|angle| (angle, usize::from(input.rotate_axis.unwrap_or_default()))),
+            translate: input.translate.unwrap_or_default(),
+        };
+
+        let position_data = sqlx::query!(
+            r#"
+                SELECT
+                    PositionData.dancer_id,
+                    PositionData.frame_id,
+                    PositionData.x AS "x!",
+                    PositionData.y AS "y!",
+                    PositionData.z AS "z!",
+                    PositionData.rx,
+                    PositionData.ry,
+                    PositionData.rz,
+                    PositionData.qw,
+                    PositionData.qx,
+                    PositionData.qy,
+                    PositionData.qz
+                FROM PositionData
+                INNER JOIN PositionFrame ON PositionData.frame_id = PositionFrame.id
+                WHERE PositionFrame.start >= ? AND PositionFrame.start <= ?
+                    AND PositionData.type = 'POSITION';
+            "#,
+            start,
+            end
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .filter(|data| input.dancer_ids.contains(&data.dancer_id))
+        .collect_vec();
+
+        let frame_ids = position_data
+            .iter()
+            .map(|data| data.frame_id)
+            .unique()
+            .collect_vec();
+
+        // check editing
+        check_editing_position_frames(mysql, context.user_id, &frame_ids).await?;
+
+        let mut tx = mysql.begin().await?;
+
+        for data in &position_data {
+            let pose = transform.apply(Pose {
+                location: [data.x, data.y, data.z],
+                rotation: [data.rx, data.ry, data.rz],
+                quaternion: match (data.qw, data.qx, data.qy, data.qz) {
+                    (Some(w), Some(x), Some(y), Some(z)) => Some([w, x, y, z]),
+                    _ => None,
+                },
+            });
+
+            sqlx::query!(
+                r#"
+                    UPDATE PositionData
+                    SET x = ?, y = ?, z = ?, rx = ?, ry = ?, rz = ?,
+                        qw = ?, qx = ?, qy = ?, qz = ?
+                    WHERE dancer_id = ? AND frame_id = ?;
+                "#,
+                pose.location[0],
+                pose.location[1],
+                pose.location[2],
+                pose.rotation[0],
+                pose.rotation[1],
+                pose.rotation[2],
+                pose.quaternion.map(|q| q[0]),
+                pose.quaternion.map(|q| q[1]),
+                pose.quaternion.map(|q| q[2]),
+                pose.quaternion.map(|q| q[3]),
+                data.dancer_id,
+                data.frame_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for frame_id in &frame_ids {
+            sqlx::query!(
+                r#"
+                    UPDATE PositionFrame
+                    SET data_rev = data_rev + 1
+                    WHERE id = ?;
+                "#,
+                frame_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        // update redis and publish the position map
+        if !frame_ids.is_empty() {
+            let update_frames = update_redis_positions(mysql, redis, &frame_ids).await?;
+
+            let position_map_payload = PositionMapPayload {
+                edit_by: context.user_id,
+                frame: PosDataScalar(FrameData {
+                    create_frames: HashMap::new(),
+                    delete_frames: Vec::new(),
+                    update_frames,
+                }),
+            };
+            Subscriptor::publish(position_map_payload);
+
+            update_revision(mysql).await?;
+        }
+
+        Ok(TransformFormationResponse {
+            ok: true,
+            msg: format!("Transformed {} frames", frame_ids.len()),
+            frame_ids,
+        })
+    }
+}
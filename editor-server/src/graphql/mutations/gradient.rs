@@ -0,0 +1,566 @@
+//! Gradient mutation methods.
+
+use crate::db::types::gradient::{GradientData, GradientEasing, GradientSpace};
+use crate::graphql::subscriptions::color::{ColorMutationMode, ColorPayload};
+use crate::graphql::subscriptions::control_map::ControlMapPayload;
+use crate::graphql::subscriptions::gradient::{GradientMutationMode, GradientPayload};
+use crate::graphql::subscriptor::Subscriptor;
+use crate::graphql::types::control_data::{
+    ControlFramesSubDatScalar, ControlFramesSubData, RedisControlMandatory,
+};
+use crate::graphql::types::gradient::{Gradient, GradientStopInput};
+use crate::graphql::types::scope::ControlScopeInput;
+use crate::types::global::{PartType, UserContext};
+use crate::utils::color::{rgb_to_hex, ColorQuantizer};
+use crate::utils::data::{check_editing_control_frames, update_redis_controls};
+use crate::utils::gradient::{self, get_gradient, validate_stops, write_gradient_bulbs, Stop};
+use crate::utils::revision::update_revision;
+
+use async_graphql::{
+    Context, Error as GQLError, InputObject, Object, Result as GQLResult, SimpleObject,
+};
+use itertools::Itertools;
+use sqlx::{MySql, Pool, Transaction};
+use std::collections::HashMap;
+
+/// Largest RGB distance to reuse an existing color for an expanded bulb.
+const DEFAULT_TOLERANCE: f64 = 8.0;
+
+#[derive(InputObject, Default)]
+pub struct AddGradientInput {
+    pub name: String,
+    pub space: Option<GradientSpace>,
+    pub easing: Option<GradientEasing>,
+    pub stops: Vec<GradientStopInput>,
+}
+
+#[derive(InputObject, Default)]
+pub struct EditGradientInput {
+    pub id: i32,
+    pub name: Option<String>,
+    pub space: Option<GradientSpace>,
+    pub easing: Option<GradientEasing>,
+    pub stops: Option<Vec<GradientStopInput>>,
+    /// Largest RGB distance to reuse an existing color; defaults to 8.
+    pub tolerance: Option<f64>,
+}
+
+#[derive(InputObject, Default)]
+pub struct ApplyGradientInput {
+    pub gradient_id: i32,
+    /// Only the LED parts in scope are assigned the gradient.
+    pub scope: ControlScopeInput,
+    /// Largest RGB distance to reuse an existing color; defaults to 8.
+    pub tolerance: Option<f64>,
+}
+
+#[derive(SimpleObject, Default)]
+pub struct GradientResponse {
+    ok: bool,
+    msg: String,
+    gradient: Option<Gradient>,
+    /// Number of control data whose bulbs were expanded.
+    control_count: i32,
+    created_colors: Vec<i32>,
+}
+
+// control data of an LED part to expand a gradient on
+struct GradientTarget {
+    id: i32,
+    frame_id: i32,
+    length: usize,
+}
+
+/// Attach the color codes to the stops, checking the colors exist.
+async fn resolve_stops(mysql: &Pool<MySql>, stops: &[GradientStopInput]) -> GQLResult<Vec<Stop>> {
+    let colors: HashMap<i32, [i32; 3]> = sqlx::query!(
+        r#"
+            SELECT id, r, g, b FROM Color;
+        "#
+    )
+    .fetch_all(mysql)
+    .await?
+    .into_iter()
+    .map(|color| (color.id, [color.r, color.g, color.b]))
+    .collect();
+
+    let mut stops = stops
+        .iter()
+        .map(|stop| {
+            let rgb = colors
+                .get(&stop.color_id)
+                .ok_or(format!("Color #{} not found", stop.color_id))?;
+            Ok(Stop {
+                position: stop.position,
+                color_id: stop.color_id,
+                rgb: *rgb,
+                alpha: stop.alpha.unwrap_or(255),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    validate_stops(&mut stops)?;
+
+    Ok(stops)
+}
+
+async fn insert_stops(
+    tx: &mut Transaction<'static, MySql>,
+    gradient_id: i32,
+    stops: &[Stop],
+) -> GQLResult<()> {
+    for stop in stops {
+        sqlx::query!(
+            r#"
+                INSERT INTO GradientStop (gradient_id, position, color_id, alpha)
+                VALUES (?, ?, ?, ?);
+            "#,
+            gradient_id,
+            stop.position,
+            stop.color_id,
+            stop.alpha
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn get_quantizer(mysql: &Pool<MySql>, tolerance: Option<f64>) -> GQLResult<ColorQuantizer> {
+    let colors = sqlx::query!(
+        r#"
+            SELECT id, r, g, b FROM Color;
+        "#
+    )
+    .fetch_all(mysql)
+    .await?
+    .into_iter()
+    .map(|color| (color.id, [color.r, color.g, color.b]))
+    .collect_vec();
+
+    Ok(ColorQuantizer::new(
+        colors,
+        tolerance.unwrap_or(DEFAULT_TOLERANCE),
+    ))
+}
+
+/// Expand the gradient on the targets, returning the ids of the frames changed.
+async fn expand_on_targets(
+    tx: &mut Transaction<'static, MySql>,
+    quantizer: &mut ColorQuantizer,
+    gradient: &gradient::Gradient,
+    targets: &[GradientTarget],
+) -> GQLResult<Vec<i32>> {
+    for target in targets {
+        write_gradient_bulbs(tx, quantizer, target.id, gradient, target.length).await?;
+    }
+
+    let frame_ids = targets
+        .iter()
+        .map(|target| target.frame_id)
+        .unique()
+        .collect_vec();
+
+    for frame_id in &frame_ids {
+        sqlx::query!(
+            r#"
+                UPDATE ControlFrame
+                SET data_rev = data_rev + 1
+                WHERE id = ?;
+            "#,
+            frame_id
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(frame_ids)
+}
+
+/// Publish the colors created while expanding and the frames changed.
+async fn publish_expanded(
+    context: &UserContext,
+    quantizer: &ColorQuantizer,
+    frame_ids: &[i32],
+) -> GQLResult<()> {
+    let clients = context.clients;
+
+    for (id, code) in &quantizer.created {
+        let color_payload = ColorPayload {
+            mutation: ColorMutationMode::Created,
+            id: *id,
+            color: Some(rgb_to_hex(*code)),
+            color_code: Some(code.to_vec()),
+            edit_by: context.user_id,
+        };
+        Subscriptor::publish(color_payload);
+    }
+
+    if !frame_ids.is_empty() {
+        let update_frames =
+            update_redis_controls(clients.mysql_pool(), clients.redis_client(), frame_ids)
+                .await?
+                .into_iter()
+                .map(|(id, redis_control)| (id, RedisControlMandatory::from(redis_control)))
+                .collect();
+
+        let control_map_payload = ControlMapPayload {
+            edit_by: context.user_id,
+            frame: ControlFramesSubDatScalar(ControlFramesSubData {
+                create_frames: HashMap::new(),
+                delete_frames: Vec::new(),
+                update_frames,
+            }),
+        };
+        Subscriptor::publish(control_map_payload);
+    }
+
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct GradientMutation;
+
+#[Object]
+impl GradientMutation {
+    async fn add_gradient(
+        &self,
+        ctx: &Context<'_>,
+        input: AddGradientInput,
+    ) -> GQLResult<Gradient> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Mutation: addGradient");
+
+        let existing = sqlx::query!(
+            r#"
+                SELECT id FROM Gradient
+                WHERE name = ?;
+            "#,
+            input.name
+        )
+        .fetch_optional(mysql)
+        .await?;
+
+        if existing.is_some() {
+            return Err(GQLError::new(format!(
+                "Gradient {} already exists",
+                input.name
+            )));
+        }
+
+        let stops = resolve_stops(mysql, &input.stops).await?;
+        let space = input.space.unwrap_or_default();
+        let easing = input.easing.unwrap_or_default();
+
+        let mut tx = mysql.begin().await?;
+
+        let id = sqlx::query!(
+            r#"
+                INSERT INTO Gradient (name, space, easing)
+                VALUES (?, ?, ?);
+            "#,
+            input.name,
+            space,
+            easing
+        )
+        .execute(&mut *tx)
+        .await?
+        .last_insert_id() as i32;
+
+        insert_stops(&mut tx, id, &stops).await?;
+
+        tx.commit().await?;
+
+        let gradient = Gradient::from(gradient::Gradient {
+            data: GradientData {
+                id,
+                name: input.name,
+                space,
+                easing,
+            },
+            stops,
+        });
+
+        let gradient_payload = GradientPayload {
+            mutation: GradientMutationMode::Created,
+            id,
+            gradient: Some(gradient.clone()),
+            edit_by: context.user_id,
+        };
+        Subscriptor::publish(gradient_payload);
+
+        update_revision(mysql).await?;
+
+        Ok(gradient)
+    }
+
+    // Edit a gradient, expanding it again on every control data it is assigned to
+    async fn edit_gradient(
+        &self,
+        ctx: &Context<'_>,
+        input: EditGradientInput,
+    ) -> GQLResult<GradientResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Mutation: editGradient");
+
+        let mut gradient = get_gradient(mysql, input.id).await?;
+
+        if let Some(name) = input.name {
+            let existing = sqlx::query!(
+                r#"
+                    SELECT id FROM Gradient
+                    WHERE name = ? AND id != ?;
+                "#,
+                name,
+                input.id
+            )
+            .fetch_optional(mysql)
+            .await?;
+
+            if existing.is_some() {
+                return Err(GQLError::new(format!("Gradient {name} already exists")));
+            }
+            gradient.data.name = name;
+        }
+        if let Some(space) = input.space {
+            gradient.data.space = space;
+        }
+        if let Some(easing) = input.easing {
+            gradient.data.easing = easing;
+        }
+        let stops_changed = input.stops.is_some();
+        if let Some(stops) = &input.stops {
+            gradient.stops = resolve_stops(mysql, stops).await?;
+        }
+
+        // stale gradient ids may remain on control data changed to other types
+        let targets = sqlx::query!(
+            r#"
+                SELECT
+                    ControlData.id,
+                    ControlData.frame_id,
+                    Part.length
+                FROM ControlData
+                INNER JOIN Part ON ControlData.part_id = Part.id
+                WHERE ControlData.gradient_id = ? AND ControlData.type = "LED_BULBS";
+            "#,
+            input.id
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .map(|data| GradientTarget {
+            id: data.id,
+            frame_id: data.frame_id,
+            length: data.length.unwrap_or(0).max(0) as usize,
+        })
+        .collect_vec();
+
+        let frame_ids = targets
+            .iter()
+            .map(|target| target.frame_id)
+            .unique()
+            .collect_vec();
+
+        // check editing
+        check_editing_control_frames(mysql, context.user_id, &frame_ids).await?;
+
+        let mut quantizer = get_quantizer(mysql, input.tolerance).await?;
+
+        let mut tx = mysql.begin().await?;
+
+        sqlx::query!(
+            r#"
+                UPDATE Gradient
+                SET name = ?, space = ?, easing = ?
+                WHERE id = ?;
+            "#,
+            gradient.data.name,
+            gradient.data.space,
+            gradient.data.easing,
+            input.id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if stops_changed {
+            sqlx::query!(
+                r#"
+                    DELETE FROM GradientStop
+                    WHERE gradient_id = ?;
+                "#,
+                input.id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            insert_stops(&mut tx, input.id, &gradient.stops).await?;
+        }
+
+        let frame_ids = expand_on_targets(&mut tx, &mut quantizer, &gradient, &targets).await?;
+
+        tx.commit().await?;
+
+        publish_expanded(context, &quantizer, &frame_ids).await?;
+
+        let gradient = Gradient::from(gradient);
+
+        let gradient_payload = GradientPayload {
+            mutation: GradientMutationMode::Updated,
+            id: input.id,
+            gradient: Some(gradient.clone()),
+            edit_by: context.user_id,
+        };
+        Subscriptor::publish(gradient_payload);
+
+        update_revision(mysql).await?;
+
+        Ok(GradientResponse {
+            ok: true,
+            msg: format!("Expanded gradient on {} control data", targets.len()),
+            gradient: Some(gradient),
+            control_count: targets.len() as i32,
+            created_colors: quantizer.created.iter().map(|(id, _)| *id).collect_vec(),
+        })
+    }
+
+    // Delete a gradient; the control data it was assigned to keep their bulbs
+    async fn delete_gradient(&self, ctx: &Context<'_>, id: i32) -> GQLResult<GradientResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Mutation: deleteGradient");
+
+        let affected = sqlx::query!(
+            r#"
+                DELETE FROM Gradient
+                WHERE id = ?;
+            "#,
+            id
+        )
+        .execute(mysql)
+        .await?
+        .rows_affected();
+
+        if affected == 0 {
+            return Err(GQLError::new(format!("Gradient #{id} not found")));
+        }
+
+        let gradient_payload = GradientPayload {
+            mutation: GradientMutationMode::Deleted,
+            id,
+            gradient: None,
+            edit_by: context.user_id,
+        };
+        Subscriptor::publish(gradient_payload);
+
+        update_revision(mysql).await?;
+
+        Ok(GradientResponse {
+            ok: true,
+            msg: "Gradient deleted".to_string(),
+            ..Default::default()
+        })
+    }
+
+    // Assign a gradient to the LED parts of a scope of dancers, parts and frames
+    async fn apply_gradient(
+        &self,
+        ctx: &Context<'_>,
+        input: ApplyGradientInput,
+    ) -> GQLResult<GradientResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Mutation: applyGradient");
+
+        let ApplyGradientInput {
+            gradient_id,
+            scope,
+            tolerance,
+        } = input;
+
+        let (start, end) = scope.range()?;
+        let gradient = get_gradient(mysql, gradient_id).await?;
+
+        let targets = sqlx::query!(
+            r#"
+                SELECT
+                    ControlData.id,
+                    ControlData.frame_id,
+                    ControlData.dancer_id,
+                    ControlData.type,
+                    Part.name AS "part_name",
+                    Part.type AS "part_type: PartType",
+                    Part.length
+                FROM ControlData
+                INNER JOIN ControlFrame ON ControlData.frame_id = ControlFrame.id
+                INNER JOIN Part ON ControlData.part_id = Part.id
+                WHERE ControlFrame.start >= ? AND ControlFrame.start <= ?;
+            "#,
+            start,
+            end
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        // parts keeping their status are left as they are
+        .filter(|data| {
+            data.part_type == PartType::LED
+                && data.r#type != "NO_EFFECT"
+                && scope.contains(data.dancer_id, &data.part_name, data.part_type)
+        })
+        .map(|data| GradientTarget {
+            id: data.id,
+            frame_id: data.frame_id,
+            length: data.length.unwrap_or(0).max(0) as usize,
+        })
+        .collect_vec();
+
+        if targets.is_empty() {
+            return Err(GQLError::new("No LED part in scope"));
+        }
+
+        let frame_ids = targets
+            .iter()
+            .map(|target| target.frame_id)
+            .unique()
+            .collect_vec();
+
+        // check editing
+        check_editing_control_frames(mysql, context.user_id, &frame_ids).await?;
+
+        let mut quantizer = get_quantizer(mysql, tolerance).await?;
+
+        let mut tx = mysql.begin().await?;
+
+        let frame_ids = expand_on_targets(&mut tx, &mut quantizer, &gradient, &targets).await?;
+
+        tx.commit().await?;
+
+        publish_expanded(context, &quantizer, &frame_ids).await?;
+
+        update_revision(mysql).await?;
+
+        Ok(GradientResponse {
+            ok: true,
+            msg: format!(
+                "Applied gradient {} to {} control data",
+                gradient.data.name,
+                targets.len()
+            ),
+            gradient: Some(gradient.into()),
+            control_count: targets.len() as i32,
+            created_colors: quantizer.created.iter().map(|(id, _)| *id).collect_vec(),
+        })
+    }
+}
@@ -1,12 +1,19 @@
+use crate::graphql::subscriptions::color::{ColorMutationMode, ColorPayload};
 use crate::graphql::types::led::{Frame, LEDEffectData, LEDEffectFrame};
 use crate::graphql::{subscriptions::led::LEDPayload, subscriptor::Subscriptor};
 use crate::types::global::UserContext;
+use crate::utils::color::{rgb_to_hex, ColorQuantizer};
+use crate::utils::led::{
+    blend_leds, get_led_effect_data, insert_led_effect, resample_leds, tile_leds,
+};
 use crate::utils::revision::update_revision;
 
 use async_graphql::{
-    Context, Error as GQLError, InputObject, Object, Result as GQLResult, SimpleObject,
+    Context, Enum, Error as GQLError, InputObject, Object, Result as GQLResult, SimpleObject,
 };
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 // struct defined to to fit old schema
 #[derive(InputObject, Default, Debug)]
@@ -28,6 +35,30 @@ pub struct EditLEDInput {
     pub frames: Vec<Frame>,
 }
 
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Default, Debug)]
+pub enum LEDRetargetMode {
+    /// Scale the states over the new length, taking the nearest state.
+    #[default]
+    Nearest,
+    /// Scale the states over the new length, blending neighbouring colors.
+    Blend,
+    /// Repeat the states over the new length.
+    Tile,
+}
+
+#[derive(InputObject, Default, Debug)]
+#[graphql(name = "RetargetLEDEffectInput")]
+pub struct RetargetLEDEffectInput {
+    pub id: i32,
+    pub model_name: String,
+    pub part_name: String,
+    /// Name of the copy; defaults to the name of the effect.
+    pub name: Option<String>,
+    pub mode: Option<LEDRetargetMode>,
+    /// Largest RGB distance to reuse an existing color for blended states; defaults to 24.
+    pub tolerance: Option<f64>,
+}
+
 #[derive(SimpleObject, Serialize, Deserialize, Default, Debug)]
 #[graphql(name = "LEDEffectResponse")]
 pub struct LEDEffectResponse {
@@ -531,4 +562,192 @@ impl LEDMutation {
             msg: "successfully deleted LED effect".to_string(),
         })
     }
+
+    // Copy an LED effect to another model and part, resampled to the length of the part
+    #[graphql(name = "retargetLEDEffect")]
+    async fn retarget_led_effect(
+        &self,
+        ctx: &Context<'_>,
+        input: RetargetLEDEffectInput,
+    ) -> GQLResult<LEDEffectResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Mutation: retargetLEDEffect");
+
+        let model_name = input.model_name.clone();
+        let part_name = input.part_name.clone();
+
+        let source = match get_led_effect_data(mysql, input.id).await {
+            Ok(source) => source,
+            Err(msg) => {
+                return Ok(LEDEffectResponse {
+                    id: -1,
+                    model_name,
+                    part_name,
+                    effect_name: input.name.unwrap_or_default(),
+                    repeat: 0,
+                    effects: vec![],
+                    ok: false,
+                    msg,
+                })
+            }
+        };
+        let effect_name = input.name.unwrap_or(source.name);
+
+        // check part exists
+        let part = match sqlx::query!(
+            r#"
+                SELECT
+                    Model.id AS "model_id",
+                    Part.id AS "part_id",
+                    Part.length
+                FROM Part
+                INNER JOIN Model ON Part.model_id = Model.id
+                WHERE Part.name = ? AND Model.name = ? AND Part.type = 'LED';
+            "#,
+            &part_name,
+            &model_name,
+        )
+        .fetch_optional(mysql)
+        .await?
+        {
+            Some(part) => part,
+            None => {
+                return Ok(LEDEffectResponse {
+                    id: -1,
+                    model_name,
+                    part_name,
+                    effect_name,
+                    repeat: 0,
+                    effects: vec![],
+                    ok: false,
+                    msg: "No corresponding LED part.".to_string(),
+                })
+            }
+        };
+        let length = part.length.unwrap_or(0).max(0) as usize;
+
+        // check if effect name exists
+        if sqlx::query!(
+            r#"
+                SELECT id FROM LEDEffect
+                WHERE name = ? AND part_id = ? AND model_id = ?;
+            "#,
+            &effect_name,
+            part.part_id,
+            part.model_id
+        )
+        .fetch_optional(mysql)
+        .await?
+        .is_some()
+        {
+            return Ok(LEDEffectResponse {
+                id: -1,
+                model_name,
+                part_name,
+                effect_name,
+                repeat: 0,
+                effects: vec![],
+                ok: false,
+                msg: "effectName exists.".to_string(),
+            });
+        }
+
+        let colors = sqlx::query!(
+            r#"
+                SELECT id, r, g, b FROM Color;
+            "#
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .map(|color| (color.id, [color.r, color.g, color.b]))
+        .collect_vec();
+
+        let source_leds = source
+            .frames
+            .first()
+            .map(|frame| frame.leds.clone())
+            .unwrap_or_default();
+
+        let mut tx = mysql.begin().await?;
+
+        let mut quantizer = ColorQuantizer::new(colors.clone(), input.tolerance.unwrap_or(24.0));
+        let leds = match input.mode.unwrap_or_default() {
+            LEDRetargetMode::Nearest => resample_leds(&source_leds, length),
+            LEDRetargetMode::Tile => tile_leds(&source_leds, length),
+            LEDRetargetMode::Blend => {
+                let codes = colors.into_iter().collect::<HashMap<_, _>>();
+                let values = source_leds
+                    .iter()
+                    .map(|[color_id, alpha]| {
+                        let [r, g, b] = codes.get(color_id).copied().unwrap_or_default();
+                        [r as f64, g as f64, b as f64, *alpha as f64]
+                    })
+                    .collect_vec();
+
+                let mut leds = Vec::new();
+                for value in blend_leds(&values, length) {
+                    let rgb = [value[0], value[1], value[2]].map(|c| c.round() as i32);
+                    let color_id = quantizer.quantize(&mut tx, rgb).await?;
+                    leds.push([color_id, value[3].round() as i32]);
+                }
+                leds
+            }
+        };
+
+        let effect_id =
+            insert_led_effect(&mut tx, &effect_name, part.model_id, part.part_id, &leds).await?;
+
+        tx.commit().await?;
+
+        for (id, code) in quantizer.created {
+            let color_payload = ColorPayload {
+                mutation: ColorMutationMode::Created,
+                id,
+                color: Some(rgb_to_hex(code)),
+                color_code: Some(code.to_vec()),
+                edit_by: context.user_id,
+            };
+            Subscriptor::publish(color_payload);
+        }
+
+        let frames = vec![LEDEffectFrame {
+            leds,
+            fade: false,
+            start: 0,
+        }];
+
+        // publish to subscribers
+        let led_payload = LEDPayload {
+            create_effects: vec![LEDEffectData {
+                id: effect_id,
+                name: effect_name.clone(),
+                model_name: model_name.clone(),
+                part_name: part_name.clone(),
+                repeat: 0,
+                frames: frames.clone(),
+            }],
+            update_effects: Vec::new(),
+            delete_effects: Vec::new(),
+        };
+
+        Subscriptor::publish(led_payload);
+
+        update_revision(mysql).await?;
+
+        Ok(LEDEffectResponse {
+            id: effect_id,
+            model_name,
+            part_name,
+            effect_name,
+            repeat: 0,
+            effects: frames,
+            ok: true,
+            msg: "successfully retargeted LED effect".to_string(),
+        })
+    }
 }
@@ -0,0 +1,584 @@
+//! LED pattern generator mutation methods.
+
+use crate::graphql::subscriptions::color::{ColorMutationMode, ColorPayload};
+use crate::graphql::subscriptions::control_map::ControlMapPayload;
+use crate::graphql::types::control_data::{
+    ControlFramesSubDatScalar, ControlFramesSubData, RedisControlMandatory,
+};
+use crate::graphql::types::led::{LEDEffectData, LEDEffectFrame};
+use crate::graphql::{subscriptions::led::LEDPayload, subscriptor::Subscriptor};
+use crate::types::global::{PartType, UserContext};
+use crate::utils::color::{get_or_create_color, hsv_to_rgb, rgb_to_hex};
+use crate::utils::data::{check_editing_control_frames, update_redis_controls};
+use crate::utils::led;
+use crate::utils::revision::update_revision;
+
+use async_graphql::{
+    Context, Enum, Error as GQLError, InputObject, Object, Result as GQLResult, SimpleObject,
+};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// Upper bound on the number of frames of a cycle.
+const MAX_CYCLE_FRAMES: i32 = 1000;
+/// Upper bound on the number of control frames placed on the timeline at once.
+const MAX_PLACED_FRAMES: usize = 5000;
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Default, Debug)]
+pub enum LEDPattern {
+    /// Colors along the strip, shifting by one strip per cycle.
+    #[default]
+    Rainbow,
+    /// Every `spacing`-th LED lit, moving by one strip per cycle.
+    Chase,
+    /// The whole strip fading in and out once per cycle.
+    Breathe,
+    /// Random LEDs lit, about `density` of the strip at a time.
+    Sparkle,
+    /// A head crossing the strip once per cycle, followed by a fading tail.
+    Comet,
+}
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Default, Debug)]
+pub enum LEDDirection {
+    /// From the first LED to the last.
+    #[default]
+    Forward,
+    Backward,
+}
+
+#[derive(InputObject, Default)]
+pub struct GenerateLEDEffectInput {
+    /// Prefix of the generated effect names.
+    pub name: String,
+    pub model_name: String,
+    pub part_name: String,
+    pub pattern: LEDPattern,
+    /// Colors of the pattern; a hue wheel for rainbows and white otherwise if empty.
+    pub color_ids: Vec<i32>,
+    /// Cycles per second.
+    pub speed: f64,
+    pub direction: Option<LEDDirection>,
+    /// Length of the effect, in milliseconds.
+    pub duration: i32,
+    /// Time between generated frames; defaults to 50.
+    pub interval: Option<i32>,
+    /// Number of hues of a rainbow without colors; defaults to 12.
+    pub hues: Option<i32>,
+    /// Distance between lit LEDs of a chase; defaults to 3.
+    pub spacing: Option<i32>,
+    /// Tail length of a comet; defaults to a quarter of the part.
+    pub tail: Option<i32>,
+    /// Share of LEDs lit in a sparkle; defaults to 0.1.
+    pub density: Option<f64>,
+    pub seed: Option<i32>,
+    /// Place the pattern on the timeline from this time, for the whole duration;
+    /// only the effects are created if not given.
+    pub start: Option<i32>,
+    /// Dancers to place the pattern on; defaults to every dancer of the model.
+    pub dancer_ids: Option<Vec<i32>>,
+}
+
+#[derive(SimpleObject, Default)]
+pub struct GenerateLEDEffectResponse {
+    ok: bool,
+    msg: String,
+    /// The created effects, one for every distinct frame of a cycle.
+    effects: Vec<LEDEffectData>,
+    /// The frames of a cycle.
+    frames: Vec<LEDEffectFrame>,
+    /// Id of the effect showing each frame of `frames`.
+    frame_effect_ids: Vec<i32>,
+    /// Number of times the cycle repeats after the first to fill the duration.
+    repeat: i32,
+    /// Control frames created to place the pattern on the timeline.
+    control_frame_ids: Vec<i32>,
+    /// Existing control frames the pattern was placed on.
+    updated_control_frame_ids: Vec<i32>,
+}
+
+#[derive(Default)]
+pub struct LEDPatternMutation;
+
+#[Object]
+impl LEDPatternMutation {
+    // Generate the frames of an animated LED pattern as effects, optionally placed on the timeline
+    #[graphql(name = "generateLEDEffect")]
+    async fn generate_led_effect(
+        &self,
+        ctx: &Context<'_>,
+        input: GenerateLEDEffectInput,
+    ) -> GQLResult<GenerateLEDEffectResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+
+        let mysql = clients.mysql_pool();
+        let redis = clients.redis_client();
+
+        tracing::info!("Mutation: generateLEDEffect");
+
+        let interval = input.interval.unwrap_or(50);
+        if input.speed <= 0.0 {
+            return Err(GQLError::new("Speed must be positive"));
+        }
+        if input.duration <= 0 || interval <= 0 {
+            return Err(GQLError::new("Duration and interval must be positive"));
+        }
+
+        let part = sqlx::query!(
+            r#"
+                SELECT
+                    Model.id AS "model_id",
+                    Part.id AS "part_id",
+                    Part.length
+                FROM Part
+                INNER JOIN Model ON Part.model_id = Model.id
+                WHERE Part.name = ? AND Model.name = ? AND Part.type = 'LED';
+            "#,
+            &input.part_name,
+            &input.model_name,
+        )
+        .fetch_optional(mysql)
+        .await?
+        .ok_or(GQLError::new("No corresponding LED part."))?;
+
+        let length = part.length.unwrap_or(0).max(0) as usize;
+        if length == 0 {
+            return Err(GQLError::new("LED part has no LEDs"));
+        }
+
+        // sparkles never repeat, so a cycle lasts the whole duration
+        let cycle = match input.pattern {
+            LEDPattern::Sparkle => input.duration,
+            _ => ((1000.0 / input.speed).round() as i32).clamp(1, input.duration),
+        };
+        let frame_count = (cycle / interval).max(1);
+        if frame_count > MAX_CYCLE_FRAMES {
+            return Err(GQLError::new(format!(
+                "Cannot generate more than {MAX_CYCLE_FRAMES} frames in a cycle"
+            )));
+        }
+        let repeat = (input.duration + cycle - 1) / cycle - 1;
+
+        let existing = sqlx::query!(
+            r#"
+                SELECT name FROM LEDEffect
+                WHERE model_id = ? AND part_id = ?;
+            "#,
+            part.model_id,
+            part.part_id
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        let known_colors = sqlx::query!(
+            r#"
+                SELECT id FROM Color;
+            "#
+        )
+        .fetch_all(mysql)
+        .await?;
+        if let Some(id) = input
+            .color_ids
+            .iter()
+            .find(|id| !known_colors.iter().any(|color| color.id == **id))
+        {
+            return Err(GQLError::new(format!("Color Id {id} not found")));
+        }
+
+        // dancers to place the pattern on, with the control frames it lands on
+        let placement = match input.start {
+            Some(start) => {
+                if start < 0 {
+                    return Err(GQLError::new("Negative start is not legal"));
+                }
+
+                let model_dancers = sqlx::query!(
+                    r#"
+                        SELECT id FROM Dancer
+                        WHERE model_id = ?;
+                    "#,
+                    part.model_id
+                )
+                .fetch_all(mysql)
+                .await?
+                .into_iter()
+                .map(|dancer| dancer.id)
+                .collect_vec();
+
+                let dancer_ids = match &input.dancer_ids {
+                    Some(dancer_ids) => {
+                        if let Some(id) = dancer_ids.iter().find(|id| !model_dancers.contains(id)) {
+                            return Err(GQLError::new(format!(
+                                "Dancer #{id} is not a {}",
+                                input.model_name
+                            )));
+                        }
+                        dancer_ids.clone()
+                    }
+                    None => model_dancers,
+                };
+
+                let frame_ids: HashMap<i32, i32> = sqlx::query!(
+                    r#"
+                        SELECT id, start FROM ControlFrame;
+                    "#
+                )
+                .fetch_all(mysql)
+                .await?
+                .into_iter()
+                .map(|frame| (frame.start, frame.id))
+                .collect();
+
+                Some((start, dancer_ids, frame_ids))
+            }
+            None => None,
+        };
+
+        let mut tx = mysql.begin().await?;
+
+        // create the colors the pattern needs
+        let mut created_colors = Vec::new();
+        let mut colors = input.color_ids.clone();
+        if colors.is_empty() {
+            let codes = match input.pattern {
+                LEDPattern::Rainbow => {
+                    let hues = input.hues.unwrap_or(12).max(1);
+                    (0..hues)
+                        .map(|hue| hsv_to_rgb(hue as f64 / hues as f64, 1.0, 1.0))
+                        .collect_vec()
+                }
+                _ => vec![[255, 255, 255]],
+            };
+
+            for code in codes {
+                let (id, created) = get_or_create_color(&mut tx, code).await?;
+                if created {
+                    created_colors.push((id, code));
+                }
+                colors.push(id);
+            }
+        }
+
+        let spacing = input.spacing.unwrap_or(3).max(1) as usize;
+        let tail = input
+            .tail
+            .map_or(length / 4, |tail| tail.max(0) as usize)
+            .max(1);
+        let density = input.density.unwrap_or(0.1).clamp(0.0, 1.0);
+        let mut seed = u64::from(input.seed.unwrap_or(1) as u32).max(1);
+
+        let frames = (0..frame_count)
+            .map(|index| {
+                let phase = index as f64 / frame_count as f64;
+                let mut leds = match input.pattern {
+                    LEDPattern::Rainbow => led::rainbow(&colors, length, phase),
+                    LEDPattern::Chase => led::chase(&colors, length, spacing, phase),
+                    LEDPattern::Breathe => led::breathe(colors[0], length, phase),
+                    LEDPattern::Sparkle => led::sparkle(&colors, length, density, &mut seed),
+                    LEDPattern::Comet => led::comet(colors[0], length, tail, phase),
+                };
+                if input.direction.unwrap_or_default() == LEDDirection::Backward {
+                    leds.reverse();
+                }
+
+                LEDEffectFrame {
+                    leds,
+                    fade: input.pattern == LEDPattern::Breathe,
+                    start: index * cycle / frame_count,
+                }
+            })
+            .collect_vec();
+
+        // identical frames share an effect
+        let mut distinct: Vec<Vec<[i32; 2]>> = Vec::new();
+        let frame_effects = frames
+            .iter()
+            .map(
+                |frame| match distinct.iter().position(|leds| *leds == frame.leds) {
+                    Some(index) => index,
+                    None => {
+                        distinct.push(frame.leds.clone());
+                        distinct.len() - 1
+                    }
+                },
+            )
+            .collect_vec();
+
+        let width = (distinct.len() - 1).to_string().len();
+        let names = (0..distinct.len())
+            .map(|index| format!("{}_{:0width$}", input.name, index))
+            .collect_vec();
+        if let Some(effect) = existing.iter().find(|effect| names.contains(&effect.name)) {
+            return Err(GQLError::new(format!("effectName {} exists.", effect.name)));
+        }
+
+        let mut effects = Vec::new();
+        for (name, leds) in names.iter().zip(&distinct) {
+            let effect_id =
+                led::insert_led_effect(&mut tx, name, part.model_id, part.part_id, leds).await?;
+
+            effects.push(LEDEffectData {
+                id: effect_id,
+                name: name.clone(),
+                model_name: input.model_name.clone(),
+                part_name: input.part_name.clone(),
+                repeat: 0,
+                frames: vec![LEDEffectFrame {
+                    leds: leds.clone(),
+                    fade: false,
+                    start: 0,
+                }],
+            });
+        }
+
+        let frame_effect_ids = frame_effects
+            .iter()
+            .map(|index| effects[*index].id)
+            .collect_vec();
+
+        // place every frame of every cycle on the timeline
+        let mut create_frame_ids = Vec::new();
+        let mut update_frame_ids = Vec::new();
+        if let Some((start, dancer_ids, frame_ids)) = &placement {
+            let placed = (0..=repeat)
+                .flat_map(|cycle_index| {
+                    frames
+                        .iter()
+                        .zip(&frame_effect_ids)
+                        .map(move |(frame, effect_id)| {
+                            (
+                                start + cycle_index * cycle + frame.start,
+                                frame.fade,
+                                *effect_id,
+                            )
+                        })
+                })
+                .filter(|(time, _, _)| *time < start + input.duration)
+                .collect_vec();
+
+            if placed.len() > MAX_PLACED_FRAMES {
+                return Err(GQLError::new(format!(
+                    "Cannot place more than {MAX_PLACED_FRAMES} frames"
+                )));
+            }
+
+            let existing_ids = placed
+                .iter()
+                .filter_map(|(time, _, _)| frame_ids.get(time).copied())
+                .collect_vec();
+            check_editing_control_frames(mysql, context.user_id, &existing_ids).await?;
+
+            let all_parts = sqlx::query!(
+                r#"
+                    SELECT
+                        Dancer.id AS "dancer_id",
+                        Part.id AS "part_id",
+                        Part.type AS "part_type: PartType",
+                        Part.length
+                    FROM Dancer
+                    INNER JOIN Part ON Part.model_id = Dancer.model_id;
+                "#
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+
+            // a frame placed before every other one has no status to keep
+            let mut first_start = frame_ids.keys().min().copied();
+
+            for (time, fade, effect_id) in &placed {
+                match frame_ids.get(time) {
+                    Some(frame_id) => {
+                        for dancer_id in dancer_ids {
+                            sqlx::query!(
+                                r#"
+                                    DELETE LEDBulb FROM LEDBulb
+                                    INNER JOIN ControlData ON LEDBulb.control_id = ControlData.id
+                                    WHERE ControlData.frame_id = ?
+                                        AND ControlData.dancer_id = ?
+                                        AND ControlData.part_id = ?;
+                                "#,
+                                frame_id,
+                                dancer_id,
+                                part.part_id
+                            )
+                            .execute(&mut *tx)
+                            .await?;
+
+                            sqlx::query!(
+                                r#"
+                                    UPDATE ControlData
+                                    SET type = 'EFFECT', effect_id = ?, color_id = NULL, gradient_id = NULL, fade = ?
+                                    WHERE frame_id = ? AND dancer_id = ? AND part_id = ?;
+                                "#,
+                                effect_id,
+                                fade,
+                                frame_id,
+                                dancer_id,
+                                part.part_id
+                            )
+                            .execute(&mut *tx)
+                            .await?;
+                        }
+
+                        sqlx::query!(
+                            r#"
+                                UPDATE ControlFrame
+                                SET data_rev = data_rev + 1
+                                WHERE id = ?;
+                            "#,
+                            frame_id
+                        )
+                        .execute(&mut *tx)
+                        .await?;
+
+                        update_frame_ids.push(*frame_id);
+                    }
+                    None => {
+                        let frame_id = sqlx::query!(
+                            r#"
+                                INSERT INTO ControlFrame (start, fade_for_new_status)
+                                VALUES (?, ?);
+                            "#,
+                            time,
+                            fade
+                        )
+                        .execute(&mut *tx)
+                        .await?
+                        .last_insert_id() as i32;
+
+                        let is_first = first_start.map_or(true, |first| *time < first);
+                        let off_color = if is_first {
+                            first_start = Some(*time);
+
+                            let code = [0, 0, 0];
+                            let (id, created) = get_or_create_color(&mut tx, code).await?;
+                            if created {
+                                created_colors.push((id, code));
+                            }
+                            Some(id)
+                        } else {
+                            None
+                        };
+
+                        // the other dancers and parts keep their status, or are off on the first frame
+                        for data in &all_parts {
+                            let on_part = data.part_id == part.part_id
+                                && dancer_ids.contains(&data.dancer_id);
+
+                            let r#type = match (on_part, off_color, data.part_type) {
+                                (true, _, _) => "EFFECT",
+                                (false, Some(_), PartType::FIBER) => "COLOR",
+                                (false, Some(_), PartType::LED) => "LED_BULBS",
+                                (false, None, _) => "NO_EFFECT",
+                            };
+                            let alpha = if off_color.is_some() && !on_part {
+                                0
+                            } else {
+                                255
+                            };
+
+                            let control_id = sqlx::query!(
+                                r#"
+                                    INSERT INTO ControlData
+                                    (dancer_id, part_id, frame_id, type, fade, effect_id, color_id, alpha)
+                                    VALUES (?, ?, ?, ?, ?, ?, ?, ?);
+                                "#,
+                                data.dancer_id,
+                                data.part_id,
+                                frame_id,
+                                r#type,
+                                on_part && *fade,
+                                on_part.then_some(*effect_id),
+                                off_color.filter(|_| r#type == "COLOR"),
+                                alpha
+                            )
+                            .execute(&mut *tx)
+                            .await?
+                            .last_insert_id() as i32;
+
+                            if let (Some(color_id), "LED_BULBS") = (off_color, r#type) {
+                                for position in 0..data.length.unwrap_or(0) {
+                                    sqlx::query!(
+                                        r#"
+                                            INSERT INTO LEDBulb (control_id, position, color_id, alpha)
+                                            VALUES (?, ?, ?, ?);
+                                        "#,
+                                        control_id,
+                                        position,
+                                        color_id,
+                                        0
+                                    )
+                                    .execute(&mut *tx)
+                                    .await?;
+                                }
+                            }
+                        }
+
+                        create_frame_ids.push(frame_id);
+                    }
+                }
+            }
+        }
+
+        tx.commit().await?;
+
+        for (id, code) in created_colors {
+            let color_payload = ColorPayload {
+                mutation: ColorMutationMode::Created,
+                id,
+                color: Some(rgb_to_hex(code)),
+                color_code: Some(code.to_vec()),
+                edit_by: context.user_id,
+            };
+            Subscriptor::publish(color_payload);
+        }
+
+        let led_payload = LEDPayload {
+            create_effects: effects.clone(),
+            update_effects: Vec::new(),
+            delete_effects: Vec::new(),
+        };
+        Subscriptor::publish(led_payload);
+
+        if placement.is_some() {
+            let create_frames = update_redis_controls(mysql, redis, &create_frame_ids)
+                .await?
+                .into_iter()
+                .map(|(id, redis_control)| (id, RedisControlMandatory::from(redis_control)))
+                .collect();
+            let update_frames = update_redis_controls(mysql, redis, &update_frame_ids)
+                .await?
+                .into_iter()
+                .map(|(id, redis_control)| (id, RedisControlMandatory::from(redis_control)))
+                .collect();
+
+            let control_map_payload = ControlMapPayload {
+                edit_by: context.user_id,
+                frame: ControlFramesSubDatScalar(ControlFramesSubData {
+                    create_frames,
+                    delete_frames: Vec::new(),
+                    update_frames,
+                }),
+            };
+            Subscriptor::publish(control_map_payload);
+        }
+
+        update_revision(mysql).await?;
+
+        Ok(GenerateLEDEffectResponse {
+            ok: true,
+            msg: format!(
+                "Generated {} effects on {} frames",
+                effects.len(),
+                create_frame_ids.len() + update_frame_ids.len()
+            ),
+            effects,
+            frames,
+            frame_effect_ids,
+            repeat,
+            control_frame_ids: create_frame_ids,
+            updated_control_frame_ids: update_frame_ids,
+        })
+    }
+}
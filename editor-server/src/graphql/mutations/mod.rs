@@ -1,26 +1,42 @@
 //! Mutations for the GraphQL API.
 
+pub mod alpha;
 pub mod color;
 pub mod control_frame;
 pub mod control_map;
+pub mod copy_part;
 pub mod dancer;
+pub mod formation;
+pub mod gradient;
 pub mod led;
+pub mod led_pattern;
 pub mod model;
+pub mod motion;
+pub mod palette;
 pub mod part;
 pub mod position_frame;
 pub mod position_map;
+pub mod recolor;
 pub mod request_edit;
 pub mod shift;
 
+use alpha::*;
 use color::*;
 use control_frame::*;
 use control_map::*;
+use copy_part::*;
 use dancer::*;
+use formation::*;
+use gradient::*;
 use led::*;
+use led_pattern::*;
 use model::*;
+use motion::*;
+use palette::*;
 use part::*;
 use position_frame::*;
 use position_map::*;
+use recolor::*;
 use request_edit::*;
 use shift::*;
 
@@ -37,4 +53,12 @@ pub struct MutationRoot(
     PartMutation,
     FrameMutation,
     ModelMutation,
+    CopyPartMutation,
+    RecolorMutation,
+    AlphaMutation,
+    MotionMutation,
+    FormationMutation,
+    LEDPatternMutation,
+    GradientMutation,
+    PaletteMutation,
 );
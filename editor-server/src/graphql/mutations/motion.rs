@@ -0,0 +1,391 @@
+//! Motion generator mutation methods.
+
+use crate::graphql::subscriptions::position_map::PositionMapPayload;
+use crate::graphql::subscriptor::Subscriptor;
+use crate::graphql::types::pos_data::{FrameData, PosDataScalar};
+use crate::types::global::UserContext;
+use crate::utils::data::{check_editing_position_frames, update_redis_positions};
+use crate::utils::motion::{self, Pose};
+use crate::utils::revision::update_revision;
+
+use async_graphql::{
+    Context, Enum, Error as GQLError, InputObject, Object, Result as GQLResult, SimpleObject,
+};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// Upper bound on the number of frames generated at once.
+const MAX_GENERATED_FRAMES: i32 = 10000;
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Default, Debug)]
+pub enum MotionKind {
+    /// Spin in place about the axis at `rpm`.
+    #[default]
+    Rotate,
+    /// Circle around `center` with `radius` at `rpm`, starting at angle `phase`.
+    Orbit,
+    /// Move in a straight line from `from` to `to`.
+    Linear,
+    /// Move from `from` to `to` along a parabola peaking `height` above the line.
+    Arc,
+}
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Default, Debug)]
+pub enum MotionAxis {
+    X,
+    Y,
+    #[default]
+    Z,
+}
+
+impl From<MotionAxis> for usize {
+    fn from(axis: MotionAxis) -> Self {
+        match axis {
+            MotionAxis::X => 0,
+            MotionAxis::Y => 1,
+            MotionAxis::Z => 2,
+        }
+    }
+}
+
+#[derive(InputObject, Default)]
+pub struct GenerateMotionInput {
+    pub dancer_ids: Vec<i32>,
+    pub start: i32,
+    pub end: i32,
+    /// Time between generated frames.
+    pub interval: i32,
+    pub kind: MotionKind,
+    /// Rotation axis, or the upward axis of orbits and arcs; defaults to Z.
+    pub axis: Option<MotionAxis>,
+    pub rpm: Option<f64>,
+    pub center: Option<[f64; 3]>,
+    pub radius: Option<f64>,
+    pub phase: Option<f64>,
+    /// Defaults to the location of the dancer at start.
+    pub from: Option<[f64; 3]>,
+    pub to: Option<[f64; 3]>,
+    pub height: Option<f64>,
+}
+
+#[derive(SimpleObject, Default)]
+pub struct GenerateMotionResponse {
+    ok: bool,
+    msg: String,
+    frame_ids: Vec<i32>,
+}
+
+#[derive(Default)]
+pub struct MotionMutation;
+
+#[Object]
+impl MotionMutation {
+    // Write position frames for a motion primitive over a time range
+    async fn generate_motion(
+        &self,
+        ctx: &Context<'_>,
+        input: GenerateMotionInput,
+    ) -> GQLResult<GenerateMotionResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+
+        let mysql = clients.mysql_pool();
+        let redis = clients.redis_client();
+
+        tracing::info!("Mutation: generateMotion");
+
+        let GenerateMotionInput {
+            dancer_ids,
+            start,
+            end,
+            interval,
+            kind,
+            axis,
+            rpm,
+            center,
+            radius,
+            phase,
+            from,
+            to,
+            height,
+        } = input;
+
+        if start < 0 {
+            return Err(GQLError::new("Negative start is not legal"));
+        }
+        if start >= end {
+            return Err(GQLError::new("Start must smaller than end"));
+        }
+        if interval <= 0 {
+            return Err(GQLError::new("Interval must be positive"));
+        }
+        if (end - start) / interval >= MAX_GENERATED_FRAMES {
+            return Err(GQLError::new(format!(
+                "Cannot generate more than {MAX_GENERATED_FRAMES} frames"
+            )));
+        }
+
+        let axis = usize::from(axis.unwrap_or_default());
+
+        // check the parameters required by the motion
+        match kind {
+            MotionKind::Rotate if rpm.is_none() => {
+                return Err(GQLError::new("Rotate requires rpm"));
+            }
+            MotionKind::Orbit if rpm.is_none() || center.is_none() || radius.is_none() => {
+                return Err(GQLError::new("Orbit requires rpm, center and radius"));
+            }
+            MotionKind::Linear if to.is_none() => {
+                return Err(GQLError::new("Linear requires to"));
+            }
+            MotionKind::Arc if to.is_none() || height.is_none() => {
+                return Err(GQLError::new("Arc requires to and height"));
+            }
+            _ => {}
+        }
+
+        let dancers = sqlx::query!(
+            r#"
+                SELECT id FROM Dancer
+                ORDER BY id ASC;
+            "#,
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        if let Some(dancer_id) = dancer_ids
+            .iter()
+            .find(|id| !dancers.iter().any(|dancer| dancer.id == **id))
+        {
+            return Err(GQLError::new(format!("Dancer #{dancer_id} not found")));
+        }
+
+        // the pose of every dancer at start
+        let mut bases = HashMap::new();
+        for dancer_id in &dancer_ids {
+            let base = sqlx::query!(
+                r#"
+                    SELECT
+                        PositionData.x,
+                        PositionData.y,
+                        PositionData.z,
+                        PositionData.rx,
+                        PositionData.ry,
+                        PositionData.rz,
+                        PositionData.qw,
+                        PositionData.qx,
+                        PositionData.qy,
+                        PositionData.qz
+                    FROM PositionData
+                    INNER JOIN PositionFrame ON PositionData.frame_id = PositionFrame.id
+                    WHERE PositionData.dancer_id = ?
+                        AND PositionData.type = 'POSITION'
+                        AND PositionFrame.start <= ?
+                    ORDER BY PositionFrame.start DESC
+                    LIMIT 1;
+                "#,
+                dancer_id,
+                start
+            )
+            .fetch_optional(mysql)
+            .await?
+            .map(|data| Pose {
+                location: [
+                    data.x.unwrap_or(0.0),
+                    data.y.unwrap_or(0.0),
+                    data.z.unwrap_or(0.0),
+                ],
+                rotation: [data.rx, data.ry, data.rz],
+                quaternion: match (data.qw, data.qx, data.qy, data.qz) {
+                    (Some(w), Some(x), Some(y), Some(z)) => Some([w, x, y, z]),
+                    _ => None,
+                },
+            })
+            .unwrap_or_default();
+
+            bases.insert(*dancer_id, base);
+        }
+
+        let times = (start..=end)
+            .step_by(interval as usize)
+            .chain(std::iter::once(end))
+            .unique()
+            .collect_vec();
+
+        let existing_frames = sqlx::query!(
+            r#"
+                SELECT id, start FROM PositionFrame
+                WHERE start >= ?
+                AND start <= ?;
+            "#,
+            start,
+            end
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .filter(|frame| times.contains(&frame.start))
+        .collect_vec();
+
+        // check editing
+        let existing_ids = existing_frames.iter().map(|frame| frame.id).collect_vec();
+        check_editing_position_frames(mysql, context.user_id, &existing_ids).await?;
+
+        let mut tx = mysql.begin().await?;
+
+        let mut create_ids = Vec::new();
+        let mut update_ids = Vec::new();
+
+        for time in &times {
+            let existing = existing_frames.iter().find(|frame| frame.start == *time);
+
+            let frame_id = match existing {
+                Some(frame) => {
+                    sqlx::query!(
+                        r#"
+                            UPDATE PositionFrame
+                            SET data_rev = data_rev + 1
+                            WHERE id = ?;
+                        "#,
+                        frame.id
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+
+                    update_ids.push(frame.id);
+                    frame.id
+                }
+                None => {
+                    let frame_id = sqlx::query!(
+                        r#"
+                            INSERT INTO PositionFrame (start)
+                            VALUES (?);
+                        "#,
+                        time
+                    )
+                    .execute(&mut *tx)
+                    .await?
+                    .last_insert_id() as i32;
+
+                    // the other dancers keep their position
+                    for dancer in &dancers {
+                        if dancer_ids.contains(&dancer.id) {
+                            continue;
+                        }
+
+                        sqlx::query!(
+                            r#"
+                                INSERT INTO PositionData (dancer_id, frame_id, type, x, y, z, rx, ry, rz)
+                                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);
+                            "#,
+                            dancer.id,
+                            frame_id,
+                            "NO_EFFECT",
+                            0.0,
+                            0.0,
+                            0.0,
+                            0.0,
+                            0.0,
+                            0.0,
+                        )
+                        .execute(&mut *tx)
+                        .await?;
+                    }
+
+                    create_ids.push(frame_id);
+                    frame_id
+                }
+            };
+
+            let elapsed = time - start;
+            let progress = elapsed as f64 / (end - start) as f64;
+
+            for dancer_id in &dancer_ids {
+                let base = bases[dancer_id];
+                let pose = match kind {
+                    MotionKind::Rotate => motion::rotate(base, axis, rpm.unwrap_or(0.0), elapsed),
+                    MotionKind::Orbit => motion::orbit(
+                        base,
+                        center.unwrap_or_default(),
+                        radius.unwrap_or(0.0),
+                        axis,
+                        rpm.unwrap_or(0.0),
+                        phase.unwrap_or(0.0),
+                        elapsed,
+                    ),
+                    MotionKind::Linear => motion::linear(
+                        base,
+                        from.unwrap_or(base.location),
+                        to.unwrap_or(base.location),
+                        progress,
+                    ),
+                    MotionKind::Arc => motion::arc(
+                        base,
+                        from.unwrap_or(base.location),
+                        to.unwrap_or(base.location),
+                        height.unwrap_or(0.0),
+                        axis,
+                        progress,
+                    ),
+                };
+
+                sqlx::query!(
+                    r#"
+                        DELETE FROM PositionData
+                        WHERE dancer_id = ? AND frame_id = ?;
+                    "#,
+                    dancer_id,
+                    frame_id
+                )
+                .execute(&mut *tx)
+                .await?;
+
+                sqlx::query!(
+                    r#"
+                        INSERT INTO PositionData (dancer_id, frame_id, type, x, y, z, rx, ry, rz, qw, qx, qy, qz)
+                        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);
+                    "#,
+                    dancer_id,
+                    frame_id,
+                    "POSITION",
+                    pose.location[0],
+                    pose.location[1],
+                    pose.location[2],
+                    pose.rotation[0],
+                    pose.rotation[1],
+                    pose.rotation[2],
+                    pose.quaternion.map(|q| q[0]),
+                    pose.quaternion.map(|q| q[1]),
+                    pose.quaternion.map(|q| q[2]),
+                    pose.quaternion.map(|q| q[3]),
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+
+        // update redis and publish the position map
+        let create_frames = update_redis_positions(mysql, redis, &create_ids).await?;
+        let update_frames = update_redis_positions(mysql, redis, &update_ids).await?;
+
+        let position_map_payload = PositionMapPayload {
+            edit_by: context.user_id,
+            frame: PosDataScalar(FrameData {
+                create_frames,
+                delete_frames: Vec::new(),
+                update_frames,
+            }),
+        };
+        Subscriptor::publish(position_map_payload);
+
+        update_revision(mysql).await?;
+
+        Ok(GenerateMotionResponse {
+            ok: true,
+            msg: format!("Generated {} frames", times.len()),
+            frame_ids: create_ids.into_iter().chain(update_ids).collect_vec(),
+        })
+    }
+}
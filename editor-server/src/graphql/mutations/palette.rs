@@ -0,0 +1,696 @@
+//! Palette mutation methods.
+
+use crate::graphql::subscriptions::control_map::ControlMapPayload;
+use crate::graphql::subscriptions::led::LEDPayload;
+use crate::graphql::subscriptions::palette::{PaletteMutationMode, PalettePayload};
+use crate::graphql::subscriptor::Subscriptor;
+use crate::graphql::types::control_data::{
+    ControlFramesSubDatScalar, ControlFramesSubData, RedisControlMandatory,
+};
+use crate::graphql::types::palette::{Palette, PaletteColorInput};
+use crate::graphql::types::scope::ControlScopeInput;
+use crate::types::global::{PartType, UserContext};
+use crate::utils::data::{check_editing_control_frames, update_redis_controls};
+use crate::utils::led::{get_led_effect_data, recolor_led_effect};
+use crate::utils::palette::{get_palette, role_mapping};
+use crate::utils::revision::update_revision;
+
+use async_graphql::{
+    Context, Error as GQLError, InputObject, Object, Result as GQLResult, SimpleObject,
+};
+use itertools::Itertools;
+use sqlx::{MySql, Pool, Transaction};
+use std::collections::{HashMap, HashSet};
+
+#[derive(InputObject, Default)]
+pub struct AddPaletteInput {
+    pub name: String,
+    /// Colors in order, with distinct roles.
+    pub colors: Vec<PaletteColorInput>,
+}
+
+#[derive(InputObject, Default)]
+pub struct EditPaletteInput {
+    pub id: i32,
+    pub name: Option<String>,
+    pub colors: Option<Vec<PaletteColorInput>>,
+}
+
+#[derive(InputObject, Default)]
+pub struct AssignPaletteInput {
+    pub palette_id: i32,
+    pub start: i32,
+    pub end: i32,
+}
+
+#[derive(InputObject, Default)]
+pub struct SwapPaletteInput {
+    pub from_palette_id: i32,
+    pub to_palette_id: i32,
+    /// Sections of the first palette within the time range of the scope are
+    /// assigned the second one.
+    pub scope: ControlScopeInput,
+    /// Also remap the LED effects of the parts in scope. Effects also used by control data out
+    /// of scope are copied, and the control data in scope use the remapped copy.
+    pub include_led_effects: Option<bool>,
+}
+
+#[derive(SimpleObject, Default)]
+pub struct PaletteResponse {
+    ok: bool,
+    msg: String,
+}
+
+#[derive(SimpleObject, Default)]
+pub struct SwapPaletteResponse {
+    ok: bool,
+    msg: String,
+    control_count: i32,
+    bulb_count: i32,
+    effect_state_count: i32,
+    section_count: i32,
+}
+
+/// Check the roles are distinct and the colors exist.
+async fn check_colors(mysql: &Pool<MySql>, colors: &[PaletteColorInput]) -> GQLResult<()> {
+    if let Some(role) = colors.iter().map(|color| &color.role).duplicates().next() {
+        return Err(GQLError::new(format!("Role {role} is used more than once")));
+    }
+
+    for color in colors {
+        let existing = sqlx::query!(
+            r#"
+                SELECT id FROM Color
+                WHERE id = ?;
+            "#,
+            color.color_id
+        )
+        .fetch_optional(mysql)
+        .await?;
+
+        if existing.is_none() {
+            return Err(GQLError::new(format!(
+                "Color #{} not found",
+                color.color_id
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+async fn insert_colors(
+    tx: &mut Transaction<'static, MySql>,
+    palette_id: i32,
+    colors: &[PaletteColorInput],
+) -> GQLResult<()> {
+    for (position, color) in colors.iter().enumerate() {
+        sqlx::query!(
+            r#"
+                INSERT INTO PaletteColor (palette_id, role, position, color_id)
+                VALUES (?, ?, ?, ?);
+            "#,
+            palette_id,
+            color.role,
+            position as i32,
+            color.color_id
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn check_name(mysql: &Pool<MySql>, name: &str, id: i32) -> GQLResult<()> {
+    let existing = sqlx::query!(
+        r#"
+            SELECT id FROM Palette
+            WHERE name = ? AND id != ?;
+        "#,
+        name,
+        id
+    )
+    .fetch_optional(mysql)
+    .await?;
+
+    match existing {
+        Some(_) => Err(GQLError::new(format!("Palette {name} already exists"))),
+        None => Ok(()),
+    }
+}
+
+fn publish_palette(mutation: PaletteMutationMode, id: i32, palette: Option<Palette>, edit_by: i32) {
+    let palette_payload = PalettePayload {
+        mutation,
+        id,
+        palette,
+        edit_by,
+    };
+    Subscriptor::publish(palette_payload);
+}
+
+#[derive(Default)]
+pub struct PaletteMutation;
+
+#[Object]
+impl PaletteMutation {
+    async fn add_palette(&self, ctx: &Context<'_>, input: AddPaletteInput) -> GQLResult<Palette> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Mutation: addPalette");
+
+        check_name(mysql, &input.name, -1).await?;
+        check_colors(mysql, &input.colors).await?;
+
+        let mut tx = mysql.begin().await?;
+
+        let id = sqlx::query!(
+            r#"
+                INSERT INTO Palette (name)
+                VALUES (?);
+            "#,
+            input.name
+        )
+        .execute(&mut *tx)
+        .await?
+        .last_insert_id() as i32;
+
+        insert_colors(&mut tx, id, &input.colors).await?;
+
+        tx.commit().await?;
+
+        let palette = get_palette(mysql, id).await?;
+        publish_palette(
+            PaletteMutationMode::Created,
+            id,
+            Some(palette.clone()),
+            context.user_id,
+        );
+
+        update_revision(mysql).await?;
+
+        Ok(palette)
+    }
+
+    async fn edit_palette(&self, ctx: &Context<'_>, input: EditPaletteInput) -> GQLResult<Palette> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Mutation: editPalette");
+
+        let palette = get_palette(mysql, input.id).await?;
+
+        let name = input.name.unwrap_or(palette.name);
+        check_name(mysql, &name, input.id).await?;
+        if let Some(colors) = &input.colors {
+            check_colors(mysql, colors).await?;
+        }
+
+        let mut tx = mysql.begin().await?;
+
+        sqlx::query!(
+            r#"
+                UPDATE Palette SET name = ?
+                WHERE id = ?;
+            "#,
+            name,
+            input.id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if let Some(colors) = &input.colors {
+            sqlx::query!(
+                r#"
+                    DELETE FROM PaletteColor
+                    WHERE palette_id = ?;
+                "#,
+                input.id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            insert_colors(&mut tx, input.id, colors).await?;
+        }
+
+        tx.commit().await?;
+
+        let palette = get_palette(mysql, input.id).await?;
+        publish_palette(
+            PaletteMutationMode::Updated,
+            input.id,
+            Some(palette.clone()),
+            context.user_id,
+        );
+
+        update_revision(mysql).await?;
+
+        Ok(palette)
+    }
+
+    // Delete a palette; the colors it uses are kept
+    async fn delete_palette(&self, ctx: &Context<'_>, id: i32) -> GQLResult<PaletteResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Mutation: deletePalette");
+
+        let affected = sqlx::query!(
+            r#"
+                DELETE FROM Palette
+                WHERE id = ?;
+            "#,
+            id
+        )
+        .execute(mysql)
+        .await?
+        .rows_affected();
+
+        if affected == 0 {
+            return Err(GQLError::new(format!("Palette #{id} not found")));
+        }
+
+        publish_palette(PaletteMutationMode::Deleted, id, None, context.user_id);
+
+        update_revision(mysql).await?;
+
+        Ok(PaletteResponse {
+            ok: true,
+            msg: "Palette deleted".to_string(),
+        })
+    }
+
+    // Assign a palette to the section of the show in [start, end]
+    async fn assign_palette(
+        &self,
+        ctx: &Context<'_>,
+        input: AssignPaletteInput,
+    ) -> GQLResult<Palette> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Mutation: assignPalette");
+
+        if input.start > input.end {
+            return Err(GQLError::new("Start must not be larger than end"));
+        }
+
+        get_palette(mysql, input.palette_id).await?;
+
+        let overlapping = sqlx::query!(
+            r#"
+                SELECT id, palette_id FROM PaletteSection
+                WHERE start <= ? AND end >= ?
+                LIMIT 1;
+            "#,
+            input.end,
+            input.start
+        )
+        .fetch_optional(mysql)
+        .await?;
+
+        if let Some(section) = overlapping {
+            return Err(GQLError::new(format!(
+                "Section overlaps section #{} of palette #{}",
+                section.id, section.palette_id
+            )));
+        }
+
+        sqlx::query!(
+            r#"
+                INSERT INTO PaletteSection (palette_id, start, end)
+                VALUES (?, ?, ?);
+            "#,
+            input.palette_id,
+            input.start,
+            input.end
+        )
+        .execute(mysql)
+        .await?;
+
+        let palette = get_palette(mysql, input.palette_id).await?;
+        publish_palette(
+            PaletteMutationMode::Updated,
+            input.palette_id,
+            Some(palette.clone()),
+            context.user_id,
+        );
+
+        update_revision(mysql).await?;
+
+        Ok(palette)
+    }
+
+    async fn unassign_palette(&self, ctx: &Context<'_>, section_id: i32) -> GQLResult<Palette> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Mutation: unassignPalette");
+
+        let section = sqlx::query!(
+            r#"
+                SELECT palette_id FROM PaletteSection
+                WHERE id = ?;
+            "#,
+            section_id
+        )
+        .fetch_optional(mysql)
+        .await?
+        .ok_or(format!("Palette section #{section_id} not found"))?;
+
+        sqlx::query!(
+            r#"
+                DELETE FROM PaletteSection
+                WHERE id = ?;
+            "#,
+            section_id
+        )
+        .execute(mysql)
+        .await?;
+
+        let palette = get_palette(mysql, section.palette_id).await?;
+        publish_palette(
+            PaletteMutationMode::Updated,
+            section.palette_id,
+            Some(palette.clone()),
+            context.user_id,
+        );
+
+        update_revision(mysql).await?;
+
+        Ok(palette)
+    }
+
+    // Replace the colors of a palette with the colors of the same roles in
+    // another palette, in the control data of a scope of dancers, parts and frames
+    async fn swap_palette(
+        &self,
+        ctx: &Context<'_>,
+        input: SwapPaletteInput,
+    ) -> GQLResult<SwapPaletteResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+
+        let mysql = clients.mysql_pool();
+        let redis = clients.redis_client();
+
+        tracing::info!("Mutation: swapPalette");
+
+        let SwapPaletteInput {
+            from_palette_id,
+            to_palette_id,
+            scope,
+            include_led_effects,
+        } = input;
+
+        let (start, end) = scope.range()?;
+
+        let from_palette = get_palette(mysql, from_palette_id).await?;
+        let to_palette = get_palette(mysql, to_palette_id).await?;
+        let mapping = role_mapping(&from_palette.colors, &to_palette.colors)?;
+
+        // find the control data in scope
+        let control_data = sqlx::query!(
+            r#"
+                SELECT
+                    ControlData.id,
+                    ControlData.frame_id,
+                    ControlData.dancer_id,
+                    ControlData.type,
+                    ControlData.color_id,
+                    Part.name AS "part_name",
+                    Part.type AS "part_type: PartType"
+                FROM ControlData
+                INNER JOIN ControlFrame ON ControlData.frame_id = ControlFrame.id
+                INNER JOIN Part ON ControlData.part_id = Part.id
+                WHERE ControlFrame.start >= ? AND ControlFrame.start <= ?;
+            "#,
+            start,
+            end
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .filter(|data| scope.contains(data.dancer_id, &data.part_name, data.part_type))
+        .collect_vec();
+
+        let frame_ids = control_data
+            .iter()
+            .map(|data| data.frame_id)
+            .unique()
+            .collect_vec();
+
+        // check editing
+        check_editing_control_frames(mysql, context.user_id, &frame_ids).await?;
+
+        // find the LED effects of the parts in scope
+        let effect_ids = if include_led_effects.unwrap_or(false) {
+            let effect_ids = sqlx::query!(
+                r#"
+                    SELECT DISTINCT
+                        LEDEffect.id,
+                        Dancer.id AS "dancer_id",
+                        Part.name AS "part_name",
+                        Part.type AS "part_type: PartType"
+                    FROM LEDEffect
+                    INNER JOIN Part ON LEDEffect.part_id = Part.id
+                    INNER JOIN Dancer ON Dancer.model_id = LEDEffect.model_id;
+                "#,
+            )
+            .fetch_all(mysql)
+            .await?
+            .into_iter()
+            .filter(|effect| scope.contains(effect.dancer_id, &effect.part_name, effect.part_type))
+            .map(|effect| effect.id)
+            .unique()
+            .collect_vec();
+
+            let editing_effects = sqlx::query!(
+                r#"
+                    SELECT led_effect_id AS "led_effect_id!", user_id
+                    FROM EditingLEDEffect
+                    WHERE led_effect_id IS NOT NULL AND user_id != ?;
+                "#,
+                context.user_id
+            )
+            .fetch_all(mysql)
+            .await?;
+
+            if let Some(editing) = editing_effects
+                .iter()
+                .find(|editing| effect_ids.contains(&editing.led_effect_id))
+            {
+                return Err(GQLError::new(format!(
+                    "LED effect #{} is being edited by user #{}",
+                    editing.led_effect_id, editing.user_id
+                )));
+            }
+
+            effect_ids
+        } else {
+            Vec::new()
+        };
+
+        let mut tx = mysql.begin().await?;
+
+        let mut control_count = 0;
+        let mut bulb_count = 0;
+        let mut effect_state_count = 0;
+        let mut touched_frame_ids = Vec::new();
+
+        // rows are remapped one by one, so colors swapping roles do not chain
+        for data in &control_data {
+            let mut touched = false;
+
+            if data.r#type == "LED_BULBS" {
+                let bulbs = sqlx::query!(
+                    r#"
+                        SELECT id, color_id FROM LEDBulb
+                        WHERE control_id = ?;
+                    "#,
+                    data.id
+                )
+                .fetch_all(&mut *tx)
+                .await?;
+
+                for bulb in bulbs {
+                    if let Some(color_id) = mapping.get(&bulb.color_id) {
+                        sqlx::query!(
+                            r#"
+                                UPDATE LEDBulb
+                                SET color_id = ?
+                                WHERE id = ?;
+                            "#,
+                            color_id,
+                            bulb.id
+                        )
+                        .execute(&mut *tx)
+                        .await?;
+
+                        bulb_count += 1;
+                        touched = true;
+                    }
+                }
+
+                // the bulbs no longer follow the applied gradient
+                if touched {
+                    sqlx::query!(
+                        r#"
+                            UPDATE ControlData
+                            SET gradient_id = NULL
+                            WHERE id = ?;
+                        "#,
+                        data.id
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            } else if let Some(color_id) = data.color_id.and_then(|id| mapping.get(&id)) {
+                sqlx::query!(
+                    r#"
+                        UPDATE ControlData
+                        SET color_id = ?
+                        WHERE id = ?;
+                    "#,
+                    color_id,
+                    data.id
+                )
+                .execute(&mut *tx)
+                .await?;
+
+                control_count += 1;
+                touched = true;
+            }
+
+            if touched && !touched_frame_ids.contains(&data.frame_id) {
+                touched_frame_ids.push(data.frame_id);
+            }
+        }
+
+        // effects also used outside of the scope are copied for the control data in scope
+        let scope_ids: HashSet<i32> = control_data.iter().map(|data| data.id).collect();
+        let mut touched_effect_ids = Vec::new();
+        let mut created_effect_ids = Vec::new();
+
+        for effect_id in &effect_ids {
+            let recolored = recolor_led_effect(
+                &mut tx,
+                *effect_id,
+                &mapping,
+                &scope_ids,
+                &to_palette.name,
+                &mut touched_frame_ids,
+            )
+            .await?;
+
+            if let Some(recolored) = recolored {
+                effect_state_count += recolored.state_count;
+                match recolored.copy_id {
+                    Some(copy_id) => created_effect_ids.push(copy_id),
+                    None => touched_effect_ids.push(*effect_id),
+                }
+            }
+        }
+
+        for frame_id in &touched_frame_ids {
+            sqlx::query!(
+                r#"
+                    UPDATE ControlFrame
+                    SET data_rev = data_rev + 1
+                    WHERE id = ?;
+                "#,
+                frame_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let section_count = sqlx::query!(
+            r#"
+                UPDATE PaletteSection
+                SET palette_id = ?
+                WHERE palette_id = ? AND start >= ? AND end <= ?;
+            "#,
+            to_palette_id,
+            from_palette_id,
+            start,
+            end
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() as i32;
+
+        tx.commit().await?;
+
+        // update redis and publish the control map
+        if !touched_frame_ids.is_empty() {
+            let update_frames = update_redis_controls(mysql, redis, &touched_frame_ids)
+                .await?
+                .into_iter()
+                .map(|(id, redis_control)| (id, RedisControlMandatory::from(redis_control)))
+                .collect();
+
+            let control_map_payload = ControlMapPayload {
+                edit_by: context.user_id,
+                frame: ControlFramesSubDatScalar(ControlFramesSubData {
+                    create_frames: HashMap::new(),
+                    delete_frames: Vec::new(),
+                    update_frames,
+                }),
+            };
+            Subscriptor::publish(control_map_payload);
+        }
+
+        if !touched_effect_ids.is_empty() || !created_effect_ids.is_empty() {
+            let mut create_effects = Vec::new();
+            for effect_id in &created_effect_ids {
+                create_effects.push(get_led_effect_data(mysql, *effect_id).await?);
+            }
+
+            let mut update_effects = Vec::new();
+            for effect_id in &touched_effect_ids {
+                update_effects.push(get_led_effect_data(mysql, *effect_id).await?);
+            }
+
+            let led_payload = LEDPayload {
+                create_effects,
+                update_effects,
+                delete_effects: Vec::new(),
+            };
+            Subscriptor::publish(led_payload);
+        }
+
+        if section_count > 0 {
+            for id in [from_palette_id, to_palette_id] {
+                let palette = get_palette(mysql, id).await?;
+                publish_palette(
+                    PaletteMutationMode::Updated,
+                    id,
+                    Some(palette),
+                    context.user_id,
+                );
+            }
+        }
+
+        update_revision(mysql).await?;
+
+        Ok(SwapPaletteResponse {
+            ok: true,
+            msg: format!(
+                "Remapped {} control data, {} bulbs and {} effect states",
+                control_count, bulb_count, effect_state_count
+            ),
+            control_count,
+            bulb_count,
+            effect_state_count,
+            section_count,
+        })
+    }
+}
@@ -1,11 +1,26 @@
 //! Part mutation methods.
 use crate::db::types::{model::ModelData, part::PartData, position::PositionData};
+use crate::graphql::subscriptions::color::{ColorMutationMode, ColorPayload};
+use crate::graphql::subscriptions::control_map::ControlMapPayload;
+use crate::graphql::subscriptions::led::LEDPayload;
+use crate::graphql::subscriptor::Subscriptor;
+use crate::graphql::types::control_data::{
+    ControlFramesSubDatScalar, ControlFramesSubData, RedisControlMandatory,
+};
 use crate::graphql::types::dancer::Part;
 use crate::types::global::PartType;
 use crate::types::global::UserContext;
-use crate::utils::data::{init_redis_control, init_redis_position};
-
-use async_graphql::{Context, InputObject, Object, Result as GQLResult, SimpleObject};
+use crate::utils::color::{get_or_create_color, rgb_to_hex};
+use crate::utils::data::{
+    check_editing_control_frames, init_redis_control, init_redis_position, update_redis_controls,
+};
+use crate::utils::led::{get_led_effect_data, pad_leds, resample_leds};
+use crate::utils::revision::update_revision;
+use crate::utils::vector::partition_by_field;
+
+use async_graphql::{Context, Enum, InputObject, Object, Result as GQLResult, SimpleObject};
+use itertools::Itertools;
+use std::collections::HashMap;
 
 #[derive(InputObject, Default)]
 pub struct PartUpdateInput {
@@ -23,6 +38,26 @@ pub struct PartCreateInput {
     pub length: Option<i32>,
 }
 
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Default, Debug)]
+pub enum LEDResampleMode {
+    /// Scale the existing bulbs over the new length.
+    #[default]
+    Stretch,
+    /// Keep bulbs in place; truncate, or pad with bulbs turned off.
+    Pad,
+    /// Keep bulbs in place and cut the ones past the new length; the part can't grow.
+    ///
+    /// Pad and Truncate only differ when the part grows, which Truncate refuses.
+    Truncate,
+}
+
+#[derive(InputObject, Default)]
+pub struct PartResizeInput {
+    pub id: i32,
+    pub length: i32,
+    pub mode: LEDResampleMode,
+}
+
 #[derive(InputObject, Default)]
 pub struct PartDeleteInput {
     pub id: i32,
@@ -164,7 +199,11 @@ impl PartMutation {
                     COALESCE(z, 0) AS z,
                     rx,
                     ry,
-                    rz
+                    rz,
+                    qw,
+                    qx,
+                    qy,
+                    qz
                 FROM PositionData
                 WHERE dancer_id = ?
                 ORDER BY frame_id ASC;
@@ -415,4 +454,322 @@ impl PartMutation {
             }),
         })
     }
+
+    // Change the length of an LED part and resample its LED bulbs and LED effects
+    async fn resize_led_part(
+        &self,
+        ctx: &Context<'_>,
+        input: PartResizeInput,
+    ) -> GQLResult<PartResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+
+        let mysql = clients.mysql_pool();
+        let redis = clients.redis_client();
+
+        tracing::info!("Mutation: resizeLEDPart");
+
+        let part = sqlx::query_as!(
+            PartData,
+            r#"
+                SELECT * FROM Part WHERE id = ?;
+            "#,
+            input.id
+        )
+        .fetch_optional(mysql)
+        .await?;
+
+        let part = match part {
+            Some(part) => part,
+            None => {
+                return Ok(PartResponse {
+                    ok: false,
+                    msg: Some("no part found".to_string()),
+                    part_data: None,
+                })
+            }
+        };
+
+        if part.r#type != PartType::LED {
+            return Ok(PartResponse {
+                ok: false,
+                msg: Some("only LED parts have a length".to_string()),
+                part_data: None,
+            });
+        }
+
+        if input.length <= 0 {
+            return Ok(PartResponse {
+                ok: false,
+                msg: Some("length of LED part must be positive number".to_string()),
+                part_data: None,
+            });
+        }
+
+        if input.mode == LEDResampleMode::Truncate && input.length > part.length.unwrap_or(0) {
+            return Ok(PartResponse {
+                ok: false,
+                msg: Some("truncate can't lengthen the part".to_string()),
+                part_data: None,
+            });
+        }
+
+        let length = input.length as usize;
+
+        // bulbs of the part, grouped by control data
+        let bulbs = sqlx::query!(
+            r#"
+                SELECT
+                    LEDBulb.control_id,
+                    LEDBulb.color_id,
+                    LEDBulb.alpha,
+                    ControlData.frame_id
+                FROM LEDBulb
+                INNER JOIN ControlData ON LEDBulb.control_id = ControlData.id
+                WHERE ControlData.part_id = ?
+                ORDER BY LEDBulb.control_id ASC, LEDBulb.position ASC;
+            "#,
+            part.id
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        let frame_ids = bulbs
+            .iter()
+            .map(|bulb| bulb.frame_id)
+            .unique()
+            .collect_vec();
+        let bulbs = partition_by_field(|bulb| bulb.control_id, bulbs);
+
+        let effect_ids = sqlx::query!(
+            r#"
+                SELECT id FROM LEDEffect
+                WHERE part_id = ?;
+            "#,
+            part.id
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .map(|effect| effect.id)
+        .collect_vec();
+
+        // check editing
+        check_editing_control_frames(mysql, context.user_id, &frame_ids).await?;
+
+        let editing_effects = sqlx::query!(
+            r#"
+                SELECT led_effect_id AS "led_effect_id!", user_id
+                FROM EditingLEDEffect
+                WHERE led_effect_id IS NOT NULL AND user_id != ?;
+            "#,
+            context.user_id
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        if let Some(editing) = editing_effects
+            .iter()
+            .find(|editing| effect_ids.contains(&editing.led_effect_id))
+        {
+            return Ok(PartResponse {
+                ok: false,
+                msg: Some(format!(
+                    "LED effect #{} is being edited by user #{}",
+                    editing.led_effect_id, editing.user_id
+                )),
+                part_data: None,
+            });
+        }
+
+        let mut tx = mysql.begin().await?;
+
+        // padded bulbs are black and fully transparent, the color being made on first use
+        let mut off_color: Option<(i32, bool)> = None;
+        let needs_padding =
+            |leds: &[(i32, i32)]| input.mode != LEDResampleMode::Stretch && leds.len() < length;
+        let resample = |leds: &[(i32, i32)], off_color_id: i32| match input.mode {
+            LEDResampleMode::Stretch => resample_leds(leds, length),
+            LEDResampleMode::Pad | LEDResampleMode::Truncate => {
+                pad_leds(leds, length, (off_color_id, 0))
+            }
+        };
+
+        sqlx::query!(
+            r#"
+                UPDATE Part SET length = ?
+                WHERE id = ?;
+            "#,
+            input.length,
+            part.id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for control_bulbs in &bulbs {
+            let control_id = control_bulbs[0].control_id;
+            let leds = control_bulbs
+                .iter()
+                .map(|bulb| (bulb.color_id, bulb.alpha))
+                .collect_vec();
+            if needs_padding(&leds) && off_color.is_none() {
+                off_color = Some(get_or_create_color(&mut tx, [0, 0, 0]).await?);
+            }
+            let resampled = resample(&leds, off_color.map_or(0, |(id, _)| id));
+
+            sqlx::query!(
+                r#"
+                    DELETE FROM LEDBulb
+                    WHERE control_id = ?;
+                "#,
+                control_id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            // the bulbs no longer follow the applied gradient
+            sqlx::query!(
+                r#"
+                    UPDATE ControlData
+                    SET gradient_id = NULL
+                    WHERE id = ?;
+                "#,
+                control_id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            for (position, (color_id, alpha)) in resampled.into_iter().enumerate() {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO LEDBulb (control_id, position, color_id, alpha)
+                        VALUES (?, ?, ?, ?);
+                    "#,
+                    control_id,
+                    position as i32,
+                    color_id,
+                    alpha
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        for frame_id in &frame_ids {
+            sqlx::query!(
+                r#"
+                    UPDATE ControlFrame
+                    SET data_rev = data_rev + 1
+                    WHERE id = ?;
+                "#,
+                frame_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for effect_id in &effect_ids {
+            let leds = sqlx::query!(
+                r#"
+                    SELECT color_id, alpha
+                    FROM LEDEffectState
+                    WHERE effect_id = ?
+                    ORDER BY position ASC;
+                "#,
+                effect_id
+            )
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .map(|state| (state.color_id, state.alpha))
+            .collect_vec();
+            if needs_padding(&leds) && off_color.is_none() {
+                off_color = Some(get_or_create_color(&mut tx, [0, 0, 0]).await?);
+            }
+            let resampled = resample(&leds, off_color.map_or(0, |(id, _)| id));
+
+            sqlx::query!(
+                r#"
+                    DELETE FROM LEDEffectState
+                    WHERE effect_id = ?;
+                "#,
+                effect_id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            for (position, (color_id, alpha)) in resampled.into_iter().enumerate() {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO LEDEffectState (effect_id, position, color_id, alpha)
+                        VALUES (?, ?, ?, ?);
+                    "#,
+                    effect_id,
+                    position as i32,
+                    color_id,
+                    alpha
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+
+        if let Some((off_color_id, true)) = off_color {
+            let code = [0, 0, 0];
+            let color_payload = ColorPayload {
+                mutation: ColorMutationMode::Created,
+                id: off_color_id,
+                color: Some(rgb_to_hex(code)),
+                color_code: Some(code.to_vec()),
+                edit_by: context.user_id,
+            };
+            Subscriptor::publish(color_payload);
+        }
+
+        // update redis and publish the control map and LED effects
+        if !frame_ids.is_empty() {
+            let update_frames = update_redis_controls(mysql, redis, &frame_ids)
+                .await?
+                .into_iter()
+                .map(|(id, redis_control)| (id, RedisControlMandatory::from(redis_control)))
+                .collect();
+
+            let control_map_payload = ControlMapPayload {
+                edit_by: context.user_id,
+                frame: ControlFramesSubDatScalar(ControlFramesSubData {
+                    create_frames: HashMap::new(),
+                    delete_frames: Vec::new(),
+                    update_frames,
+                }),
+            };
+            Subscriptor::publish(control_map_payload);
+        }
+
+        if !effect_ids.is_empty() {
+            let mut update_effects = Vec::new();
+            for effect_id in &effect_ids {
+                update_effects.push(get_led_effect_data(mysql, *effect_id).await?);
+            }
+
+            let led_payload = LEDPayload {
+                create_effects: Vec::new(),
+                update_effects,
+                delete_effects: Vec::new(),
+            };
+            Subscriptor::publish(led_payload);
+        }
+
+        update_revision(mysql).await?;
+
+        Ok(PartResponse {
+            ok: true,
+            msg: Some("successfully resize part".to_string()),
+            part_data: Some(PartData {
+                length: Some(input.length),
+                ..part
+            }),
+        })
+    }
 }
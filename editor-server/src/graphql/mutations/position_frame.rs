@@ -12,6 +12,7 @@ use crate::graphql::types::pos_data::{FrameData, PosDataScalar};
 use crate::graphql::types::pos_frame::{PositionFrame, PositionFrameRevision};
 use crate::types::global::{RedisPosition, Revision, UserContext};
 use crate::utils::data::{delete_redis_position, get_redis_position, update_redis_position};
+use crate::utils::quaternion::{from_euler, split_position_row};
 use crate::utils::revision::update_revision;
 
 use async_graphql::{Context, Error, InputObject, Object, Result as GQLResult};
@@ -111,10 +112,10 @@ impl PositionFrameMutation {
             let mut errors = Vec::<String>::new();
 
             for (idx, coordinatesdinates) in data.iter().enumerate() {
-                // (bool, [x, y, z, rx, ry, rz])
-                if coordinatesdinates.len() != 6 {
+                // (bool, [x, y, z, rx, ry, rz] or [x, y, z, qw, qx, qy, qz])
+                if split_position_row(coordinatesdinates).is_none() {
                     errors.push(format!(
-                        "Dancer #{} data must have 6 elements [x, y, z, rx, ry, rz]. Got: {}",
+                        "Dancer #{} data must have 6 elements [x, y, z, rx, ry, rz] or 7 elements [x, y, z, qw, qx, qy, qz]. Got: {}",
                         idx + 1,
                         coordinatesdinates.len()
                     ));
@@ -144,7 +145,12 @@ impl PositionFrameMutation {
 
         match &position_data {
             Some(data) => {
-                for (idx, coordinates) in (*data).iter().enumerate() {
+                let rows = data
+                    .iter()
+                    .filter_map(|coor| split_position_row(coor))
+                    .collect::<Vec<_>>();
+
+                for (idx, (location, rotation, quaternion)) in rows.iter().enumerate() {
                     if !has_position[idx] {
                         sqlx::query!(
                             r#"
@@ -166,18 +172,22 @@ impl PositionFrameMutation {
                     } else {
                         sqlx::query!(
                             r#"
-                            INSERT INTO PositionData (dancer_id, frame_id, type, x, y, z, rx, ry, rz)
-                            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);
+                            INSERT INTO PositionData (dancer_id, frame_id, type, x, y, z, rx, ry, rz, qw, qx, qy, qz)
+                            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);
                         "#,
                             dancers[idx].id,
                             id,
                             "POSITION",
-                            coordinates[0],
-                            coordinates[1],
-                            coordinates[2],
-                            coordinates[3],
-                            coordinates[4],
-                            coordinates[5],
+                            location[0],
+                            location[1],
+                            location[2],
+                            rotation[0],
+                            rotation[1],
+                            rotation[2],
+                            quaternion.map(|q| q[0]),
+                            quaternion.map(|q| q[1]),
+                            quaternion.map(|q| q[2]),
+                            quaternion.map(|q| q[3]),
                         )
                         .execute(&mut *tx)
                         .await?;
@@ -190,13 +200,11 @@ impl PositionFrameMutation {
                         editing: None,
                         has_position,
                         rev: Revision::default(),
-                        location: data
-                            .iter()
-                            .map(|coor| [coor[0], coor[1], coor[2]])
-                            .collect(),
-                        rotation: data
+                        location: rows.iter().map(|row| row.0).collect(),
+                        rotation: rows.iter().map(|row| row.1).collect(),
+                        quaternion: rows
                             .iter()
-                            .map(|coor| [coor[3], coor[4], coor[5]])
+                            .map(|row| row.2.unwrap_or_else(|| from_euler(row.1)))
                             .collect(),
                     },
                 );
@@ -13,6 +13,7 @@ use crate::graphql::types::{
 use crate::types::global::RedisPosition;
 use crate::types::global::UserContext;
 use crate::utils::data::{get_redis_position, update_redis_position};
+use crate::utils::quaternion::split_position_row;
 use crate::utils::revision::update_revision;
 
 use async_graphql::{Context, InputObject, Object, Result as GQLResult};
@@ -106,10 +107,10 @@ impl PositionMapMutation {
 
         // check input correctness
         for (idx, coor) in input.position_data.iter().enumerate() {
-            // 6 elements: [x, y, z, rx, ry, rz]
-            if coor.len() != 6 {
+            // 6 elements: [x, y, z, rx, ry, rz], or 7 elements: [x, y, z, qw, qx, qy, qz]
+            if split_position_row(coor).is_none() {
                 errors.push(format!(
-                    "Dancer #{} data must have 6 elements [x, y, z, rx, ry, rz]. Got: {}",
+                    "Dancer #{} data must have 6 elements [x, y, z, rx, ry, rz] or 7 elements [x, y, z, qw, qx, qy, qz]. Got: {}",
                     idx + 1,
                     coor.len()
                 ));
@@ -128,7 +129,8 @@ impl PositionMapMutation {
                 let _ = sqlx::query!(
                     r#"
                         UPDATE PositionData
-                        SET type = ?, x = NULL, y = NULL, z = NULL, rx = 0, ry = 0, rz = 0
+                        SET type = ?, x = NULL, y = NULL, z = NULL, rx = 0, ry = 0, rz = 0,
+                            qw = NULL, qx = NULL, qy = NULL, qz = NULL
                         WHERE dancer_id = ? AND frame_id = ?;
                     "#,
                     "NO_EFFECT",
@@ -137,20 +139,25 @@ impl PositionMapMutation {
                 )
                 .execute(&mut *tx)
                 .await?;
-            } else {
+            } else if let Some((location, rotation, quaternion)) = split_position_row(coor) {
                 let _ = sqlx::query!(
                     r#"
                         UPDATE PositionData
-                        SET type = ?, x = ?, y = ?, z = ?, rx = ?, ry = ?, rz = ?
+                        SET type = ?, x = ?, y = ?, z = ?, rx = ?, ry = ?, rz = ?,
+                            qw = ?, qx = ?, qy = ?, qz = ?
                         WHERE dancer_id = ? AND frame_id = ?;
                     "#,
                     "POSITION",
-                    coor[0],
-                    coor[1],
-                    coor[2],
-                    coor[3],
-                    coor[4],
-                    coor[5],
+                    location[0],
+                    location[1],
+                    location[2],
+                    rotation[0],
+                    rotation[1],
+                    rotation[2],
+                    quaternion.map(|q| q[0]),
+                    quaternion.map(|q| q[1]),
+                    quaternion.map(|q| q[2]),
+                    quaternion.map(|q| q[3]),
                     dancer.id,
                     frame_to_edit.id
                 )
@@ -0,0 +1,322 @@
+//! Recolor mutation methods.
+
+use crate::graphql::subscriptions::control_map::ControlMapPayload;
+use crate::graphql::subscriptions::led::LEDPayload;
+use crate::graphql::subscriptor::Subscriptor;
+use crate::graphql::types::control_data::{
+    ControlFramesSubDatScalar, ControlFramesSubData, RedisControlMandatory,
+};
+use crate::graphql::types::scope::ControlScopeInput;
+use crate::types::global::{PartType, UserContext};
+use crate::utils::data::{check_editing_control_frames, update_redis_controls};
+use crate::utils::led::{get_led_effect_data, recolor_led_effect};
+use crate::utils::revision::update_revision;
+
+use async_graphql::{
+    Context, Error as GQLError, InputObject, Object, Result as GQLResult, SimpleObject,
+};
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+
+#[derive(InputObject, Default)]
+pub struct RecolorInput {
+    pub from_color_id: i32,
+    pub to_color_id: i32,
+    pub scope: ControlScopeInput,
+    /// Also recolor the LED effects of the parts in scope; effects also used outside of the
+    /// scope are copied for the control data in scope.
+    pub include_led_effects: Option<bool>,
+}
+
+#[derive(SimpleObject, Default)]
+pub struct RecolorResponse {
+    ok: bool,
+    msg: String,
+    control_count: i32,
+    bulb_count: i32,
+    effect_state_count: i32,
+}
+
+#[derive(Default)]
+pub struct RecolorMutation;
+
+#[Object]
+impl RecolorMutation {
+    // Replace a color with another one in the control data of a scope of
+    // dancers, parts and frames
+    async fn recolor(&self, ctx: &Context<'_>, input: RecolorInput) -> GQLResult<RecolorResponse> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+
+        let mysql = clients.mysql_pool();
+        let redis = clients.redis_client();
+
+        tracing::info!("Mutation: recolor");
+
+        let RecolorInput {
+            from_color_id,
+            to_color_id,
+            scope,
+            include_led_effects,
+        } = input;
+
+        let (start, end) = scope.range()?;
+
+        let mut colors = Vec::new();
+        for color_id in [from_color_id, to_color_id] {
+            let color = sqlx::query!(
+                r#"
+                    SELECT id, name FROM Color
+                    WHERE id = ?;
+                "#,
+                color_id
+            )
+            .fetch_optional(mysql)
+            .await?;
+
+            match color {
+                Some(color) => colors.push(color),
+                None => return Err(GQLError::new(format!("Color #{color_id} not found"))),
+            }
+        }
+        let to_color = &colors[1];
+
+        // find the control data in scope
+        let control_data = sqlx::query!(
+            r#"
+                SELECT
+                    ControlData.id,
+                    ControlData.frame_id,
+                    ControlData.dancer_id,
+                    ControlData.type,
+                    Part.name AS "part_name",
+                    Part.type AS "part_type: PartType"
+                FROM ControlData
+                INNER JOIN ControlFrame ON ControlData.frame_id = ControlFrame.id
+                INNER JOIN Part ON ControlData.part_id = Part.id
+                WHERE ControlFrame.start >= ? AND ControlFrame.start <= ?;
+            "#,
+            start,
+            end
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .filter(|data| scope.contains(data.dancer_id, &data.part_name, data.part_type))
+        .collect_vec();
+
+        let frame_ids = control_data
+            .iter()
+            .map(|data| data.frame_id)
+            .unique()
+            .collect_vec();
+
+        // check editing
+        check_editing_control_frames(mysql, context.user_id, &frame_ids).await?;
+
+        // find the LED effects of the parts in scope
+        let effect_ids = if include_led_effects.unwrap_or(false) {
+            let effect_ids = sqlx::query!(
+                r#"
+                    SELECT DISTINCT
+                        LEDEffect.id,
+                        Dancer.id AS "dancer_id",
+                        Part.name AS "part_name",
+                        Part.type AS "part_type: PartType"
+                    FROM LEDEffect
+                    INNER JOIN Part ON LEDEffect.part_id = Part.id
+                    INNER JOIN Dancer ON Dancer.model_id = LEDEffect.model_id;
+                "#,
+            )
+            .fetch_all(mysql)
+            .await?
+            .into_iter()
+            .filter(|effect| scope.contains(effect.dancer_id, &effect.part_name, effect.part_type))
+            .map(|effect| effect.id)
+            .unique()
+            .collect_vec();
+
+            let editing_effects = sqlx::query!(
+                r#"
+                    SELECT led_effect_id AS "led_effect_id!", user_id
+                    FROM EditingLEDEffect
+                    WHERE led_effect_id IS NOT NULL AND user_id != ?;
+                "#,
+                context.user_id
+            )
+            .fetch_all(mysql)
+            .await?;
+
+            if let Some(editing) = editing_effects
+                .iter()
+                .find(|editing| effect_ids.contains(&editing.led_effect_id))
+            {
+                return Err(GQLError::new(format!(
+                    "LED effect #{} is being edited by user #{}",
+                    editing.led_effect_id, editing.user_id
+                )));
+            }
+
+            effect_ids
+        } else {
+            Vec::new()
+        };
+
+        let mut tx = mysql.begin().await?;
+
+        let mut control_count = 0;
+        let mut bulb_count = 0;
+        let mut effect_state_count = 0;
+        let mut touched_frame_ids = Vec::new();
+
+        for data in &control_data {
+            let affected = if data.r#type == "LED_BULBS" {
+                sqlx::query!(
+                    r#"
+                        UPDATE LEDBulb
+                        SET color_id = ?
+                        WHERE control_id = ? AND color_id = ?;
+                    "#,
+                    to_color_id,
+                    data.id,
+                    from_color_id
+                )
+                .execute(&mut *tx)
+                .await?
+                .rows_affected()
+            } else {
+                sqlx::query!(
+                    r#"
+                        UPDATE ControlData
+                        SET color_id = ?
+                        WHERE id = ? AND color_id = ?;
+                    "#,
+                    to_color_id,
+                    data.id,
+                    from_color_id
+                )
+                .execute(&mut *tx)
+                .await?
+                .rows_affected()
+            };
+
+            if affected == 0 {
+                continue;
+            }
+
+            if data.r#type == "LED_BULBS" {
+                // the bulbs no longer follow the applied gradient
+                sqlx::query!(
+                    r#"
+                        UPDATE ControlData
+                        SET gradient_id = NULL
+                        WHERE id = ?;
+                    "#,
+                    data.id
+                )
+                .execute(&mut *tx)
+                .await?;
+
+                bulb_count += affected as i32;
+            } else {
+                control_count += affected as i32;
+            }
+
+            if !touched_frame_ids.contains(&data.frame_id) {
+                touched_frame_ids.push(data.frame_id);
+            }
+        }
+
+        // effects also used outside of the scope are copied for the control data in scope
+        let mapping = HashMap::from([(from_color_id, to_color_id)]);
+        let scope_ids: HashSet<i32> = control_data.iter().map(|data| data.id).collect();
+        let mut touched_effect_ids = Vec::new();
+        let mut created_effect_ids = Vec::new();
+
+        for effect_id in &effect_ids {
+            let recolored = recolor_led_effect(
+                &mut tx,
+                *effect_id,
+                &mapping,
+                &scope_ids,
+                &to_color.name,
+                &mut touched_frame_ids,
+            )
+            .await?;
+
+            if let Some(recolored) = recolored {
+                effect_state_count += recolored.state_count;
+                match recolored.copy_id {
+                    Some(copy_id) => created_effect_ids.push(copy_id),
+                    None => touched_effect_ids.push(*effect_id),
+                }
+            }
+        }
+
+        for frame_id in &touched_frame_ids {
+            sqlx::query!(
+                r#"
+                    UPDATE ControlFrame
+                    SET data_rev = data_rev + 1
+                    WHERE id = ?;
+                "#,
+                frame_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        // update redis and publish the control map
+        if !touched_frame_ids.is_empty() {
+            let update_frames = update_redis_controls(mysql, redis, &touched_frame_ids)
+                .await?
+                .into_iter()
+                .map(|(id, redis_control)| (id, RedisControlMandatory::from(redis_control)))
+                .collect();
+
+            let control_map_payload = ControlMapPayload {
+                edit_by: context.user_id,
+                frame: ControlFramesSubDatScalar(ControlFramesSubData {
+                    create_frames: HashMap::new(),
+                    delete_frames: Vec::new(),
+                    update_frames,
+                }),
+            };
+            Subscriptor::publish(control_map_payload);
+        }
+
+        if !touched_effect_ids.is_empty() || !created_effect_ids.is_empty() {
+            let mut create_effects = Vec::new();
+            for effect_id in &created_effect_ids {
+                create_effects.push(get_led_effect_data(mysql, *effect_id).await?);
+            }
+
+            let mut update_effects = Vec::new();
+            for effect_id in &touched_effect_ids {
+                update_effects.push(get_led_effect_data(mysql, *effect_id).await?);
+            }
+
+            let led_payload = LEDPayload {
+                create_effects,
+                update_effects,
+                delete_effects: Vec::new(),
+            };
+            Subscriptor::publish(led_payload);
+        }
+
+        update_revision(mysql).await?;
+
+        Ok(RecolorResponse {
+            ok: true,
+            msg: format!(
+                "Recolored {} control data, {} bulbs and {} effect states",
+                control_count, bulb_count, effect_state_count
+            ),
+            control_count,
+            bulb_count,
+            effect_state_count,
+        })
+    }
+}
@@ -5,11 +5,13 @@ use crate::graphql::subscriptor::Subscriptor;
 use crate::graphql::types::{control_data::*, pos_data::*};
 use crate::types::global::{RedisPosition, UserContext};
 use crate::utils::data::{
-    get_redis_control, get_redis_position, update_redis_control, update_redis_position,
+    check_editing_control_frames, check_editing_position_frames, delete_redis_control,
+    delete_redis_position, get_redis_control, get_redis_position, update_redis_control,
+    update_redis_controls, update_redis_position, update_redis_positions,
 };
 use crate::utils::revision::update_revision;
 
-use async_graphql::{Context, Error as GQLError, Object, Result as GQLResult, SimpleObject};
+use async_graphql::{Context, Enum, Error as GQLError, Object, Result as GQLResult, SimpleObject};
 use itertools::Itertools;
 use std::collections::HashMap;
 
@@ -19,9 +21,57 @@ struct ShiftResponse {
     ok: bool,
 }
 
+#[derive(SimpleObject, Default)]
+struct DeleteFramesResponse {
+    msg: String,
+    ok: bool,
+    control_frame_ids: Vec<i32>,
+    position_frame_ids: Vec<i32>,
+}
+
+#[derive(SimpleObject, Default)]
+struct DuplicateFramesResponse {
+    msg: String,
+    ok: bool,
+    control_frame_ids: Vec<i32>,
+    position_frame_ids: Vec<i32>,
+}
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Default, Debug)]
+pub enum CollisionPolicy {
+    /// Fail if a frame already exists at a target start.
+    #[default]
+    Refuse,
+    /// Replace the data of the copied dancers in the existing frame.
+    Overwrite,
+}
+
 #[derive(Default)]
 pub struct FrameMutation;
 
+/// Map the starts of the frames in [start, end] proportionally onto [new_start, new_end],
+/// failing if two frames would end up with the same start.
+fn stretch_starts(
+    frames: &[(i32, i32)],
+    others: &[i32],
+    (start, end): (i32, i32),
+    (new_start, new_end): (i32, i32),
+) -> Result<Vec<(i32, i32)>, String> {
+    let scale = (new_end - new_start) as f64 / (end - start) as f64;
+
+    let mut result: Vec<(i32, i32)> = Vec::new();
+    for (id, frame_start) in frames {
+        let target = new_start + ((frame_start - start) as f64 * scale).round() as i32;
+
+        if others.contains(&target) || result.iter().any(|(_, start)| *start == target) {
+            return Err(format!("Duplicated frame start at {target}"));
+        }
+        result.push((*id, target));
+    }
+
+    Ok(result)
+}
+
 #[Object]
 impl FrameMutation {
     async fn shift(
@@ -328,4 +378,929 @@ impl FrameMutation {
             ok: true,
         })
     }
+
+    async fn delete_frames(
+        &self,
+        ctx: &Context<'_>,
+        start: i32,
+        end: i32,
+        delete_control: bool,
+        delete_position: bool,
+    ) -> GQLResult<DeleteFramesResponse> {
+        let context = ctx.data::<UserContext>()?;
+
+        let clients = context.clients;
+        let redis_client = &clients.redis_client;
+
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Mutation: deleteFrames");
+
+        //check start after end
+        if start > end {
+            return Err(GQLError::new("Start must not be larger than end"));
+        }
+        //the first frame must be kept
+        if start <= 0 {
+            return Err(GQLError::new("The first frame can not be deleted"));
+        }
+
+        // check editing
+        let control_frame_ids = if delete_control {
+            let exists_editing_frame = sqlx::query!(
+                r#"
+                    SELECT COUNT(*) as count
+                    FROM ControlFrame
+                    INNER JOIN EditingControlFrame
+                    ON EditingControlFrame.frame_id = ControlFrame.id
+                    AND start >= ?
+                    AND start <= ?;
+                "#,
+                start,
+                end
+            )
+            .fetch_one(mysql)
+            .await?
+            .count
+                > 0;
+
+            if exists_editing_frame {
+                return Err(GQLError::new("Editing frame exists in the interval"));
+            }
+
+            sqlx::query!(
+                r#"
+                    SELECT id FROM ControlFrame
+                    WHERE start >= ?
+                    AND start <= ?
+                    ORDER BY start ASC;
+                "#,
+                start,
+                end
+            )
+            .fetch_all(mysql)
+            .await?
+            .into_iter()
+            .map(|frame| frame.id)
+            .collect_vec()
+        } else {
+            Vec::new()
+        };
+
+        let position_frame_ids = if delete_position {
+            let exists_editing_frame = sqlx::query!(
+                r#"
+                    SELECT COUNT(*) as count
+                    FROM PositionFrame
+                    INNER JOIN EditingPositionFrame
+                    ON EditingPositionFrame.frame_id = PositionFrame.id
+                    AND start >= ?
+                    AND start <= ?;
+                "#,
+                start,
+                end
+            )
+            .fetch_one(mysql)
+            .await?
+            .count
+                > 0;
+
+            if exists_editing_frame {
+                return Err(GQLError::new("Editing frame exists in the interval"));
+            }
+
+            sqlx::query!(
+                r#"
+                    SELECT id FROM PositionFrame
+                    WHERE start >= ?
+                    AND start <= ?
+                    ORDER BY start ASC;
+                "#,
+                start,
+                end
+            )
+            .fetch_all(mysql)
+            .await?
+            .into_iter()
+            .map(|frame| frame.id)
+            .collect_vec()
+        } else {
+            Vec::new()
+        };
+
+        let mut tx = mysql.begin().await?;
+
+        if delete_control {
+            sqlx::query!(
+                r#"
+                    DELETE FROM ControlFrame
+                    WHERE start >= ?
+                    AND start <= ?;
+                "#,
+                start,
+                end
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        if delete_position {
+            sqlx::query!(
+                r#"
+                    DELETE FROM PositionFrame
+                    WHERE start >= ?
+                    AND start <= ?;
+                "#,
+                start,
+                end
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        if !control_frame_ids.is_empty() {
+            for id in &control_frame_ids {
+                delete_redis_control(redis_client, *id).await?;
+            }
+
+            //subscription
+            let control_map_payload = ControlMapPayload {
+                edit_by: context.user_id,
+                frame: ControlFramesSubDatScalar(ControlFramesSubData {
+                    create_frames: HashMap::new(),
+                    delete_frames: control_frame_ids.clone(),
+                    update_frames: HashMap::new(),
+                }),
+            };
+            Subscriptor::publish(control_map_payload);
+        }
+
+        if !position_frame_ids.is_empty() {
+            for id in &position_frame_ids {
+                delete_redis_position(redis_client, *id).await?;
+            }
+
+            //subscription
+            let position_map_payload = PositionMapPayload {
+                edit_by: context.user_id,
+                frame: PosDataScalar(FrameData {
+                    create_frames: HashMap::new(),
+                    delete_frames: position_frame_ids.iter().map(|id| id.to_string()).collect(),
+                    update_frames: HashMap::new(),
+                }),
+            };
+            Subscriptor::publish(position_map_payload);
+        }
+
+        update_revision(mysql).await?;
+
+        Ok(DeleteFramesResponse {
+            msg: format!(
+                "Deleted {} control frames and {} position frames",
+                control_frame_ids.len(),
+                position_frame_ids.len()
+            ),
+            ok: true,
+            control_frame_ids,
+            position_frame_ids,
+        })
+    }
+
+    async fn stretch(
+        &self,
+        ctx: &Context<'_>,
+        start: i32,
+        end: i32,
+        new_start: i32,
+        new_end: i32,
+        stretch_control: bool,
+        stretch_position: bool,
+    ) -> GQLResult<ShiftResponse> {
+        let context = ctx.data::<UserContext>()?;
+
+        let clients = context.clients;
+        let redis_client = &clients.redis_client;
+
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Mutation: stretch");
+
+        //check negative
+        if new_start < 0 {
+            return Err(GQLError::new("Negative start is not legal"));
+        }
+        //check start after end
+        if start >= end || new_start >= new_end {
+            return Err(GQLError::new("Start must smaller than end"));
+        }
+        //the first frame must be kept at 0
+        if start < 0 || (start == 0 && new_start != 0) {
+            return Err(GQLError::new("The first frame can not be moved"));
+        }
+
+        let check_start = std::cmp::min(start, new_start);
+        let check_end = std::cmp::max(end, new_end);
+
+        // check editing and duplicated start
+        let control_frames_to_stretch = if stretch_control {
+            let exists_editing_frame = sqlx::query!(
+                r#"
+                    SELECT COUNT(*) as count
+                    FROM ControlFrame
+                    INNER JOIN EditingControlFrame
+                    ON EditingControlFrame.frame_id = ControlFrame.id
+                    AND start >= ?
+                    AND start <= ?;
+                "#,
+                start,
+                end
+            )
+            .fetch_one(mysql)
+            .await?
+            .count
+                > 0;
+
+            if exists_editing_frame {
+                return Err(GQLError::new("Editing frame exists in the interval"));
+            }
+
+            let frames = sqlx::query!(
+                r#"
+                    SELECT id, start FROM ControlFrame
+                    WHERE start >= ?
+                    AND start <= ?
+                    ORDER BY start ASC;
+                "#,
+                check_start,
+                check_end
+            )
+            .fetch_all(mysql)
+            .await?;
+
+            let (frames_to_stretch, others): (Vec<_>, Vec<_>) = frames
+                .into_iter()
+                .partition(|frame| frame.start >= start && frame.start <= end);
+
+            stretch_starts(
+                &frames_to_stretch
+                    .iter()
+                    .map(|frame| (frame.id, frame.start))
+                    .collect_vec(),
+                &others.iter().map(|frame| frame.start).collect_vec(),
+                (start, end),
+                (new_start, new_end),
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let pos_frames_to_stretch = if stretch_position {
+            let exists_editing_frame = sqlx::query!(
+                r#"
+                    SELECT COUNT(*) as count
+                    FROM PositionFrame
+                    INNER JOIN EditingPositionFrame
+                    ON EditingPositionFrame.frame_id = PositionFrame.id
+                    AND start >= ?
+                    AND start <= ?;
+                "#,
+                start,
+                end
+            )
+            .fetch_one(mysql)
+            .await?
+            .count
+                > 0;
+
+            if exists_editing_frame {
+                return Err(GQLError::new("Editing frame exists in the interval"));
+            }
+
+            let frames = sqlx::query!(
+                r#"
+                    SELECT id, start FROM PositionFrame
+                    WHERE start >= ?
+                    AND start <= ?
+                    ORDER BY start ASC;
+                "#,
+                check_start,
+                check_end
+            )
+            .fetch_all(mysql)
+            .await?;
+
+            let (frames_to_stretch, others): (Vec<_>, Vec<_>) = frames
+                .into_iter()
+                .partition(|frame| frame.start >= start && frame.start <= end);
+
+            stretch_starts(
+                &frames_to_stretch
+                    .iter()
+                    .map(|frame| (frame.id, frame.start))
+                    .collect_vec(),
+                &others.iter().map(|frame| frame.start).collect_vec(),
+                (start, end),
+                (new_start, new_end),
+            )?
+        } else {
+            Vec::new()
+        };
+
+        // update database, moving the frames out of the way first to keep start unique
+        let mut tx = mysql.begin().await?;
+
+        for (id, _) in &control_frames_to_stretch {
+            sqlx::query!(
+                r#"
+                    UPDATE ControlFrame
+                    SET start = -1 - id
+                    WHERE id = ?;
+                "#,
+                id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for (id, new_start) in &control_frames_to_stretch {
+            sqlx::query!(
+                r#"
+                    UPDATE ControlFrame
+                    SET
+                        start = ?,
+                        meta_rev = meta_rev + 1
+                    WHERE id = ?;
+                "#,
+                new_start,
+                id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for (id, _) in &pos_frames_to_stretch {
+            sqlx::query!(
+                r#"
+                    UPDATE PositionFrame
+                    SET start = -1 - id
+                    WHERE id = ?;
+                "#,
+                id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for (id, new_start) in &pos_frames_to_stretch {
+            sqlx::query!(
+                r#"
+                    UPDATE PositionFrame
+                    SET
+                        start = ?,
+                        meta_rev = meta_rev + 1
+                    WHERE id = ?;
+                "#,
+                new_start,
+                id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        if stretch_control {
+            let update_control_ids = control_frames_to_stretch
+                .iter()
+                .map(|(id, _)| *id)
+                .collect_vec();
+
+            //subscription
+            let update_control_frames =
+                update_redis_controls(mysql, redis_client, &update_control_ids)
+                    .await?
+                    .into_iter()
+                    .map(|(id, redis_control)| (id, RedisControlMandatory::from(redis_control)))
+                    .collect();
+
+            let control_map_payload = ControlMapPayload {
+                edit_by: context.user_id,
+                frame: ControlFramesSubDatScalar(ControlFramesSubData {
+                    create_frames: HashMap::new(),
+                    delete_frames: Vec::new(),
+                    update_frames: update_control_frames,
+                }),
+            };
+            Subscriptor::publish(control_map_payload);
+        }
+
+        if stretch_position {
+            let update_position_ids = pos_frames_to_stretch
+                .iter()
+                .map(|(id, _)| *id)
+                .collect_vec();
+
+            //subscription
+            let update_position_frames =
+                update_redis_positions(mysql, redis_client, &update_position_ids).await?;
+
+            let position_map_payload = PositionMapPayload {
+                edit_by: context.user_id,
+                frame: PosDataScalar(FrameData {
+                    create_frames: HashMap::new(),
+                    delete_frames: Vec::new(),
+                    update_frames: update_position_frames,
+                }),
+            };
+            Subscriptor::publish(position_map_payload);
+        }
+
+        update_revision(mysql).await?;
+
+        Ok(ShiftResponse {
+            msg: "Stretch success".to_string(),
+            ok: true,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn duplicate_frames(
+        &self,
+        ctx: &Context<'_>,
+        start: i32,
+        end: i32,
+        target_start: i32,
+        duplicate_control: bool,
+        duplicate_position: bool,
+        dancer_ids: Option<Vec<i32>>,
+        policy: Option<CollisionPolicy>,
+    ) -> GQLResult<DuplicateFramesResponse> {
+        let context = ctx.data::<UserContext>()?;
+
+        let clients = context.clients;
+        let redis_client = &clients.redis_client;
+
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Mutation: duplicateFrames");
+
+        let policy = policy.unwrap_or_default();
+        let mv = target_start - start;
+        let target_end = end + mv;
+
+        //check negative
+        if target_start < 0 {
+            return Err(GQLError::new("Negative start is not legal"));
+        }
+        //check start after end
+        if start > end {
+            return Err(GQLError::new("Start must not be larger than end"));
+        }
+        //check overlapping
+        if target_start <= end && target_end >= start {
+            return Err(GQLError::new(
+                "Target interval overlaps the source interval",
+            ));
+        }
+
+        let dancer_ids = match dancer_ids {
+            Some(dancer_ids) => dancer_ids,
+            None => sqlx::query!(
+                r#"
+                    SELECT id FROM Dancer;
+                "#,
+            )
+            .fetch_all(mysql)
+            .await?
+            .into_iter()
+            .map(|dancer| dancer.id)
+            .collect_vec(),
+        };
+
+        // check editing and collisions, pairing every source frame with its existing target
+        let control_frames_to_copy = if duplicate_control {
+            let source_frames = sqlx::query!(
+                r#"
+                    SELECT id, start, fade_for_new_status AS "fade: bool"
+                    FROM ControlFrame
+                    WHERE start >= ?
+                    AND start <= ?
+                    ORDER BY start ASC;
+                "#,
+                start,
+                end
+            )
+            .fetch_all(mysql)
+            .await?;
+
+            let target_frames = sqlx::query!(
+                r#"
+                    SELECT id, start FROM ControlFrame
+                    WHERE start >= ?
+                    AND start <= ?
+                    ORDER BY start ASC;
+                "#,
+                target_start,
+                target_end
+            )
+            .fetch_all(mysql)
+            .await?;
+
+            let mut frames = Vec::new();
+            for frame in source_frames {
+                let target = target_frames
+                    .iter()
+                    .find(|target| target.start == frame.start + mv)
+                    .map(|target| target.id);
+
+                if target.is_some() && policy == CollisionPolicy::Refuse {
+                    return Err(GQLError::new(format!(
+                        "Duplicated frame start at {}",
+                        frame.start + mv
+                    )));
+                }
+                frames.push((frame.id, frame.start + mv, frame.fade, target));
+            }
+
+            let target_ids = frames
+                .iter()
+                .filter_map(|(_, _, _, target)| *target)
+                .collect_vec();
+            check_editing_control_frames(mysql, context.user_id, &target_ids).await?;
+
+            frames
+        } else {
+            Vec::new()
+        };
+
+        let pos_frames_to_copy = if duplicate_position {
+            let source_frames = sqlx::query!(
+                r#"
+                    SELECT id, start FROM PositionFrame
+                    WHERE start >= ?
+                    AND start <= ?
+                    ORDER BY start ASC;
+                "#,
+                start,
+                end
+            )
+            .fetch_all(mysql)
+            .await?;
+
+            let target_frames = sqlx::query!(
+                r#"
+                    SELECT id, start FROM PositionFrame
+                    WHERE start >= ?
+                    AND start <= ?
+                    ORDER BY start ASC;
+                "#,
+                target_start,
+                target_end
+            )
+            .fetch_all(mysql)
+            .await?;
+
+            let mut frames = Vec::new();
+            for frame in source_frames {
+                let target = target_frames
+                    .iter()
+                    .find(|target| target.start == frame.start + mv)
+                    .map(|target| target.id);
+
+                if target.is_some() && policy == CollisionPolicy::Refuse {
+                    return Err(GQLError::new(format!(
+                        "Duplicated frame start at {}",
+                        frame.start + mv
+                    )));
+                }
+                frames.push((frame.id, frame.start + mv, target));
+            }
+
+            let target_ids = frames
+                .iter()
+                .filter_map(|(_, _, target)| *target)
+                .collect_vec();
+            check_editing_position_frames(mysql, context.user_id, &target_ids).await?;
+
+            frames
+        } else {
+            Vec::new()
+        };
+
+        let mut tx = mysql.begin().await?;
+
+        let mut create_control_ids = Vec::new();
+        let mut update_control_ids = Vec::new();
+
+        for (source_id, frame_start, fade, target) in &control_frames_to_copy {
+            let frame_id = match target {
+                Some(target_id) => {
+                    for dancer_id in &dancer_ids {
+                        sqlx::query!(
+                            r#"
+                                DELETE FROM ControlData
+                                WHERE frame_id = ? AND dancer_id = ?;
+                            "#,
+                            target_id,
+                            dancer_id
+                        )
+                        .execute(&mut *tx)
+                        .await?;
+                    }
+
+                    sqlx::query!(
+                        r#"
+                            UPDATE ControlFrame
+                            SET data_rev = data_rev + 1
+                            WHERE id = ?;
+                        "#,
+                        target_id
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+
+                    update_control_ids.push(*target_id);
+                    *target_id
+                }
+                None => {
+                    let frame_id = sqlx::query!(
+                        r#"
+                            INSERT INTO ControlFrame (start, fade_for_new_status)
+                            VALUES (?, ?);
+                        "#,
+                        frame_start,
+                        fade
+                    )
+                    .execute(&mut *tx)
+                    .await?
+                    .last_insert_id() as i32;
+
+                    create_control_ids.push(frame_id);
+                    frame_id
+                }
+            };
+
+            let control_data = sqlx::query!(
+                r#"
+                    SELECT
+                        id, dancer_id, part_id, type, fade AS "fade: bool",
+                        color_id, effect_id, gradient_id, alpha
+                    FROM ControlData
+                    WHERE frame_id = ?;
+                "#,
+                source_id
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+
+            for data in &control_data {
+                let copied = dancer_ids.contains(&data.dancer_id);
+
+                // a new frame still needs data for the other dancers, which keep their status
+                if !copied && target.is_some() {
+                    continue;
+                }
+
+                let control_id = sqlx::query!(
+                    r#"
+                        INSERT INTO ControlData
+                        (dancer_id, part_id, frame_id, type, fade, color_id, effect_id, gradient_id, alpha)
+                        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);
+                    "#,
+                    data.dancer_id,
+                    data.part_id,
+                    frame_id,
+                    if copied {
+                        data.r#type.as_str()
+                    } else {
+                        "NO_EFFECT"
+                    },
+                    data.fade,
+                    if copied { data.color_id } else { None },
+                    if copied { data.effect_id } else { None },
+                    if copied { data.gradient_id } else { None },
+                    data.alpha
+                )
+                .execute(&mut *tx)
+                .await?
+                .last_insert_id() as i32;
+
+                if copied {
+                    sqlx::query!(
+                        r#"
+                            INSERT INTO LEDBulb (control_id, position, color_id, alpha)
+                            SELECT ?, position, color_id, alpha
+                            FROM LEDBulb
+                            WHERE control_id = ?;
+                        "#,
+                        control_id,
+                        data.id
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+
+        let mut create_position_ids = Vec::new();
+        let mut update_position_ids = Vec::new();
+
+        for (source_id, frame_start, target) in &pos_frames_to_copy {
+            let frame_id = match target {
+                Some(target_id) => {
+                    for dancer_id in &dancer_ids {
+                        sqlx::query!(
+                            r#"
+                                DELETE FROM PositionData
+                                WHERE frame_id = ? AND dancer_id = ?;
+                            "#,
+                            target_id,
+                            dancer_id
+                        )
+                        .execute(&mut *tx)
+                        .await?;
+                    }
+
+                    sqlx::query!(
+                        r#"
+                            UPDATE PositionFrame
+                            SET data_rev = data_rev + 1
+                            WHERE id = ?;
+                        "#,
+                        target_id
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+
+                    update_position_ids.push(*target_id);
+                    *target_id
+                }
+                None => {
+                    let frame_id = sqlx::query!(
+                        r#"
+                            INSERT INTO PositionFrame (start)
+                            VALUES (?);
+                        "#,
+                        frame_start
+                    )
+                    .execute(&mut *tx)
+                    .await?
+                    .last_insert_id() as i32;
+
+                    create_position_ids.push(frame_id);
+                    frame_id
+                }
+            };
+
+            let position_data = sqlx::query!(
+                r#"
+                    SELECT dancer_id, type, x, y, z, rx, ry, rz, qw, qx, qy, qz
+                    FROM PositionData
+                    WHERE frame_id = ?;
+                "#,
+                source_id
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+
+            for data in &position_data {
+                let copied = dancer_ids.contains(&data.dancer_id);
+
+                if copied {
+                    sqlx::query!(
+                        r#"
+                            INSERT INTO PositionData (dancer_id, frame_id, type, x, y, z, rx, ry, rz, qw, qx, qy, qz)
+                            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);
+                        "#,
+                        data.dancer_id,
+                        frame_id,
+                        data.r#type,
+                        data.x,
+                        data.y,
+                        data.z,
+                        data.rx,
+                        data.ry,
+                        data.rz,
+                        data.qw,
+                        data.qx,
+                        data.qy,
+                        data.qz
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                } else if target.is_none() {
+                    sqlx::query!(
+                        r#"
+                            INSERT INTO PositionData (dancer_id, frame_id, type, x, y, z, rx, ry, rz)
+                            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);
+                        "#,
+                        data.dancer_id,
+                        frame_id,
+                        "NO_EFFECT",
+                        0.0,
+                        0.0,
+                        0.0,
+                        0.0,
+                        0.0,
+                        0.0,
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+
+        tx.commit().await?;
+
+        if duplicate_control {
+            //subscription
+            let create_control_frames =
+                update_redis_controls(mysql, redis_client, &create_control_ids)
+                    .await?
+                    .into_iter()
+                    .map(|(id, redis_control)| (id, RedisControlMandatory::from(redis_control)))
+                    .collect();
+            let update_control_frames =
+                update_redis_controls(mysql, redis_client, &update_control_ids)
+                    .await?
+                    .into_iter()
+                    .map(|(id, redis_control)| (id, RedisControlMandatory::from(redis_control)))
+                    .collect();
+
+            let control_map_payload = ControlMapPayload {
+                edit_by: context.user_id,
+                frame: ControlFramesSubDatScalar(ControlFramesSubData {
+                    create_frames: create_control_frames,
+                    delete_frames: Vec::new(),
+                    update_frames: update_control_frames,
+                }),
+            };
+            Subscriptor::publish(control_map_payload);
+        }
+
+        if duplicate_position {
+            //subscription
+            let create_position_frames =
+                update_redis_positions(mysql, redis_client, &create_position_ids).await?;
+            let update_position_frames =
+                update_redis_positions(mysql, redis_client, &update_position_ids).await?;
+
+            let position_map_payload = PositionMapPayload {
+                edit_by: context.user_id,
+                frame: PosDataScalar(FrameData {
+                    create_frames: create_position_frames,
+                    delete_frames: Vec::new(),
+                    update_frames: update_position_frames,
+                }),
+            };
+            Subscriptor::publish(position_map_payload);
+        }
+
+        update_revision(mysql).await?;
+
+        Ok(DuplicateFramesResponse {
+            msg: "Duplicate success".to_string(),
+            ok: true,
+            control_frame_ids: create_control_ids,
+            position_frame_ids: create_position_ids,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stretch_keeps_order() {
+        let frames = [(1, 100), (2, 150), (3, 200)];
+        assert_eq!(
+            stretch_starts(&frames, &[], (100, 200), (100, 400)),
+            Ok(vec![(1, 100), (2, 250), (3, 400)])
+        );
+        assert_eq!(
+            stretch_starts(&frames, &[], (100, 200), (0, 50)),
+            Ok(vec![(1, 0), (2, 25), (3, 50)])
+        );
+    }
+
+    #[test]
+    fn stretch_rejects_merged_frames() {
+        let frames = [(1, 100), (2, 101), (3, 200)];
+        assert_eq!(
+            stretch_starts(&frames, &[], (100, 200), (100, 110)),
+            Err("Duplicated frame start at 100".to_string())
+        );
+    }
+
+    #[test]
+    fn stretch_rejects_other_frames() {
+        let frames = [(1, 100), (2, 200)];
+        assert_eq!(
+            stretch_starts(&frames, &[300], (100, 200), (100, 300)),
+            Err("Duplicated frame start at 300".to_string())
+        );
+    }
 }
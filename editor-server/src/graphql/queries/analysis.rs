@@ -0,0 +1,239 @@
+//! Analysis query methods
+
+use crate::types::global::UserContext;
+use crate::utils::position::{get_dancer_tracks, polygon_contains};
+use crate::utils::quaternion::dot;
+
+use async_graphql::{
+    Context, Enum, Error as GQLError, InputObject, Object, Result as GQLResult, SimpleObject,
+};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// Upper bound on the number of sampled timestamps.
+const MAX_SAMPLES: i32 = 100000;
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum PositionIssueKind {
+    Collision,
+    OutOfBounds,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct PositionIssue {
+    pub kind: PositionIssueKind,
+    pub dancer_ids: Vec<i32>,
+    pub start: i32,
+    pub end: i32,
+    /// Smallest distance between the dancers during a collision.
+    pub min_distance: Option<f64>,
+}
+
+#[derive(InputObject, Default)]
+pub struct PositionCheckInput {
+    /// Dancers closer than this on the floor collide.
+    pub threshold: f64,
+    /// Stage outline on the floor (x, y); no boundary check if not given.
+    pub stage: Option<Vec<[f64; 2]>>,
+    /// Time between samples; defaults to 50.
+    pub step: Option<i32>,
+    /// Defaults to the first and last position frames.
+    pub start: Option<i32>,
+    pub end: Option<i32>,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct SpeedSegment {
+    pub dancer_id: i32,
+    pub start: i32,
+    pub end: i32,
+    /// Distance per second between the two frames.
+    pub speed: f64,
+    /// Angle turned per second (in radians) between the two frames.
+    pub angular_speed: f64,
+    /// Ratio of the speeds to their limits; above 1 the segment is flagged.
+    pub severity: f64,
+    pub flagged: bool,
+}
+
+#[derive(InputObject, Default)]
+pub struct SpeedReportInput {
+    pub max_speed: f64,
+    pub max_angular_speed: Option<f64>,
+    /// Only report the flagged segments; defaults to true.
+    pub only_flagged: Option<bool>,
+}
+
+#[derive(Default)]
+pub struct AnalysisQuery;
+
+#[Object]
+impl AnalysisQuery {
+    async fn check_positions(
+        &self,
+        ctx: &Context<'_>,
+        input: PositionCheckInput,
+    ) -> GQLResult<Vec<PositionIssue>> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = &context.clients;
+
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Query: checkPositions");
+
+        let tracks = get_dancer_tracks(mysql).await?;
+
+        let step = input.step.unwrap_or(50);
+        if step <= 0 {
+            return Err(GQLError::new("Step must be positive"));
+        }
+
+        let starts = tracks
+            .iter()
+            .flat_map(|track| track.keys.iter().map(|(start, _)| *start));
+        let start = input.start.unwrap_or(starts.clone().min().unwrap_or(0));
+        let end = input.end.unwrap_or(starts.max().unwrap_or(0));
+
+        if start > end {
+            return Err(GQLError::new("Start must not be larger than end"));
+        }
+        if (end as i64 - start as i64) / step as i64 >= MAX_SAMPLES as i64 {
+            return Err(GQLError::new(format!(
+                "Cannot sample more than {MAX_SAMPLES} timestamps"
+            )));
+        }
+
+        let mut issues = Vec::new();
+        let mut open: HashMap<(PositionIssueKind, i32, i32), PositionIssue> = HashMap::new();
+        let mut last_time = start;
+
+        let times = (start..end)
+            .step_by(step as usize)
+            .chain(std::iter::once(end));
+        for time in times {
+            let poses = tracks
+                .iter()
+                .filter_map(|track| Some((track.dancer_id, track.pose_at(time)?)))
+                .collect::<Vec<_>>();
+
+            let mut active = HashMap::new();
+
+            for (i, (a, pose_a)) in poses.iter().enumerate() {
+                for (b, pose_b) in &poses[i + 1..] {
+                    let distance = (pose_a.location[0] - pose_b.location[0])
+                        .hypot(pose_a.location[1] - pose_b.location[1]);
+                    if distance < input.threshold {
+                        active.insert((PositionIssueKind::Collision, *a, *b), Some(distance));
+                    }
+                }
+
+                if let Some(stage) = &input.stage {
+                    if !polygon_contains(stage, [pose_a.location[0], pose_a.location[1]]) {
+                        active.insert((PositionIssueKind::OutOfBounds, *a, *a), None);
+                    }
+                }
+            }
+
+            // close the issues which are over
+            let closed = open
+                .keys()
+                .filter(|key| !active.contains_key(key))
+                .copied()
+                .collect::<Vec<_>>();
+            for key in closed {
+                if let Some(mut issue) = open.remove(&key) {
+                    issue.end = last_time;
+                    issues.push(issue);
+                }
+            }
+
+            for (key, distance) in active {
+                let (kind, a, b) = key;
+                let issue = open.entry(key).or_insert_with(|| PositionIssue {
+                    kind,
+                    dancer_ids: if a == b { vec![a] } else { vec![a, b] },
+                    start: time,
+                    end: time,
+                    min_distance: distance,
+                });
+                if let (Some(min), Some(distance)) = (issue.min_distance, distance) {
+                    issue.min_distance = Some(min.min(distance));
+                }
+            }
+
+            last_time = time;
+        }
+
+        for (_, mut issue) in open {
+            issue.end = last_time;
+            issues.push(issue);
+        }
+
+        issues.sort_by_key(|issue| (issue.start, issue.dancer_ids.clone()));
+
+        Ok(issues)
+    }
+
+    async fn speed_report(
+        &self,
+        ctx: &Context<'_>,
+        input: SpeedReportInput,
+    ) -> GQLResult<Vec<SpeedSegment>> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = &context.clients;
+
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Query: speedReport");
+
+        if input.max_speed <= 0.0 || input.max_angular_speed.is_some_and(|max| max <= 0.0) {
+            return Err(GQLError::new("Speed limits must be positive"));
+        }
+
+        let tracks = get_dancer_tracks(mysql).await?;
+        let only_flagged = input.only_flagged.unwrap_or(true);
+
+        let mut segments = Vec::new();
+        for track in &tracks {
+            for ((start, from), (end, to)) in track.keys.iter().tuple_windows() {
+                let seconds = (end - start) as f64 / 1000.0;
+
+                let distance = (0..3)
+                    .map(|i| (to.location[i] - from.location[i]).powi(2))
+                    .sum::<f64>()
+                    .sqrt();
+                // the shortest turn between the two orientations
+                let rotation = 2.0
+                    * dot(from.orientation(), to.orientation())
+                        .abs()
+                        .min(1.0)
+                        .acos();
+
+                let speed = distance / seconds;
+                let angular_speed = rotation / seconds;
+
+                let severity = match input.max_angular_speed {
+                    Some(max) => (speed / input.max_speed).max(angular_speed / max),
+                    None => speed / input.max_speed,
+                };
+                let flagged = severity > 1.0;
+
+                if flagged || !only_flagged {
+                    segments.push(SpeedSegment {
+                        dancer_id: track.dancer_id,
+                        start: *start,
+                        end: *end,
+                        speed,
+                        angular_speed,
+                        severity,
+                        flagged,
+                    });
+                }
+            }
+        }
+
+        segments.sort_by(|a, b| b.severity.total_cmp(&a.severity));
+
+        Ok(segments)
+    }
+}
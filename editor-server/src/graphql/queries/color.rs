@@ -1,10 +1,14 @@
 //! Color query methods
 
 use crate::db::types::color::ColorData;
+use crate::graphql::types::color::{ColorControlUsage, ColorEffectUsage, ColorUsage};
 use crate::graphql::types::color_map::{ColorMap, ColorMapScalar};
 use crate::types::global::UserContext;
+use crate::utils::vector::partition_by_field;
 
-use async_graphql::{Context, Object, Result as GQLResult};
+use async_graphql::{Context, Error as GQLError, Object, Result as GQLResult};
+use itertools::Itertools;
+use std::collections::BTreeMap;
 
 #[derive(Default)]
 pub struct ColorQuery;
@@ -37,4 +41,177 @@ impl ColorQuery {
             color_map: ColorMapScalar(result),
         })
     }
+
+    // Every control data, LED effect, gradient and palette referencing a color
+    async fn color_usage(&self, ctx: &Context<'_>, id: i32) -> GQLResult<ColorUsage> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+
+        tracing::info!("Query: colorUsage");
+
+        let mysql = clients.mysql_pool();
+
+        let color = sqlx::query!(
+            r#"
+                SELECT id FROM Color
+                WHERE id = ?;
+            "#,
+            id
+        )
+        .fetch_optional(mysql)
+        .await?;
+
+        if color.is_none() {
+            return Err(GQLError::new(format!("Color #{id} not found")));
+        }
+
+        // (start, dancer_id, part_id) => usage
+        let mut control_data: BTreeMap<(i32, i32, i32), ColorControlUsage> = BTreeMap::new();
+
+        sqlx::query!(
+            r#"
+                SELECT
+                    ControlData.frame_id,
+                    ControlFrame.start,
+                    Dancer.id AS "dancer_id",
+                    Dancer.name AS "dancer_name",
+                    Part.id AS "part_id",
+                    Part.name AS "part_name",
+                    ControlData.type
+                FROM ControlData
+                INNER JOIN ControlFrame ON ControlData.frame_id = ControlFrame.id
+                INNER JOIN Dancer ON ControlData.dancer_id = Dancer.id
+                INNER JOIN Part ON ControlData.part_id = Part.id
+                WHERE ControlData.color_id = ?;
+            "#,
+            id
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .for_each(|data| {
+            control_data.insert(
+                (data.start, data.dancer_id, data.part_id),
+                ColorControlUsage {
+                    frame_id: data.frame_id,
+                    start: data.start,
+                    dancer_id: data.dancer_id,
+                    dancer_name: data.dancer_name,
+                    part_id: data.part_id,
+                    part_name: data.part_name,
+                    r#type: data.r#type,
+                    bulb_positions: Vec::new(),
+                },
+            );
+        });
+
+        let bulbs = sqlx::query!(
+            r#"
+                SELECT
+                    ControlData.id AS "control_id",
+                    ControlData.frame_id,
+                    ControlFrame.start,
+                    Dancer.id AS "dancer_id",
+                    Dancer.name AS "dancer_name",
+                    Part.id AS "part_id",
+                    Part.name AS "part_name",
+                    ControlData.type,
+                    LEDBulb.position
+                FROM LEDBulb
+                INNER JOIN ControlData ON LEDBulb.control_id = ControlData.id
+                INNER JOIN ControlFrame ON ControlData.frame_id = ControlFrame.id
+                INNER JOIN Dancer ON ControlData.dancer_id = Dancer.id
+                INNER JOIN Part ON ControlData.part_id = Part.id
+                WHERE LEDBulb.color_id = ?
+                ORDER BY ControlData.id ASC, LEDBulb.position ASC;
+            "#,
+            id
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        for bulbs in partition_by_field(|bulb| bulb.control_id, bulbs) {
+            let bulb = &bulbs[0];
+            let usage = control_data
+                .entry((bulb.start, bulb.dancer_id, bulb.part_id))
+                .or_insert_with(|| ColorControlUsage {
+                    frame_id: bulb.frame_id,
+                    start: bulb.start,
+                    dancer_id: bulb.dancer_id,
+                    dancer_name: bulb.dancer_name.clone(),
+                    part_id: bulb.part_id,
+                    part_name: bulb.part_name.clone(),
+                    r#type: bulb.r#type.clone(),
+                    bulb_positions: Vec::new(),
+                });
+            usage.bulb_positions = bulbs.iter().map(|bulb| bulb.position).collect_vec();
+        }
+
+        let effect_states = sqlx::query!(
+            r#"
+                SELECT
+                    LEDEffect.id,
+                    LEDEffect.name,
+                    Model.name AS "model_name",
+                    Part.name AS "part_name",
+                    LEDEffectState.position
+                FROM LEDEffectState
+                INNER JOIN LEDEffect ON LEDEffectState.effect_id = LEDEffect.id
+                INNER JOIN Model ON LEDEffect.model_id = Model.id
+                INNER JOIN Part ON LEDEffect.part_id = Part.id
+                WHERE LEDEffectState.color_id = ?
+                ORDER BY LEDEffect.id ASC, LEDEffectState.position ASC;
+            "#,
+            id
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        let led_effects = partition_by_field(|state| state.id, effect_states)
+            .into_iter()
+            .map(|states| ColorEffectUsage {
+                id: states[0].id,
+                name: states[0].name.clone(),
+                model_name: states[0].model_name.clone(),
+                part_name: states[0].part_name.clone(),
+                positions: states.iter().map(|state| state.position).collect_vec(),
+            })
+            .collect_vec();
+
+        let gradient_ids = sqlx::query!(
+            r#"
+                SELECT DISTINCT gradient_id FROM GradientStop
+                WHERE color_id = ?
+                ORDER BY gradient_id ASC;
+            "#,
+            id
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .map(|stop| stop.gradient_id)
+        .collect_vec();
+
+        let palette_ids = sqlx::query!(
+            r#"
+                SELECT DISTINCT palette_id FROM PaletteColor
+                WHERE color_id = ?
+                ORDER BY palette_id ASC;
+            "#,
+            id
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .map(|color| color.palette_id)
+        .collect_vec();
+
+        Ok(ColorUsage {
+            id,
+            control_data: control_data.into_values().collect_vec(),
+            led_effects,
+            gradient_ids,
+            palette_ids,
+        })
+    }
 }
@@ -0,0 +1,38 @@
+//! Gradient query methods
+
+use crate::graphql::types::gradient::Gradient;
+use crate::types::global::UserContext;
+use crate::utils::gradient::{get_gradient, get_gradients};
+
+use async_graphql::{Context, Object, Result as GQLResult};
+use itertools::Itertools;
+
+#[derive(Default)]
+pub struct GradientQuery;
+
+#[Object]
+impl GradientQuery {
+    async fn gradients(&self, ctx: &Context<'_>) -> GQLResult<Vec<Gradient>> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Query: gradients");
+
+        let gradients = get_gradients(mysql).await?;
+
+        Ok(gradients.into_iter().map(Gradient::from).collect_vec())
+    }
+
+    async fn gradient(&self, ctx: &Context<'_>, id: i32) -> GQLResult<Gradient> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Query: gradient");
+
+        let gradient = get_gradient(mysql, id).await?;
+
+        Ok(gradient.into())
+    }
+}
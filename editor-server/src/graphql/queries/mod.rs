@@ -1,22 +1,30 @@
 //! Queries for the GraphQL API.
 
+pub mod analysis;
 pub mod color;
 pub mod control_frame;
 pub mod control_map;
 pub mod dancer;
+pub mod gradient;
 pub mod led;
 pub mod model;
+pub mod palette;
 pub mod position_frame;
 pub mod position_map;
+pub mod validation;
 
+use analysis::*;
 use color::*;
 use control_frame::*;
 use control_map::*;
 use dancer::*;
+use gradient::*;
 use led::*;
 use model::*;
+use palette::*;
 use position_frame::*;
 use position_map::*;
+use validation::*;
 
 #[derive(async_graphql::MergedObject, Default)]
 pub struct QueryRoot(
@@ -28,4 +36,8 @@ pub struct QueryRoot(
     LEDQuery,
     DancerQuery,
     ModelQuery,
+    AnalysisQuery,
+    GradientQuery,
+    PaletteQuery,
+    ValidationQuery,
 );
@@ -0,0 +1,59 @@
+//! Palette query methods
+
+use crate::graphql::types::palette::Palette;
+use crate::types::global::UserContext;
+use crate::utils::palette::{get_palette, get_palettes};
+
+use async_graphql::{Context, Object, Result as GQLResult};
+
+#[derive(Default)]
+pub struct PaletteQuery;
+
+#[Object]
+impl PaletteQuery {
+    async fn palettes(&self, ctx: &Context<'_>) -> GQLResult<Vec<Palette>> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Query: palettes");
+
+        Ok(get_palettes(mysql, None).await?)
+    }
+
+    async fn palette(&self, ctx: &Context<'_>, id: i32) -> GQLResult<Palette> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Query: palette");
+
+        Ok(get_palette(mysql, id).await?)
+    }
+
+    // The palette assigned to the section of the show containing a time
+    async fn palette_at(&self, ctx: &Context<'_>, time: i32) -> GQLResult<Option<Palette>> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Query: paletteAt");
+
+        let section = sqlx::query!(
+            r#"
+                SELECT palette_id FROM PaletteSection
+                WHERE start <= ? AND end >= ?
+                LIMIT 1;
+            "#,
+            time,
+            time
+        )
+        .fetch_optional(mysql)
+        .await?;
+
+        match section {
+            Some(section) => Ok(Some(get_palette(mysql, section.palette_id).await?)),
+            None => Ok(None),
+        }
+    }
+}
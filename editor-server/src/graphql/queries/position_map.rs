@@ -4,9 +4,29 @@ use crate::graphql::types::map::{MapID, PositionMap, PositionMapScalar};
 use crate::types::global::RedisPosition;
 use crate::types::global::UserContext;
 use crate::utils::data::get_redis_position;
-use async_graphql::{Context, InputObject, Object, Result as GQLResult};
+use crate::utils::position::get_dancer_tracks;
+use async_graphql::{Context, Enum, InputObject, Object, Result as GQLResult, SimpleObject};
 use std::collections::HashMap;
 
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Default, Debug)]
+pub enum InterpolationMethod {
+    #[default]
+    Linear,
+    CatmullRom,
+}
+
+/// Positions of every dancer (ordered by id) at a timestamp.
+#[derive(SimpleObject, Default, Debug)]
+pub struct InterpolatedPosition {
+    pub time: i32,
+    pub location: Vec<[f64; 3]>,
+    pub rotation: Vec<[f64; 3]>,
+    /// Rotations as `[w, x, y, z]` quaternions.
+    pub quaternion: Vec<[f64; 4]>,
+    /// False for dancers without any position frame.
+    pub has_position: Vec<bool>,
+}
+
 #[derive(InputObject, Default)]
 pub struct QueryPositionMapInput {
     pub frame_ids: Vec<MapID>,
@@ -70,4 +90,48 @@ impl PositionMapQuery {
             }
         }
     }
+
+    async fn interpolated_positions(
+        &self,
+        ctx: &Context<'_>,
+        timestamps: Vec<i32>,
+        method: Option<InterpolationMethod>,
+    ) -> GQLResult<Vec<InterpolatedPosition>> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = &context.clients;
+
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Query: interpolatedPositions");
+
+        let tracks = get_dancer_tracks(mysql).await?;
+        let method = method.unwrap_or_default();
+
+        let result = timestamps
+            .into_iter()
+            .map(|time| {
+                let mut position = InterpolatedPosition {
+                    time,
+                    ..Default::default()
+                };
+
+                for track in &tracks {
+                    let pose = match method {
+                        InterpolationMethod::Linear => track.pose_at(time),
+                        InterpolationMethod::CatmullRom => track.spline_pose_at(time),
+                    };
+
+                    position.has_position.push(pose.is_some());
+                    let pose = pose.unwrap_or_default();
+                    position.location.push(pose.location);
+                    position.rotation.push(pose.rotation);
+                    position.quaternion.push(pose.orientation());
+                }
+
+                position
+            })
+            .collect();
+
+        Ok(result)
+    }
 }
@@ -0,0 +1,537 @@
+//! Show validation query methods
+
+use crate::db::types::control_data::ControlType;
+use crate::global;
+use crate::types::global::{PartType, UserContext};
+
+use async_graphql::{Context, Enum, Object, Result as GQLResult, SimpleObject};
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum ShowIssueKind {
+    /// The first frame of a part does not set its output.
+    FirstFrameNoEffect,
+    /// A frame has no control data for a part of a dancer.
+    MissingControlData,
+    /// The control type does not fit the part type.
+    InvalidType,
+    /// A COLOR control data has no color.
+    MissingColor,
+    /// An EFFECT control data refers to an effect without states.
+    InvalidEffect,
+    /// An effect made for another model or part.
+    EffectWrongModel,
+    /// An effect has a number of states other than the length of its part.
+    EffectLengthMismatch,
+    /// A LED_BULBS control data has a number of bulbs other than the length of the part.
+    BulbCountMismatch,
+    AlphaOutOfRange,
+    /// A control frame, position frame or LED effect is locked by a user with no connection to
+    /// the server. Connections are tracked by each server process, so this assumes a single
+    /// server instance.
+    OrphanedLock,
+}
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum ShowIssueSeverity {
+    /// frameDat fails or outputs wrong colors.
+    Error,
+    Warning,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct ShowIssue {
+    pub kind: ShowIssueKind,
+    pub severity: ShowIssueSeverity,
+    pub message: String,
+    pub dancer_id: Option<i32>,
+    pub dancer_name: Option<String>,
+    pub part_id: Option<i32>,
+    pub part_name: Option<String>,
+    pub frame_id: Option<i32>,
+    /// Start time of the frame.
+    pub start: Option<i32>,
+    pub effect_id: Option<i32>,
+    pub user_id: Option<i32>,
+}
+
+#[derive(SimpleObject, Clone, Debug, Default)]
+pub struct ShowValidation {
+    /// No errors were found; warnings are allowed.
+    pub ok: bool,
+    pub error_count: i32,
+    pub warning_count: i32,
+    /// Issues ordered by frame time, dancer and part.
+    pub issues: Vec<ShowIssue>,
+}
+
+impl ShowIssue {
+    fn new(kind: ShowIssueKind, message: String) -> Self {
+        let severity = match kind {
+            ShowIssueKind::MissingColor
+            | ShowIssueKind::EffectLengthMismatch
+            | ShowIssueKind::OrphanedLock => ShowIssueSeverity::Warning,
+            _ => ShowIssueSeverity::Error,
+        };
+
+        Self {
+            kind,
+            severity,
+            message,
+            dancer_id: None,
+            dancer_name: None,
+            part_id: None,
+            part_name: None,
+            frame_id: None,
+            start: None,
+            effect_id: None,
+            user_id: None,
+        }
+    }
+}
+
+struct DancerInfo {
+    name: String,
+    model_id: i32,
+}
+
+struct PartInfo {
+    id: i32,
+    name: String,
+    r#type: PartType,
+    length: i32,
+}
+
+#[derive(Default)]
+pub struct ValidationQuery;
+
+#[Object]
+impl ValidationQuery {
+    /// Report the problems of the show that break or change the output of frameDat.
+    ///
+    /// Orphaned locks are found from the connections to this server process, and locks have no
+    /// timestamp to tell how old they are. With several server instances, or while clients
+    /// reconnect after a restart, locks held by connected users are reported as orphaned.
+    async fn validate_show(&self, ctx: &Context<'_>) -> GQLResult<ShowValidation> {
+        let context = ctx.data::<UserContext>()?;
+        let clients = context.clients;
+
+        let mysql = clients.mysql_pool();
+
+        tracing::info!("Query: validateShow");
+
+        let dancers: Vec<(i32, DancerInfo)> = sqlx::query!(
+            r#"
+                SELECT id, name, model_id FROM Dancer
+                ORDER BY id ASC;
+            "#,
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .map(|dancer| {
+            (
+                dancer.id,
+                DancerInfo {
+                    name: dancer.name,
+                    model_id: dancer.model_id,
+                },
+            )
+        })
+        .collect_vec();
+
+        // model_id => parts
+        let mut model_parts: HashMap<i32, Vec<PartInfo>> = HashMap::new();
+        sqlx::query!(
+            r#"
+                SELECT
+                    id,
+                    name,
+                    type AS "type: PartType",
+                    length,
+                    model_id
+                FROM Part
+                ORDER BY id ASC;
+            "#,
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .for_each(|part| {
+            model_parts
+                .entry(part.model_id)
+                .or_default()
+                .push(PartInfo {
+                    id: part.id,
+                    name: part.name,
+                    r#type: part.r#type,
+                    length: part.length.unwrap_or(0),
+                })
+        });
+
+        let frames = sqlx::query!(
+            r#"
+                SELECT id, start FROM ControlFrame
+                ORDER BY start ASC;
+            "#,
+        )
+        .fetch_all(mysql)
+        .await?;
+        let frame_starts: HashMap<i32, i32> =
+            frames.iter().map(|frame| (frame.id, frame.start)).collect();
+
+        let control_data = sqlx::query!(
+            r#"
+                SELECT
+                    id,
+                    dancer_id,
+                    part_id,
+                    frame_id,
+                    type AS "type: ControlType",
+                    fade AS "fade: bool",
+                    color_id,
+                    effect_id,
+                    alpha
+                FROM ControlData;
+            "#,
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        // control_id => (count, min alpha, max alpha)
+        let bulbs: HashMap<i32, (i64, Option<i32>, Option<i32>)> = sqlx::query!(
+            r#"
+                SELECT
+                    control_id,
+                    COUNT(*) AS "count: i64",
+                    MIN(alpha) AS "min_alpha: i32",
+                    MAX(alpha) AS "max_alpha: i32"
+                FROM LEDBulb
+                GROUP BY control_id;
+            "#,
+        )
+        .fetch_all(mysql)
+        .await?
+        .into_iter()
+        .map(|bulbs| {
+            (
+                bulbs.control_id,
+                (bulbs.count, bulbs.min_alpha, bulbs.max_alpha),
+            )
+        })
+        .collect();
+
+        let effects = sqlx::query!(
+            r#"
+                SELECT
+                    LEDEffect.id,
+                    LEDEffect.name,
+                    LEDEffect.model_id,
+                    LEDEffect.part_id,
+                    Part.length,
+                    COUNT(LEDEffectState.id) AS "count: i64",
+                    MIN(LEDEffectState.alpha) AS "min_alpha: i32",
+                    MAX(LEDEffectState.alpha) AS "max_alpha: i32"
+                FROM LEDEffect
+                INNER JOIN Part ON LEDEffect.part_id = Part.id
+                LEFT JOIN LEDEffectState ON LEDEffectState.effect_id = LEDEffect.id
+                GROUP BY LEDEffect.id
+                ORDER BY LEDEffect.id ASC;
+            "#,
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        let locks = sqlx::query!(
+            r#"
+                SELECT
+                    EditingControlFrame.user_id,
+                    ControlFrame.id AS "frame_id",
+                    ControlFrame.start
+                FROM EditingControlFrame
+                INNER JOIN ControlFrame ON EditingControlFrame.frame_id = ControlFrame.id;
+            "#,
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        let position_locks = sqlx::query!(
+            r#"
+                SELECT
+                    EditingPositionFrame.user_id,
+                    PositionFrame.id AS "frame_id",
+                    PositionFrame.start
+                FROM EditingPositionFrame
+                INNER JOIN PositionFrame ON EditingPositionFrame.frame_id = PositionFrame.id;
+            "#,
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        let effect_locks = sqlx::query!(
+            r#"
+                SELECT
+                    EditingLEDEffect.user_id,
+                    LEDEffect.id AS "effect_id",
+                    LEDEffect.name
+                FROM EditingLEDEffect
+                INNER JOIN LEDEffect ON EditingLEDEffect.led_effect_id = LEDEffect.id;
+            "#,
+        )
+        .fetch_all(mysql)
+        .await?;
+
+        let dancer_map: HashMap<i32, &DancerInfo> =
+            dancers.iter().map(|(id, dancer)| (*id, dancer)).collect();
+        let part_map: HashMap<i32, &PartInfo> = model_parts
+            .values()
+            .flatten()
+            .map(|part| (part.id, part))
+            .collect();
+        let effect_map: HashMap<i32, _> =
+            effects.iter().map(|effect| (effect.id, effect)).collect();
+
+        let mut issues = Vec::new();
+
+        let at =
+            |kind: ShowIssueKind, message: String, dancer_id: i32, part_id: i32, frame_id: i32| {
+                ShowIssue {
+                    dancer_id: Some(dancer_id),
+                    dancer_name: dancer_map.get(&dancer_id).map(|dancer| dancer.name.clone()),
+                    part_id: Some(part_id),
+                    part_name: part_map.get(&part_id).map(|part| part.name.clone()),
+                    frame_id: Some(frame_id),
+                    start: frame_starts.get(&frame_id).copied(),
+                    ..ShowIssue::new(kind, message)
+                }
+            };
+
+        // frames missing control data of a dancer part
+        let existing: HashSet<(i32, i32, i32)> = control_data
+            .iter()
+            .map(|data| (data.frame_id, data.dancer_id, data.part_id))
+            .collect();
+
+        for frame in &frames {
+            for (dancer_id, dancer) in &dancers {
+                for part in model_parts.get(&dancer.model_id).into_iter().flatten() {
+                    if !existing.contains(&(frame.id, *dancer_id, part.id)) {
+                        issues.push(at(
+                            ShowIssueKind::MissingControlData,
+                            "No control data".to_string(),
+                            *dancer_id,
+                            part.id,
+                            frame.id,
+                        ));
+                    }
+                }
+            }
+        }
+
+        let first_frame_id = frames.first().map(|frame| frame.id);
+
+        for data in &control_data {
+            let (dancer, part) =
+                match (dancer_map.get(&data.dancer_id), part_map.get(&data.part_id)) {
+                    (Some(dancer), Some(part)) => (dancer, part),
+                    _ => continue,
+                };
+            let issue = |kind: ShowIssueKind, message: String| {
+                at(kind, message, data.dancer_id, data.part_id, data.frame_id)
+            };
+
+            if Some(data.frame_id) == first_frame_id {
+                let message = match (&data.r#type, data.effect_id, data.fade) {
+                    (ControlType::NoEffect, _, _) => Some("First frame can't be no effect"),
+                    (ControlType::Effect, None, _) if part.r#type == PartType::LED => {
+                        Some("First frame can't keep the previous effect")
+                    }
+                    (_, _, None) => Some("First frame has no fade"),
+                    _ => None,
+                };
+                if let Some(message) = message {
+                    issues.push(issue(
+                        ShowIssueKind::FirstFrameNoEffect,
+                        message.to_string(),
+                    ));
+                }
+            }
+
+            let valid_type = matches!(
+                (&data.r#type, part.r#type),
+                (ControlType::NoEffect, _)
+                    | (ControlType::Color, PartType::FIBER)
+                    | (ControlType::Effect | ControlType::LEDBulbs, PartType::LED)
+            );
+            if !valid_type {
+                issues.push(issue(
+                    ShowIssueKind::InvalidType,
+                    format!("{:?} on a {:?} part", data.r#type, part.r#type),
+                ));
+                continue;
+            }
+
+            if let Some(alpha) = data.alpha.filter(|alpha| !(0..=255).contains(alpha)) {
+                issues.push(issue(
+                    ShowIssueKind::AlphaOutOfRange,
+                    format!("Alpha {alpha} is not in [0, 255]"),
+                ));
+            }
+
+            match data.r#type {
+                ControlType::Color if data.color_id.is_none() => {
+                    issues.push(issue(
+                        ShowIssueKind::MissingColor,
+                        "No color, the part is black".to_string(),
+                    ));
+                }
+                ControlType::Effect => {
+                    let effect = match data.effect_id.and_then(|id| effect_map.get(&id)) {
+                        Some(effect) => effect,
+                        None => continue,
+                    };
+
+                    if effect.model_id != dancer.model_id || effect.part_id != part.id {
+                        let effect_part = part_map
+                            .get(&effect.part_id)
+                            .map_or("unknown part".to_string(), |part| part.name.clone());
+                        issues.push(ShowIssue {
+                            effect_id: Some(effect.id),
+                            ..issue(
+                                ShowIssueKind::EffectWrongModel,
+                                format!(
+                                    "Effect {} is made for model #{} part {}",
+                                    effect.name, effect.model_id, effect_part
+                                ),
+                            )
+                        });
+                    } else if effect.count == 0 {
+                        issues.push(ShowIssue {
+                            effect_id: Some(effect.id),
+                            ..issue(
+                                ShowIssueKind::InvalidEffect,
+                                format!("Effect {} has no states", effect.name),
+                            )
+                        });
+                    }
+                }
+                ControlType::LEDBulbs => {
+                    let (count, min_alpha, max_alpha) =
+                        bulbs.get(&data.id).copied().unwrap_or((0, None, None));
+
+                    if count != part.length as i64 {
+                        issues.push(issue(
+                            ShowIssueKind::BulbCountMismatch,
+                            format!("{} bulbs for a part of length {}", count, part.length),
+                        ));
+                    }
+                    if min_alpha.is_some_and(|alpha| alpha < 0)
+                        || max_alpha.is_some_and(|alpha| alpha > 255)
+                    {
+                        issues.push(issue(
+                            ShowIssueKind::AlphaOutOfRange,
+                            "Alpha of a bulb is not in [0, 255]".to_string(),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for effect in &effects {
+            let effect_issue = |kind: ShowIssueKind, message: String| ShowIssue {
+                part_id: Some(effect.part_id),
+                part_name: part_map.get(&effect.part_id).map(|part| part.name.clone()),
+                effect_id: Some(effect.id),
+                ..ShowIssue::new(kind, message)
+            };
+
+            let length = effect.length.unwrap_or(0) as i64;
+            if effect.count > 0 && effect.count != length {
+                issues.push(effect_issue(
+                    ShowIssueKind::EffectLengthMismatch,
+                    format!(
+                        "Effect {} has {} states for a part of length {}",
+                        effect.name, effect.count, length
+                    ),
+                ));
+            }
+            if effect.min_alpha.is_some_and(|alpha| alpha < 0)
+                || effect.max_alpha.is_some_and(|alpha| alpha > 255)
+            {
+                issues.push(effect_issue(
+                    ShowIssueKind::AlphaOutOfRange,
+                    format!(
+                        "Alpha of a state of effect {} is not in [0, 255]",
+                        effect.name
+                    ),
+                ));
+            }
+        }
+
+        for lock in &locks {
+            if !global::sessions::is_connected(lock.user_id) {
+                issues.push(ShowIssue {
+                    frame_id: Some(lock.frame_id),
+                    start: Some(lock.start),
+                    user_id: Some(lock.user_id),
+                    ..ShowIssue::new(
+                        ShowIssueKind::OrphanedLock,
+                        format!(
+                            "Frame is locked by user #{} who is not connected to this server",
+                            lock.user_id
+                        ),
+                    )
+                });
+            }
+        }
+
+        for lock in &position_locks {
+            if !global::sessions::is_connected(lock.user_id) {
+                issues.push(ShowIssue {
+                    start: Some(lock.start),
+                    user_id: Some(lock.user_id),
+                    ..ShowIssue::new(
+                        ShowIssueKind::OrphanedLock,
+                        format!(
+                            "Position frame #{} is locked by user #{} who is not connected to this server",
+                            lock.frame_id, lock.user_id
+                        ),
+                    )
+                });
+            }
+        }
+
+        for lock in &effect_locks {
+            if !global::sessions::is_connected(lock.user_id) {
+                issues.push(ShowIssue {
+                    effect_id: Some(lock.effect_id),
+                    user_id: Some(lock.user_id),
+                    ..ShowIssue::new(
+                        ShowIssueKind::OrphanedLock,
+                        format!(
+                            "Effect {} is locked by user #{} who is not connected to this server",
+                            lock.name, lock.user_id
+                        ),
+                    )
+                });
+            }
+        }
+
+        issues.sort_by_key(|issue| (issue.start, issue.dancer_id, issue.part_id));
+
+        let error_count = issues
+            .iter()
+            .filter(|issue| issue.severity == ShowIssueSeverity::Error)
+            .count() as i32;
+        let warning_count = issues.len() as i32 - error_count;
+
+        Ok(ShowValidation {
+            ok: error_count == 0,
+            error_count,
+            warning_count,
+            issues,
+        })
+    }
+}
@@ -0,0 +1,37 @@
+//! Gradient subscription methods.
+
+use crate::graphql::subscriptor::Subscriptor;
+use crate::graphql::types::gradient::Gradient;
+
+use async_graphql::{Enum, SimpleObject, Subscription};
+use futures_core::stream::Stream;
+use serde::{Deserialize, Serialize};
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub enum GradientMutationMode {
+    #[default]
+    #[serde(rename = "UPDATED")]
+    Updated,
+    #[serde(rename = "CREATED")]
+    Created,
+    #[serde(rename = "DELETED")]
+    Deleted,
+}
+
+#[derive(SimpleObject, Clone, Default)]
+pub struct GradientPayload {
+    pub mutation: GradientMutationMode,
+    pub id: i32,
+    pub gradient: Option<Gradient>,
+    pub edit_by: i32,
+}
+
+#[derive(Default)]
+pub struct GradientSubscription;
+
+#[Subscription]
+impl GradientSubscription {
+    async fn gradient_subscription(&self) -> impl Stream<Item = GradientPayload> {
+        Subscriptor::<GradientPayload>::subscribe()
+    }
+}
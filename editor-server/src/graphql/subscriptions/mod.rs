@@ -3,7 +3,9 @@ pub mod color;
 pub mod control_map;
 pub mod control_record;
 pub mod dancer;
+pub mod gradient;
 pub mod led;
+pub mod palette;
 pub mod position_map;
 pub mod position_record;
 
@@ -11,7 +13,9 @@ use color::*;
 use control_map::*;
 use control_record::*;
 use dancer::*;
+use gradient::*;
 use led::*;
+use palette::*;
 use position_map::*;
 use position_record::*;
 
@@ -24,4 +28,6 @@ pub struct SubscriptionRoot(
     PositionRecordSubscription,
     LEDSubscription,
     DancerSubscription,
+    GradientSubscription,
+    PaletteSubscription,
 );
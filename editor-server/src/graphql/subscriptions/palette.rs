@@ -0,0 +1,37 @@
+//! Palette subscription methods.
+
+use crate::graphql::subscriptor::Subscriptor;
+use crate::graphql::types::palette::Palette;
+
+use async_graphql::{Enum, SimpleObject, Subscription};
+use futures_core::stream::Stream;
+use serde::{Deserialize, Serialize};
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub enum PaletteMutationMode {
+    #[default]
+    #[serde(rename = "UPDATED")]
+    Updated,
+    #[serde(rename = "CREATED")]
+    Created,
+    #[serde(rename = "DELETED")]
+    Deleted,
+}
+
+#[derive(SimpleObject, Clone, Default)]
+pub struct PalettePayload {
+    pub mutation: PaletteMutationMode,
+    pub id: i32,
+    pub palette: Option<Palette>,
+    pub edit_by: i32,
+}
+
+#[derive(Default)]
+pub struct PaletteSubscription;
+
+#[Subscription]
+impl PaletteSubscription {
+    async fn palette_subscription(&self) -> impl Stream<Item = PalettePayload> {
+        Subscriptor::<PalettePayload>::subscribe()
+    }
+}
@@ -1,17 +1,153 @@
 //! Color type.
 
 use crate::db::types::color::ColorData;
+use crate::utils::color::{
+    hex_to_rgb, hsl_to_rgb, hsv_to_rgb, kelvin_to_rgb, rgb_to_hex, rgb_to_hsl, rgb_to_hsv,
+    rgb_to_kelvin, KELVIN_RANGE,
+};
 
-use async_graphql::SimpleObject;
+use async_graphql::{ComplexObject, InputObject, SimpleObject};
 use serde::{Deserialize, Serialize};
 
 #[derive(SimpleObject, Serialize, Deserialize, Default)]
+#[graphql(complex)]
 pub struct Color {
     pub id: i32,
     pub color: String,
     pub color_code: Vec<i32>,
 }
 
+/// Hue in degrees, saturation and value from 0 to 1.
+#[derive(SimpleObject, Default, Debug, Clone, Copy)]
+#[graphql(name = "HSV")]
+pub struct Hsv {
+    pub h: f64,
+    pub s: f64,
+    pub v: f64,
+}
+
+/// Hue in degrees, saturation and lightness from 0 to 1.
+#[derive(SimpleObject, Default, Debug, Clone, Copy)]
+#[graphql(name = "HSL")]
+pub struct Hsl {
+    pub h: f64,
+    pub s: f64,
+    pub l: f64,
+}
+
+#[ComplexObject]
+impl Color {
+    /// Hex code, e.g. `#ff8000`.
+    async fn hex(&self) -> String {
+        rgb_to_hex(self.rgb())
+    }
+
+    async fn hsv(&self) -> Hsv {
+        let [h, s, v] = rgb_to_hsv(self.rgb());
+        Hsv { h: h * 360.0, s, v }
+    }
+
+    async fn hsl(&self) -> Hsl {
+        let [h, s, l] = rgb_to_hsl(self.rgb());
+        Hsl { h: h * 360.0, s, l }
+    }
+
+    /// The closest white color temperature in Kelvin, ignoring brightness.
+    async fn kelvin(&self) -> f64 {
+        rgb_to_kelvin(self.rgb())
+    }
+}
+
+impl Color {
+    fn rgb(&self) -> [i32; 3] {
+        let mut rgb = [0; 3];
+        for (i, value) in rgb.iter_mut().enumerate() {
+            *value = self.color_code.get(i).copied().unwrap_or(0);
+        }
+        rgb
+    }
+}
+
+/// Hue in degrees, saturation and value from 0 to 1.
+#[derive(InputObject, Default, Debug, Clone, Copy)]
+#[graphql(name = "HSVInput")]
+pub struct HsvInput {
+    pub h: f64,
+    pub s: f64,
+    pub v: f64,
+}
+
+/// Hue in degrees, saturation and lightness from 0 to 1.
+#[derive(InputObject, Default, Debug, Clone, Copy)]
+#[graphql(name = "HSLInput")]
+pub struct HslInput {
+    pub h: f64,
+    pub s: f64,
+    pub l: f64,
+}
+
+/// Resolve a color code given in exactly one of the color spaces to RGB.
+pub fn resolve_color_code(
+    rgb: Option<&[i32]>,
+    hsv: Option<HsvInput>,
+    hsl: Option<HslInput>,
+    hex: Option<&str>,
+    kelvin: Option<f64>,
+) -> Result<[i32; 3], String> {
+    let given = [
+        rgb.is_some(),
+        hsv.is_some(),
+        hsl.is_some(),
+        hex.is_some(),
+        kelvin.is_some(),
+    ];
+    if given.iter().filter(|given| **given).count() != 1 {
+        return Err("Exactly one of set, hsv, hsl, hex and kelvin must be given".to_string());
+    }
+
+    let unit = |name: &str, value: f64| {
+        if (0.0..=1.0).contains(&value) {
+            Ok(value)
+        } else {
+            Err(format!("{name} must be in [0, 1]"))
+        }
+    };
+
+    if let Some(rgb) = rgb {
+        return match rgb {
+            [r, g, b] if rgb.iter().all(|c| (0..=255).contains(c)) => Ok([*r, *g, *b]),
+            _ => Err("Color code must be 3 values in [0, 255]".to_string()),
+        };
+    }
+    if let Some(HsvInput { h, s, v }) = hsv {
+        return Ok(hsv_to_rgb(
+            h / 360.0,
+            unit("Saturation", s)?,
+            unit("Value", v)?,
+        ));
+    }
+    if let Some(HslInput { h, s, l }) = hsl {
+        return Ok(hsl_to_rgb(
+            h / 360.0,
+            unit("Saturation", s)?,
+            unit("Lightness", l)?,
+        ));
+    }
+    if let Some(hex) = hex {
+        return hex_to_rgb(hex);
+    }
+
+    match kelvin {
+        Some(kelvin) if (KELVIN_RANGE.0..=KELVIN_RANGE.1).contains(&kelvin) => {
+            Ok(kelvin_to_rgb(kelvin))
+        }
+        _ => Err(format!(
+            "Kelvin must be in [{}, {}]",
+            KELVIN_RANGE.0, KELVIN_RANGE.1
+        )),
+    }
+}
+
 impl From<ColorData> for Color {
     fn from(data: ColorData) -> Self {
         Self {
@@ -21,3 +157,37 @@ impl From<ColorData> for Color {
         }
     }
 }
+
+/// Control data of a dancer part referencing a color, either directly or in its bulbs.
+#[derive(SimpleObject, Clone, Default, Debug)]
+pub struct ColorControlUsage {
+    pub frame_id: i32,
+    pub start: i32,
+    pub dancer_id: i32,
+    pub dancer_name: String,
+    pub part_id: i32,
+    pub part_name: String,
+    /// `COLOR`, `LED_BULBS` or `NO_EFFECT`.
+    pub r#type: String,
+    /// Positions of the bulbs with the color, empty if not `LED_BULBS`.
+    pub bulb_positions: Vec<i32>,
+}
+
+#[derive(SimpleObject, Clone, Default, Debug)]
+pub struct ColorEffectUsage {
+    pub id: i32,
+    pub name: String,
+    pub model_name: String,
+    pub part_name: String,
+    pub positions: Vec<i32>,
+}
+
+#[derive(SimpleObject, Clone, Default, Debug)]
+pub struct ColorUsage {
+    pub id: i32,
+    pub control_data: Vec<ColorControlUsage>,
+    #[graphql(name = "LEDEffects")]
+    pub led_effects: Vec<ColorEffectUsage>,
+    pub gradient_ids: Vec<i32>,
+    pub palette_ids: Vec<i32>,
+}
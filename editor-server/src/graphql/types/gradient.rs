@@ -0,0 +1,51 @@
+//! Gradient type.
+
+use crate::db::types::gradient::{GradientEasing, GradientSpace};
+use crate::utils::gradient;
+
+use async_graphql::{InputObject, SimpleObject};
+
+#[derive(SimpleObject, Clone, Default, Debug)]
+pub struct GradientStop {
+    /// From 0 (first bulb) to 1 (last bulb).
+    pub position: f64,
+    pub color_id: i32,
+    pub alpha: i32,
+}
+
+#[derive(SimpleObject, Clone, Default, Debug)]
+pub struct Gradient {
+    pub id: i32,
+    pub name: String,
+    pub space: GradientSpace,
+    pub easing: GradientEasing,
+    pub stops: Vec<GradientStop>,
+}
+
+#[derive(InputObject, Default, Debug, Clone)]
+pub struct GradientStopInput {
+    pub position: f64,
+    pub color_id: i32,
+    /// Defaults to 255.
+    pub alpha: Option<i32>,
+}
+
+impl From<gradient::Gradient> for Gradient {
+    fn from(data: gradient::Gradient) -> Self {
+        Self {
+            id: data.data.id,
+            name: data.data.name,
+            space: data.data.space,
+            easing: data.data.easing,
+            stops: data
+                .stops
+                .into_iter()
+                .map(|stop| GradientStop {
+                    position: stop.position,
+                    color_id: stop.color_id,
+                    alpha: stop.alpha,
+                })
+                .collect(),
+        }
+    }
+}
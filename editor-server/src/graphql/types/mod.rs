@@ -5,9 +5,12 @@ pub mod color_map;
 pub mod control_data;
 pub mod control_frame;
 pub mod dancer;
+pub mod gradient;
 pub mod led;
 pub mod led_map;
 pub mod map;
 pub mod model;
+pub mod palette;
 pub mod pos_data;
 pub mod pos_frame;
+pub mod scope;
@@ -0,0 +1,33 @@
+//! Palette type.
+
+use async_graphql::{InputObject, SimpleObject};
+
+#[derive(SimpleObject, Clone, Default, Debug)]
+pub struct PaletteColor {
+    /// Role of the color in the palette, e.g. `primary` or `accent`.
+    pub role: String,
+    pub color_id: i32,
+}
+
+#[derive(SimpleObject, Clone, Default, Debug)]
+pub struct PaletteSection {
+    pub id: i32,
+    pub start: i32,
+    pub end: i32,
+}
+
+#[derive(SimpleObject, Clone, Default, Debug)]
+pub struct Palette {
+    pub id: i32,
+    pub name: String,
+    /// Colors in order.
+    pub colors: Vec<PaletteColor>,
+    /// Sections of the show the palette is assigned to.
+    pub sections: Vec<PaletteSection>,
+}
+
+#[derive(InputObject, Default, Debug, Clone)]
+pub struct PaletteColorInput {
+    pub role: String,
+    pub color_id: i32,
+}
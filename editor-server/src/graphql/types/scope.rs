@@ -0,0 +1,82 @@
+//! Scope type for bulk edits on control data.
+
+use crate::types::global::PartType;
+
+use async_graphql::InputObject;
+
+#[derive(InputObject, Default, Debug, Clone)]
+pub struct ControlScopeInput {
+    /// Dancers in scope; every dancer if not given.
+    pub dancer_ids: Option<Vec<i32>>,
+    /// Parts in scope, matched by name; every part if not given.
+    pub part_names: Option<Vec<String>>,
+    /// Restrict the scope to LED or FIBER parts.
+    pub part_type: Option<PartType>,
+    /// Frames with start in [start, end] are in scope; defaults to the whole show.
+    pub start: Option<i32>,
+    pub end: Option<i32>,
+}
+
+impl ControlScopeInput {
+    pub fn range(&self) -> Result<(i32, i32), String> {
+        let start = self.start.unwrap_or(0);
+        let end = self.end.unwrap_or(i32::MAX);
+
+        if start > end {
+            return Err("Start must not be larger than end".to_string());
+        }
+
+        Ok((start, end))
+    }
+
+    pub fn contains(&self, dancer_id: i32, part_name: &str, part_type: PartType) -> bool {
+        self.dancer_ids
+            .as_ref()
+            .map_or(true, |ids| ids.contains(&dancer_id))
+            && self
+                .part_names
+                .as_ref()
+                .map_or(true, |names| names.iter().any(|name| name == part_name))
+            && self.part_type.map_or(true, |r#type| r#type == part_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_scope_contains_everything() {
+        let scope = ControlScopeInput::default();
+        assert!(scope.contains(1, "head", PartType::LED));
+        assert!(scope.contains(2, "arm", PartType::FIBER));
+        assert_eq!(scope.range(), Ok((0, i32::MAX)));
+    }
+
+    #[test]
+    fn every_filter_applies() {
+        let scope = ControlScopeInput {
+            dancer_ids: Some(vec![1, 2]),
+            part_names: Some(vec!["head".to_string(), "arm".to_string()]),
+            part_type: Some(PartType::LED),
+            ..Default::default()
+        };
+        assert!(scope.contains(2, "arm", PartType::LED));
+        assert!(!scope.contains(3, "arm", PartType::LED));
+        assert!(!scope.contains(2, "leg", PartType::LED));
+        assert!(!scope.contains(2, "arm", PartType::FIBER));
+    }
+
+    #[test]
+    fn range_must_be_ordered() {
+        let scope = ControlScopeInput {
+            start: Some(200),
+            end: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(
+            scope.range(),
+            Err("Start must not be larger than end".to_string())
+        );
+    }
+}
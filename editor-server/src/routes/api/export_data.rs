@@ -350,6 +350,7 @@ pub async fn export_data(
                 start: position_frame.start,
                 location: redis_position.location,
                 rotation: redis_position.rotation,
+                quaternion: redis_position.quaternion,
                 has_position: redis_position.has_position,
             },
         );
@@ -63,43 +63,82 @@ fn write_checksum(frame: &mut FrameData) {
     frame.checksum = checksum;
 }
 
-fn interpolate_no_change_effects(
-    frames: &mut [FrameData],
-    intervals: &HashMap<i32, Vec<(usize, usize)>>,
+/// Fade of a frame, held from the previous frame when unset.
+pub(super) fn frame_fade(fade: Option<i8>, previous: Option<u8>) -> Option<u8> {
+    fade.map(|fade| fade as u8).or(previous)
+}
+
+/// Intervals of frames, as `[left, right)` frame indices, fading from the colors of a LED part
+/// at `left` to its colors at `right`.
+///
+/// `left` is the first frame where the part holds a no-change effect while fading, and every
+/// later frame without one closes an interval from there.
+#[derive(Debug, Default)]
+pub(super) struct NoChangeIntervals {
+    // (part_id, left)
+    left: HashMap<i32, usize>,
+    // (part_id, [[l, r)])
+    intervals: HashMap<i32, Vec<(usize, usize)>>,
+}
+
+impl NoChangeIntervals {
+    /// Record whether the part holds a fading no-change effect at frame `index`.
+    pub(super) fn push(&mut self, part_id: i32, index: usize, no_change: bool) {
+        if no_change {
+            self.left.entry(part_id).or_insert(index);
+        } else if let Some(left) = self.left.get(&part_id) {
+            self.intervals
+                .entry(part_id)
+                .or_default()
+                .push((*left, index));
+        }
+    }
+
+    pub(super) fn iter(&self) -> impl Iterator<Item = (i32, (usize, usize))> + '_ {
+        self.intervals
+            .iter()
+            .flat_map(|(part_id, intervals)| intervals.iter().map(|interval| (*part_id, *interval)))
+    }
+}
+
+/// Set `colors` to the `step`-th of `len` frames fading from `left` to `right`.
+pub(super) fn interpolate_no_change(
+    colors: &mut [Color],
+    left: &[Color],
+    right: &[Color],
+    step: usize,
+    len: usize,
 ) {
-    for (part_id, part_intervals) in intervals {
-        for interval in part_intervals {
-            let left_color = frames[interval.0]
-                .led_grb_data
-                .get(part_id)
-                .unwrap()
-                .clone();
-            let right_color = frames[interval.1]
+    for (j, color) in colors.iter_mut().enumerate() {
+        *color = [
+            (left[j][0] * (len - step) as i32 + right[j][0] * step as i32) / len as i32,
+            (left[j][1] * (len - step) as i32 + right[j][1] * step as i32) / len as i32,
+            (left[j][2] * (len - step) as i32 + right[j][2] * step as i32) / len as i32,
+        ]
+    }
+}
+
+fn interpolate_no_change_effects(frames: &mut [FrameData], intervals: &NoChangeIntervals) {
+    for (part_id, interval) in intervals.iter() {
+        let left_color = frames[interval.0]
+            .led_grb_data
+            .get(&part_id)
+            .unwrap()
+            .clone();
+        let right_color = frames[interval.1]
+            .led_grb_data
+            .get(&part_id)
+            .unwrap()
+            .clone();
+        let len = interval.1 - interval.0;
+
+        #[allow(clippy::needless_range_loop)]
+        for i in 0..len {
+            let colors = frames[interval.0 + i]
                 .led_grb_data
-                .get(part_id)
-                .unwrap()
-                .clone();
-            let len = interval.1 - interval.0;
-
-            #[allow(clippy::needless_range_loop)]
-            for i in 0..len {
-                for (j, color) in frames[interval.0 + i]
-                    .led_grb_data
-                    .get_mut(part_id)
-                    .unwrap()
-                    .iter_mut()
-                    .enumerate()
-                {
-                    *color = [
-                        (left_color[j][0] * (len - i) as i32 + right_color[j][0] * i as i32)
-                            / len as i32,
-                        (left_color[j][1] * (len - i) as i32 + right_color[j][1] * i as i32)
-                            / len as i32,
-                        (left_color[j][2] * (len - i) as i32 + right_color[j][2] * i as i32)
-                            / len as i32,
-                    ]
-                }
-            }
+                .get_mut(&part_id)
+                .unwrap();
+            interpolate_no_change(colors, &left_color, &right_color, i, len);
         }
     }
 }
@@ -457,23 +496,14 @@ pub async fn frame_dat(
 
     let mut frames: Vec<FrameData> = Vec::new();
 
-    // ((frame_id, part_id), [[l, r)])
-    let mut no_change_intervals: HashMap<i32, Vec<(usize, usize)>> = HashMap::new();
-    let mut no_change_intervals_left: HashMap<i32, i32> = HashMap::new();
+    let mut no_change_intervals = NoChangeIntervals::default();
 
     for (i, frame) in control_frames.iter().enumerate() {
         let frame_id = frame[0].id;
         let start_time = frame[0].start as u32;
-        let fade: u8 = match frame[0].fade {
-            Some(f) => f as u8,
-            None => {
-                frames
-                    .last()
-                    .ok_or("first frame can't be no effect")
-                    .into_result()?
-                    .fade
-            }
-        };
+        let fade: u8 = frame_fade(frame[0].fade, frames.last().map(|frame| frame.fade))
+            .ok_or("first frame can't be no effect")
+            .into_result()?;
 
         let mut no_effect: HashSet<i32> = HashSet::new();
 
@@ -535,20 +565,11 @@ pub async fn frame_dat(
 
             led.insert(led_part.id, color);
 
-            if no_change_parts.contains(&(frame_id, led_part.id)) && fade == 1 {
-                if *no_change_intervals_left.entry(led_part.id).or_insert(-1) == -1 {
-                    *no_change_intervals_left.get_mut(&led_part.id).unwrap() = i as i32;
-                }
-            } else {
-                let left = no_change_intervals_left.entry(led_part.id).or_insert(-1);
-
-                if *left != -1 {
-                    no_change_intervals
-                        .entry(led_part.id)
-                        .or_default()
-                        .push((*left as usize, i));
-                }
-            }
+            no_change_intervals.push(
+                led_part.id,
+                i,
+                no_change_parts.contains(&(frame_id, led_part.id)) && fade == 1,
+            );
         }
 
         let frame_data = FrameData {
@@ -618,3 +639,31 @@ pub async fn test_frame_dat(
     }))
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_change_intervals_keep_their_left_frame() {
+        let mut intervals = NoChangeIntervals::default();
+        let no_change = [false, true, true, false, false, true, false];
+        for (index, no_change) in no_change.into_iter().enumerate() {
+            intervals.push(1, index, no_change);
+        }
+
+        let found = intervals.iter().map(|(_, interval)| interval).collect_vec();
+        assert_eq!(found, vec![(1, 3), (1, 4), (1, 6)]);
+    }
+
+    #[test]
+    fn no_change_interpolation() {
+        let mut colors = vec![[0, 0, 0]; 2];
+        let (left, right) = ([[0, 100, 255], [10, 10, 10]], [[100, 0, 0], [10, 10, 10]]);
+
+        interpolate_no_change(&mut colors, &left, &right, 0, 4);
+        assert_eq!(colors, left);
+        interpolate_no_change(&mut colors, &left, &right, 1, 4);
+        assert_eq!(colors, vec![[25, 75, 191], [10, 10, 10]]);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::global;
+
+use axum::{
+    body::Bytes,
+    extract::Query,
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::Json,
+};
+use image::{ImageFormat, RgbImage};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+use super::{
+    lights::{get_light_tracks, LightTrack},
+    types::GetDataFailedResponse,
+    utils::IntoResult,
+};
+
+/// Upper bound on the width of the image.
+const MAX_WIDTH: i32 = 20000;
+/// Upper bound on the number of pixels of the image.
+const MAX_PIXELS: u64 = 1 << 26;
+/// Color of the separators between dancers.
+const SEPARATOR: image::Rgb<u8> = image::Rgb([64, 64, 64]);
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HeatMapQuery {
+    /// Name of a dancer; every dancer if not given.
+    pub dancer: Option<String>,
+    /// Time range; defaults to the first and last control frames.
+    pub start: Option<i32>,
+    pub end: Option<i32>,
+    /// Pixels per second of show time; defaults to 10.
+    #[serde(rename = "pixelsPerSecond")]
+    pub pixels_per_second: Option<f64>,
+    /// Height of every part row; defaults to 8.
+    #[serde(rename = "rowHeight")]
+    pub row_height: Option<u32>,
+}
+
+fn bad_request(err: String) -> (StatusCode, Json<GetDataFailedResponse>) {
+    (StatusCode::BAD_REQUEST, Json(GetDataFailedResponse { err }))
+}
+
+/// Height of the image, a row per part and a line between dancers.
+fn image_height(dancers: &[(String, Vec<LightTrack>)], row_height: u32) -> u64 {
+    let rows = dancers
+        .iter()
+        .map(|(_, tracks)| tracks.len() as u64)
+        .sum::<u64>();
+    let separators = dancers.len().saturating_sub(1) as u64;
+    rows * row_height as u64 + separators
+}
+
+/// Render a row per part and a column per sampled time, dancers separated by a gray line.
+fn render_heat_map(
+    dancers: &[(String, Vec<LightTrack>)],
+    times: &[i32],
+    row_height: u32,
+) -> RgbImage {
+    let height = image_height(dancers, row_height) as u32;
+
+    let mut image = RgbImage::from_pixel(times.len().max(1) as u32, height.max(1), SEPARATOR);
+
+    for (x, time) in times.iter().enumerate() {
+        let mut y = 0;
+        for (_, tracks) in dancers {
+            for track in tracks {
+                let color = track.average_at(*time);
+                let pixel = image::Rgb(color.map(|c| c.clamp(0, 255) as u8));
+                for _ in 0..row_height {
+                    image.put_pixel(x as u32, y, pixel);
+                    y += 1;
+                }
+            }
+            y += 1;
+        }
+    }
+
+    image
+}
+
+pub async fn heat_map(
+    Query(query): Query<HeatMapQuery>,
+) -> Result<(StatusCode, (HeaderMap, Bytes)), (StatusCode, Json<GetDataFailedResponse>)> {
+    let clients = global::clients::get();
+    let mysql_pool = clients.mysql_pool();
+
+    let pixels_per_second = query.pixels_per_second.unwrap_or(10.0);
+    if !(pixels_per_second.is_finite() && pixels_per_second > 0.0) {
+        return Err(bad_request("pixelsPerSecond must be positive".to_string()));
+    }
+    let row_height = query.row_height.unwrap_or(8).clamp(1, 64);
+
+    let dancers: Vec<(String, Vec<LightTrack>)> =
+        get_light_tracks(mysql_pool, query.dancer.as_deref()).await?;
+
+    let starts = dancers
+        .iter()
+        .flat_map(|(_, tracks)| tracks.iter())
+        .flat_map(|track| track.keys.iter().map(|key| key.start));
+    let start = query.start.unwrap_or(starts.clone().min().unwrap_or(0));
+    let end = query.end.unwrap_or(starts.max().unwrap_or(0));
+
+    if start > end {
+        return Err(bad_request("Start must not be larger than end".to_string()));
+    }
+
+    let width = ((end as i64 - start as i64) as f64 / 1000.0 * pixels_per_second).ceil();
+    if width > MAX_WIDTH as f64 {
+        return Err(bad_request(format!(
+            "Image cannot be wider than {MAX_WIDTH} pixels"
+        )));
+    }
+    let width = width as i32;
+    if width.max(1) as u64 * image_height(&dancers, row_height).max(1) > MAX_PIXELS {
+        return Err(bad_request(format!(
+            "Image cannot have more than {MAX_PIXELS} pixels"
+        )));
+    }
+    let times = (0..width.max(1))
+        .map(|x| start.saturating_add((x as f64 * 1000.0 / pixels_per_second) as i32))
+        .collect_vec();
+
+    let image = render_heat_map(&dancers, &times, row_height);
+
+    let mut body = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut body), ImageFormat::Png)
+        .into_result()?;
+
+    // name the rows from top to bottom, as the image has no labels
+    let rows = dancers
+        .iter()
+        .flat_map(|(name, tracks)| {
+            tracks
+                .iter()
+                .map(move |track| format!("{name}/{}", track.part_name))
+        })
+        .join(",");
+
+    let mut headers = HeaderMap::new();
+    headers.insert("content-type", HeaderValue::from_static("image/png"));
+    if let Ok(rows) = HeaderValue::from_str(&rows) {
+        headers.insert("x-heat-map-rows", rows);
+    }
+
+    Ok((StatusCode::OK, (headers, Bytes::from(body))))
+}
@@ -0,0 +1,256 @@
+use crate::global;
+use crate::graphql::subscriptions::color::{ColorMutationMode, ColorPayload};
+use crate::graphql::subscriptions::led::LEDPayload;
+use crate::graphql::subscriptor::Subscriptor;
+use crate::graphql::types::led::{LEDEffectData, LEDEffectFrame};
+use crate::server::extractors::Authentication;
+use crate::utils::color::{rgb_to_hex, ColorQuantizer};
+use crate::utils::led::{insert_led_effect, resample_leds};
+use crate::utils::revision::update_revision;
+
+use axum::{
+    extract::{Multipart, Query},
+    http::StatusCode,
+    response::Json,
+};
+use image::{codecs::gif::GifDecoder, AnimationDecoder, ImageFormat, RgbaImage};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+use super::{types::GetDataFailedResponse, utils::IntoResult};
+
+/// Upper bound on the number of frames imported at once.
+const MAX_IMPORTED_FRAMES: usize = 1000;
+/// Upper bound on the number of colors created by an import.
+const MAX_CREATED_COLORS: usize = 256;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ImportLEDEffectQuery {
+    pub model: String,
+    pub part: String,
+    /// Effect name; frames after the first are suffixed with their index.
+    pub name: String,
+    /// Time between frames; defaults to 100.
+    pub interval: Option<i32>,
+    /// Largest RGB distance to reuse an existing color; defaults to 24.
+    ///
+    /// At most 256 colors are created, a larger tolerance reusing more of them.
+    pub tolerance: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ImportedEffect {
+    pub id: i32,
+    pub name: String,
+    pub start: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ImportLEDEffectResponse {
+    pub effects: Vec<ImportedEffect>,
+    #[serde(rename = "createdColors")]
+    pub created_colors: Vec<i32>,
+}
+
+type ImportLEDEffectError = (StatusCode, Json<GetDataFailedResponse>);
+
+fn bad_request(err: String) -> ImportLEDEffectError {
+    (StatusCode::BAD_REQUEST, Json(GetDataFailedResponse { err }))
+}
+
+fn row_of(image: &RgbaImage, y: u32) -> Vec<[u8; 4]> {
+    (0..image.width())
+        .map(|x| image.get_pixel(x, y).0)
+        .collect_vec()
+}
+
+/// Decode the frames of an image: the rows of a PNG, or the top row of every GIF frame.
+fn decode_frames(bytes: &[u8]) -> Result<Vec<Vec<[u8; 4]>>, String> {
+    match image::guess_format(bytes).map_err(|e| e.to_string())? {
+        ImageFormat::Png => {
+            let image = image::load_from_memory_with_format(bytes, ImageFormat::Png)
+                .map_err(|e| e.to_string())?
+                .to_rgba8();
+            Ok((0..image.height()).map(|y| row_of(&image, y)).collect_vec())
+        }
+        ImageFormat::Gif => {
+            let frames = GifDecoder::new(Cursor::new(bytes))
+                .map_err(|e| e.to_string())?
+                .into_frames()
+                .collect_frames()
+                .map_err(|e| e.to_string())?;
+            Ok(frames
+                .iter()
+                .filter(|frame| frame.buffer().height() > 0)
+                .map(|frame| row_of(frame.buffer(), 0))
+                .collect_vec())
+        }
+        format => Err(format!("Unsupported image format: {format:?}")),
+    }
+}
+
+pub async fn import_led_effect(
+    Authentication(context): Authentication,
+    Query(query): Query<ImportLEDEffectQuery>,
+    mut files: Multipart,
+) -> Result<(StatusCode, Json<ImportLEDEffectResponse>), ImportLEDEffectError> {
+    let clients = global::clients::get();
+    let mysql_pool = clients.mysql_pool();
+
+    let interval = query.interval.unwrap_or(100);
+    if interval <= 0 {
+        return Err(bad_request("Interval must be positive".to_string()));
+    }
+    let tolerance = query.tolerance.unwrap_or(24.0);
+
+    let mut field = files
+        .next_field()
+        .await
+        .into_result()?
+        .ok_or(bad_request("No File!".to_string()))?;
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field.chunk().await.into_result()? {
+        bytes.extend_from_slice(&chunk);
+    }
+
+    let frames = decode_frames(&bytes).map_err(bad_request)?;
+    if frames.is_empty() || frames[0].is_empty() {
+        return Err(bad_request("Image is empty".to_string()));
+    }
+    if frames.len() > MAX_IMPORTED_FRAMES {
+        return Err(bad_request(format!(
+            "Cannot import more than {MAX_IMPORTED_FRAMES} frames"
+        )));
+    }
+
+    let part = sqlx::query!(
+        r#"
+            SELECT
+                Model.id AS "model_id",
+                Part.id AS "part_id",
+                Part.length
+            FROM Part
+            INNER JOIN Model ON Part.model_id = Model.id
+            WHERE Part.name = ? AND Model.name = ? AND Part.type = 'LED';
+        "#,
+        &query.part,
+        &query.model,
+    )
+    .fetch_optional(mysql_pool)
+    .await
+    .into_result()?
+    .ok_or(bad_request("No corresponding LED part.".to_string()))?;
+
+    let length = part.length.unwrap_or(0).max(0) as usize;
+
+    let names = match frames.len() {
+        1 => vec![query.name.clone()],
+        count => {
+            let width = (count - 1).to_string().len();
+            (0..count)
+                .map(|index| format!("{}_{:0width$}", query.name, index))
+                .collect_vec()
+        }
+    };
+
+    let existing = sqlx::query!(
+        r#"
+            SELECT name FROM LEDEffect
+            WHERE model_id = ? AND part_id = ?;
+        "#,
+        part.model_id,
+        part.part_id
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .into_result()?;
+    if let Some(effect) = existing.iter().find(|effect| names.contains(&effect.name)) {
+        return Err(bad_request(format!("effectName {} exists.", effect.name)));
+    }
+
+    let colors = sqlx::query!(
+        r#"
+            SELECT id, r, g, b FROM Color;
+        "#
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .into_result()?
+    .into_iter()
+    .map(|color| (color.id, [color.r, color.g, color.b]))
+    .collect_vec();
+
+    let mut tx = mysql_pool.begin().await.into_result()?;
+
+    // quantize the pixels to the existing colors, creating the missing ones
+    let mut quantizer = ColorQuantizer::new(colors, tolerance);
+    let mut effects = Vec::new();
+    let mut create_effects = Vec::new();
+    for (index, (name, frame)) in names.iter().zip(&frames).enumerate() {
+        let mut leds = Vec::new();
+        for pixel in resample_leds(frame, length) {
+            let rgb = [pixel[0] as i32, pixel[1] as i32, pixel[2] as i32];
+            let color_id = quantizer.quantize(&mut tx, rgb).await.into_result()?;
+            if quantizer.created.len() > MAX_CREATED_COLORS {
+                return Err(bad_request(format!(
+                    "Cannot create more than {MAX_CREATED_COLORS} colors, use a larger tolerance"
+                )));
+            }
+            leds.push([color_id, pixel[3] as i32]);
+        }
+
+        let effect_id = insert_led_effect(&mut tx, name, part.model_id, part.part_id, &leds)
+            .await
+            .into_result()?;
+
+        effects.push(ImportedEffect {
+            id: effect_id,
+            name: name.clone(),
+            start: index as i32 * interval,
+        });
+        create_effects.push(LEDEffectData {
+            id: effect_id,
+            name: name.clone(),
+            model_name: query.model.clone(),
+            part_name: query.part.clone(),
+            repeat: 0,
+            frames: vec![LEDEffectFrame {
+                leds,
+                fade: false,
+                start: 0,
+            }],
+        });
+    }
+
+    tx.commit().await.into_result()?;
+
+    for (id, code) in &quantizer.created {
+        let color_payload = ColorPayload {
+            mutation: ColorMutationMode::Created,
+            id: *id,
+            color: Some(rgb_to_hex(*code)),
+            color_code: Some(code.to_vec()),
+            edit_by: context.user_id,
+        };
+        Subscriptor::publish(color_payload);
+    }
+
+    let led_payload = LEDPayload {
+        create_effects,
+        update_effects: Vec::new(),
+        delete_effects: Vec::new(),
+    };
+    Subscriptor::publish(led_payload);
+
+    update_revision(mysql_pool).await.into_result()?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ImportLEDEffectResponse {
+            effects,
+            created_colors: quantizer.created.iter().map(|(id, _)| *id).collect_vec(),
+        }),
+    ))
+}
@@ -0,0 +1,344 @@
+use crate::global;
+use crate::utils::led::get_led_effect_data;
+
+use axum::{
+    body::Bytes,
+    extract::Query,
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::Json,
+};
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, ImageFormat, RgbImage, RgbaImage,
+};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use super::{
+    lights::{apply_alpha, get_light_tracks, LightKey, LightTrack},
+    types::GetDataFailedResponse,
+    utils::IntoResult,
+};
+
+/// Upper bound on the number of rendered frames, or timeline columns.
+const MAX_PREVIEW_FRAMES: i32 = 20000;
+/// Upper bound on the pixels of all rendered frames together.
+const MAX_PREVIEW_PIXELS: u64 = 1 << 26;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LEDPreviewQuery {
+    /// Comma separated LED effect ids, played one after another.
+    pub effects: Option<String>,
+    /// Name of a dancer whose LED parts are rendered instead.
+    pub dancer: Option<String>,
+    /// Time range of a dancer; defaults to its first and last control frames.
+    pub start: Option<i32>,
+    pub end: Option<i32>,
+    /// `gif` (default for effects), `apng`, or `png` (default for dancers) for a timeline.
+    pub format: Option<String>,
+    /// Time every effect is shown; defaults to 100.
+    pub interval: Option<i32>,
+    /// Time between rendered frames; defaults to the interval for effects and 50 for dancers.
+    pub step: Option<i32>,
+    /// Fade from every effect into the next.
+    pub fade: Option<bool>,
+    /// Number of plays of the animation, 0 being forever; defaults to 0.
+    pub repeat: Option<u32>,
+    /// Pixels per bulb; defaults to 8.
+    pub scale: Option<u32>,
+}
+
+fn bad_request(err: String) -> (StatusCode, Json<GetDataFailedResponse>) {
+    (StatusCode::BAD_REQUEST, Json(GetDataFailedResponse { err }))
+}
+
+/// Width and height of the strips of the parts.
+fn strips_size(tracks: &[LightTrack], scale: u32) -> (u32, u32) {
+    let width = tracks.iter().map(|track| track.length).max().unwrap_or(0) as u32;
+    ((width * scale).max(1), (tracks.len() as u32 * scale).max(1))
+}
+
+/// Height of the timeline of the parts.
+fn timeline_height(tracks: &[LightTrack], scale: u32) -> u32 {
+    tracks
+        .iter()
+        .map(|track| track.length as u32 * scale + 1)
+        .sum::<u32>()
+        .max(1)
+}
+
+/// Render the parts as stacked strips, one bulb being a `scale` pixels square.
+fn render_strips(tracks: &[LightTrack], time: i32, scale: u32) -> RgbImage {
+    let (width, height) = strips_size(tracks, scale);
+    let mut image = RgbImage::new(width, height);
+
+    for (row, track) in tracks.iter().enumerate() {
+        for (column, color) in track.colors_at(time).iter().enumerate() {
+            let pixel = image::Rgb(color.map(|c| c.clamp(0, 255) as u8));
+            for dx in 0..scale {
+                for dy in 0..scale {
+                    image.put_pixel(column as u32 * scale + dx, row as u32 * scale + dy, pixel);
+                }
+            }
+        }
+    }
+
+    image
+}
+
+/// Render the parts over time, a column per sample and `scale` pixel rows per bulb.
+fn render_timeline(tracks: &[LightTrack], times: &[i32], scale: u32) -> RgbImage {
+    let mut image = RgbImage::from_pixel(
+        times.len().max(1) as u32,
+        timeline_height(tracks, scale),
+        image::Rgb([64, 64, 64]),
+    );
+
+    for (x, time) in times.iter().enumerate() {
+        let mut y = 0;
+        for track in tracks {
+            for color in track.colors_at(*time) {
+                let pixel = image::Rgb(color.map(|c| c.clamp(0, 255) as u8));
+                for _ in 0..scale {
+                    image.put_pixel(x as u32, y, pixel);
+                    y += 1;
+                }
+            }
+            // separator between the parts
+            y += 1;
+        }
+    }
+
+    image
+}
+
+fn encode_gif(
+    frames: impl Iterator<Item = RgbImage>,
+    step: i32,
+    repeat: u32,
+) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = GifEncoder::new(&mut bytes);
+        encoder
+            .set_repeat(match repeat {
+                0 => Repeat::Infinite,
+                plays => Repeat::Finite(plays.saturating_sub(1).min(u16::MAX as u32) as u16),
+            })
+            .map_err(|e| e.to_string())?;
+
+        let delay = Delay::from_numer_denom_ms(step as u32, 1);
+        encoder
+            .encode_frames(frames.map(|frame| {
+                let frame = RgbaImage::from_fn(frame.width(), frame.height(), |x, y| {
+                    let [r, g, b] = frame.get_pixel(x, y).0;
+                    image::Rgba([r, g, b, 255])
+                });
+                Frame::from_parts(frame, 0, 0, delay)
+            }))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(bytes)
+}
+
+/// Encode `count` frames of `width` by `height` pixels, writing every frame as it is rendered.
+fn encode_apng(
+    (width, height): (u32, u32),
+    count: usize,
+    frames: impl Iterator<Item = RgbImage>,
+    step: i32,
+    repeat: u32,
+) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .set_animated(count as u32, repeat)
+            .map_err(|e| e.to_string())?;
+        encoder
+            .set_frame_delay(step.clamp(0, u16::MAX as i32) as u16, 1000)
+            .map_err(|e| e.to_string())?;
+
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        for frame in frames {
+            writer
+                .write_image_data(frame.as_raw())
+                .map_err(|e| e.to_string())?;
+        }
+        writer.finish().map_err(|e| e.to_string())?;
+    }
+    Ok(bytes)
+}
+
+fn encode_png(image: RgbImage) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// Build a single track playing the effects one after another, with the number of effects.
+async fn get_effects_track(
+    ids: &str,
+    interval: i32,
+    fade: bool,
+) -> Result<(LightTrack, usize), (StatusCode, Json<GetDataFailedResponse>)> {
+    let ids = ids
+        .split(',')
+        .map(|id| id.trim().parse::<i32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| bad_request(format!("Invalid effect ids: {ids}")))?;
+
+    let clients = global::clients::get();
+    let mysql_pool = clients.mysql_pool();
+
+    let color_map: HashMap<i32, [i32; 3]> = sqlx::query!(
+        r#"
+            SELECT Color.r, Color.g, Color.b, Color.id
+            FROM Color
+        "#,
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .into_result()?
+    .into_iter()
+    .map(|color| (color.id, [color.r, color.g, color.b]))
+    .collect();
+
+    let mut track = LightTrack {
+        part_type: "LED".to_string(),
+        ..Default::default()
+    };
+
+    for (index, id) in ids.iter().enumerate() {
+        let effect = get_led_effect_data(mysql_pool, *id)
+            .await
+            .map_err(bad_request)?;
+
+        for frame in effect.frames {
+            let status = frame
+                .leds
+                .iter()
+                .map(|[color_id, alpha]| {
+                    let [r, g, b] = color_map.get(color_id).copied().unwrap_or_default();
+                    [r, g, b, *alpha]
+                })
+                .collect_vec();
+
+            track.length = track.length.max(status.len());
+            track.keys.push(LightKey {
+                start: index as i32 * interval + frame.start,
+                fade: fade || frame.fade,
+                colors: apply_alpha(&status, status.len()),
+            });
+        }
+    }
+
+    // pad the effects made for shorter parts
+    let length = track.length;
+    for key in &mut track.keys {
+        key.colors.resize(length, [0, 0, 0]);
+    }
+
+    Ok((track, ids.len()))
+}
+
+pub async fn led_preview(
+    Query(query): Query<LEDPreviewQuery>,
+) -> Result<(StatusCode, (HeaderMap, Bytes)), (StatusCode, Json<GetDataFailedResponse>)> {
+    let scale = query.scale.unwrap_or(8).clamp(1, 64);
+    let repeat = query.repeat.unwrap_or(0);
+
+    let (tracks, start, end, step, default_format) = match (&query.effects, &query.dancer) {
+        (Some(ids), None) => {
+            let interval = query.interval.unwrap_or(100);
+            if interval <= 0 {
+                return Err(bad_request("Interval must be positive".to_string()));
+            }
+
+            let (track, count) =
+                get_effects_track(ids, interval, query.fade.unwrap_or(false)).await?;
+            let end = count as i32 * interval;
+            let step = query.step.unwrap_or(interval);
+            (vec![track], 0, end, step, "gif")
+        }
+        (None, Some(dancer)) => {
+            let clients = global::clients::get();
+            let mysql_pool = clients.mysql_pool();
+
+            let tracks = get_light_tracks(mysql_pool, Some(dancer))
+                .await?
+                .into_iter()
+                .flat_map(|(_, tracks)| tracks)
+                .filter(|track| track.part_type == "LED")
+                .collect_vec();
+
+            let starts = tracks
+                .iter()
+                .flat_map(|track| track.keys.iter().map(|key| key.start));
+            let start = query.start.unwrap_or(starts.clone().min().unwrap_or(0));
+            let end = query.end.unwrap_or(starts.max().unwrap_or(0));
+            let step = query.step.unwrap_or(50);
+            (tracks, start, end, step, "png")
+        }
+        _ => {
+            return Err(bad_request(
+                "Either effects or dancer must be given".to_string(),
+            ))
+        }
+    };
+
+    if step <= 0 {
+        return Err(bad_request("Step must be positive".to_string()));
+    }
+    if start > end {
+        return Err(bad_request("Start must not be larger than end".to_string()));
+    }
+    if (end as i64 - start as i64) / step as i64 >= MAX_PREVIEW_FRAMES as i64 {
+        return Err(bad_request(format!(
+            "Cannot render more than {MAX_PREVIEW_FRAMES} frames"
+        )));
+    }
+
+    let times = (start..end.max(start.saturating_add(1)))
+        .step_by(step as usize)
+        .collect_vec();
+
+    let format = query.format.as_deref().unwrap_or(default_format);
+    let (width, height) = match format {
+        "png" => (1, timeline_height(&tracks, scale)),
+        _ => strips_size(&tracks, scale),
+    };
+    if times.len() as u64 * width as u64 * height as u64 > MAX_PREVIEW_PIXELS {
+        return Err(bad_request(format!(
+            "Cannot render more than {MAX_PREVIEW_PIXELS} pixels, use a larger step or a smaller scale"
+        )));
+    }
+
+    let frames = times
+        .iter()
+        .map(|time| render_strips(&tracks, *time, scale));
+    let (body, content_type) = match format {
+        "gif" => (encode_gif(frames, step, repeat), "image/gif"),
+        "apng" => (
+            encode_apng((width, height), times.len(), frames, step, repeat),
+            "image/apng",
+        ),
+        "png" => (
+            encode_png(render_timeline(&tracks, &times, scale)),
+            "image/png",
+        ),
+        format => return Err(bad_request(format!("Unknown format: {format}"))),
+    };
+    let body = body.into_result()?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert("content-type", HeaderValue::from_static(content_type));
+
+    Ok((StatusCode::OK, (headers, Bytes::from(body))))
+}
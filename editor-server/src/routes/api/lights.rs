@@ -0,0 +1,362 @@
+//! Resolved output colors of dancer parts over time, with the color math of frameDat.
+
+use std::collections::HashMap;
+
+use axum::{http::StatusCode, response::Json};
+use itertools::Itertools;
+use sqlx::{MySql, Pool};
+
+use crate::utils::vector::partition_by_field;
+
+use super::{
+    frame_dat::{frame_fade, interpolate_no_change, NoChangeIntervals},
+    types::GetDataFailedResponse,
+    utils::{alpha, gradient_to_rgb_float, interpolate_gradient, IntoResult},
+};
+
+pub type Rgb = [i32; 3];
+
+type LEDStatus = [i32; 4];
+
+const DEFAULT_COLOR: Rgb = [0, 0, 0];
+
+/// Output colors of a part from the start of a control frame, fading into the next
+/// key if `fade` is set.
+#[derive(Debug, Clone, Default)]
+pub struct LightKey {
+    pub start: i32,
+    pub fade: bool,
+    pub colors: Vec<Rgb>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LightTrack {
+    pub part_id: i32,
+    pub part_name: String,
+    /// `LED` or `FIBER`.
+    pub part_type: String,
+    /// Number of bulbs; 1 for fibers.
+    pub length: usize,
+    pub keys: Vec<LightKey>,
+}
+
+impl LightTrack {
+    /// Output colors at `time`, black before the first key.
+    pub fn colors_at(&self, time: i32) -> Vec<Rgb> {
+        let index = match self.keys.partition_point(|key| key.start <= time) {
+            0 => return vec![DEFAULT_COLOR; self.length],
+            index => index - 1,
+        };
+
+        let from = &self.keys[index];
+        match self.keys.get(index + 1) {
+            Some(to) if from.fade => {
+                let t = (time - from.start) as f64 / (to.start - from.start) as f64;
+                blend(&from.colors, &to.colors, t)
+            }
+            _ => from.colors.clone(),
+        }
+    }
+
+    /// Average output color at `time`.
+    pub fn average_at(&self, time: i32) -> Rgb {
+        let colors = self.colors_at(time);
+        if colors.is_empty() {
+            return DEFAULT_COLOR;
+        }
+
+        let mut sum = [0, 0, 0];
+        for color in &colors {
+            for (i, value) in sum.iter_mut().enumerate() {
+                *value += color[i];
+            }
+        }
+        sum.map(|value| value / colors.len() as i32)
+    }
+}
+
+pub fn blend(a: &[Rgb], b: &[Rgb], t: f64) -> Vec<Rgb> {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| {
+            let mut color = [0; 3];
+            for (i, value) in color.iter_mut().enumerate() {
+                *value = (a[i] as f64 + (b[i] - a[i]) as f64 * t).round() as i32;
+            }
+            color
+        })
+        .collect_vec()
+}
+
+/// Output colors of LED statuses, padded with black or truncated to `length`.
+pub fn apply_alpha(status: &[LEDStatus], length: usize) -> Vec<Rgb> {
+    let mut colors = status.iter().map(alpha).collect_vec();
+    colors.resize(length, DEFAULT_COLOR);
+    colors
+}
+
+/// Load the colors of every part of the dancer, or of every dancer if not given, at their control
+/// frames, as `(dancer name, tracks)` ordered by dancer id.
+///
+/// Follows frameDat: NO_EFFECT data keeps the colors of the previous frame, effects without an
+/// effect id fade through to the next effect when the frame fades, and the fade of a frame is
+/// held from the previous frame when unset.
+pub async fn get_light_tracks(
+    mysql_pool: &Pool<MySql>,
+    dancer: Option<&str>,
+) -> Result<Vec<(String, Vec<LightTrack>)>, (StatusCode, Json<GetDataFailedResponse>)> {
+    let color_map: HashMap<i32, Rgb> = sqlx::query!(
+        r#"
+            SELECT Color.r, Color.g, Color.b, Color.id
+            FROM Color
+        "#,
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .into_result()?
+    .into_iter()
+    .map(|color| (color.id, [color.r, color.g, color.b]))
+    .collect();
+
+    let dancers = sqlx::query!(
+        r#"
+            SELECT
+                Dancer.id,
+                Dancer.name,
+                Dancer.model_id
+            FROM Dancer
+            WHERE ? IS NULL OR Dancer.name = ?
+            ORDER BY Dancer.id ASC;
+        "#,
+        dancer,
+        dancer
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .into_result()?;
+
+    if dancer.is_some() && dancers.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(GetDataFailedResponse {
+                err: "Dancer not found.".to_string(),
+            }),
+        ));
+    }
+
+    // (model_id, part[])
+    let mut parts_map = sqlx::query!(
+        r#"
+            SELECT
+                Part.id,
+                Part.model_id,
+                Part.name,
+                Part.type,
+                Part.length
+            FROM Part
+            ORDER BY Part.id;
+        "#,
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .into_result()?
+    .into_iter()
+    .into_group_map_by(|part| part.model_id);
+
+    // (effect_id, status[])
+    let effect_data = sqlx::query!(
+        r#"
+            SELECT
+                LEDEffectState.effect_id,
+                LEDEffectState.color_id,
+                LEDEffectState.alpha
+            FROM LEDEffectState
+            ORDER BY LEDEffectState.effect_id, LEDEffectState.position;
+        "#,
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .into_result()?;
+    let effects_map: HashMap<i32, Vec<LEDStatus>> =
+        partition_by_field(|data| data.effect_id, effect_data)
+            .into_iter()
+            .map(|states| {
+                let status = states
+                    .iter()
+                    .map(|state| {
+                        let color = color_map.get(&state.color_id).unwrap_or(&DEFAULT_COLOR);
+                        [color[0], color[1], color[2], state.alpha]
+                    })
+                    .collect_vec();
+                (states[0].effect_id, status)
+            })
+            .collect();
+
+    // (control_data_id, status[])
+    let bulb_data = sqlx::query!(
+        r#"
+            SELECT
+                ControlData.id as "control_data_id",
+                LEDBulb.color_id,
+                LEDBulb.alpha
+            FROM ControlData
+            INNER JOIN Dancer
+                ON ControlData.dancer_id = Dancer.id
+            INNER JOIN LEDBulb
+                ON LEDBulb.control_id = ControlData.id
+            WHERE (? IS NULL OR Dancer.name = ?) AND
+                ControlData.type = "LED_BULBS"
+            ORDER BY ControlData.id ASC, LEDBulb.position ASC;
+        "#,
+        dancer,
+        dancer
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .into_result()?;
+    let bulbs_map: HashMap<i32, Vec<LEDStatus>> =
+        partition_by_field(|data| data.control_data_id, bulb_data)
+            .into_iter()
+            .map(|bulbs| {
+                let status = bulbs
+                    .iter()
+                    .map(|bulb| {
+                        let color = color_map.get(&bulb.color_id).unwrap_or(&DEFAULT_COLOR);
+                        [color[0], color[1], color[2], bulb.alpha]
+                    })
+                    .collect_vec();
+                let status = interpolate_gradient(gradient_to_rgb_float(status));
+                (bulbs[0].control_data_id, status)
+            })
+            .collect();
+
+    // (dancer_id, control_data[]), ordered by start
+    let mut control_data_map = sqlx::query!(
+        r#"
+            SELECT
+                ControlData.id,
+                ControlData.dancer_id,
+                ControlData.part_id,
+                ControlData.type,
+                ControlData.fade,
+                ControlData.color_id,
+                ControlData.effect_id,
+                ControlData.alpha,
+                ControlFrame.start
+            FROM ControlData
+            INNER JOIN Dancer
+                ON ControlData.dancer_id = Dancer.id
+            INNER JOIN ControlFrame
+                ON ControlData.frame_id = ControlFrame.id
+            WHERE ? IS NULL OR Dancer.name = ?
+            ORDER BY ControlFrame.start ASC;
+        "#,
+        dancer,
+        dancer
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .into_result()?
+    .into_iter()
+    .into_group_map_by(|data| data.dancer_id);
+
+    let mut result = Vec::new();
+    for dancer in dancers {
+        let mut tracks = parts_map
+            .get(&dancer.model_id)
+            .into_iter()
+            .flatten()
+            .map(|part| {
+                let length = match part.r#type.as_str() {
+                    "LED" => part.length.unwrap_or(0).max(0) as usize,
+                    _ => 1,
+                };
+                LightTrack {
+                    part_id: part.id,
+                    part_name: part.name.clone(),
+                    part_type: part.r#type.clone(),
+                    length,
+                    keys: Vec::new(),
+                }
+            })
+            .collect_vec();
+
+        let track_index: HashMap<i32, usize> = tracks
+            .iter()
+            .enumerate()
+            .map(|(index, track)| (track.part_id, index))
+            .collect();
+
+        let control_data = control_data_map.remove(&dancer.id).unwrap_or_default();
+
+        // Every track gets a key at every frame, so key indices are frame indices as in frameDat.
+        let mut no_change_intervals = NoChangeIntervals::default();
+        let mut previous_fade = None;
+
+        for (index, frame) in partition_by_field(|data| data.start, control_data)
+            .into_iter()
+            .enumerate()
+        {
+            let fade = frame_fade(frame[0].fade, previous_fade).unwrap_or(0);
+            previous_fade = Some(fade);
+
+            let frame_data: HashMap<i32, _> =
+                frame.iter().map(|data| (data.part_id, data)).collect();
+
+            for track in tracks.iter_mut() {
+                let held = track
+                    .keys
+                    .last()
+                    .map_or(vec![DEFAULT_COLOR; track.length], |key| key.colors.clone());
+                let data = frame_data.get(&track.part_id);
+
+                let no_change = data.is_some_and(|data| {
+                    data.r#type == "EFFECT" && data.effect_id.is_none() && track.part_type == "LED"
+                });
+                no_change_intervals.push(track.part_id, index, no_change && fade == 1);
+
+                let colors = match data {
+                    Some(data) => match (data.r#type.as_str(), data.effect_id) {
+                        ("COLOR", _) => {
+                            let color = data
+                                .color_id
+                                .and_then(|id| color_map.get(&id))
+                                .unwrap_or(&DEFAULT_COLOR);
+                            let status = [color[0], color[1], color[2], data.alpha.unwrap_or(255)];
+                            apply_alpha(&[status], track.length)
+                        }
+                        ("EFFECT", Some(effect_id)) => effects_map
+                            .get(&effect_id)
+                            .map_or(held, |status| apply_alpha(status, track.length)),
+                        ("LED_BULBS", _) => bulbs_map
+                            .get(&data.id)
+                            .map_or(held, |status| apply_alpha(status, track.length)),
+                        _ => held,
+                    },
+                    None => vec![DEFAULT_COLOR; track.length],
+                };
+
+                track.keys.push(LightKey {
+                    start: frame[0].start,
+                    fade: fade == 1,
+                    colors,
+                });
+            }
+        }
+
+        for (part_id, (left, right)) in no_change_intervals.iter() {
+            let track = &mut tracks[track_index[&part_id]];
+            let left_colors = track.keys[left].colors.clone();
+            let right_colors = track.keys[right].colors.clone();
+
+            for step in 0..right - left {
+                let colors = &mut track.keys[left + step].colors;
+                interpolate_no_change(colors, &left_colors, &right_colors, step, right - left);
+            }
+        }
+
+        result.push((dancer.name, tracks));
+    }
+
+    Ok(result)
+}
@@ -4,9 +4,14 @@ mod check_token;
 mod control_dat;
 mod export_data;
 mod frame_dat;
+mod heat_map;
+mod import_led_effect;
+mod led_preview;
+mod lights;
 mod login;
 mod logout;
 mod ping;
+mod spike_marks;
 mod types;
 mod upload_data;
 mod utils;
@@ -28,6 +33,13 @@ pub fn build_api_routes() -> Router {
         .route("/frameDat", post(frame_dat::frame_dat))
         .route("/exportData", get(export_data::export_data))
         .route("/uploadData", post(upload_data::upload_data))
+        .route("/spikeMarks", get(spike_marks::spike_marks))
+        .route(
+            "/importLEDEffect",
+            post(import_led_effect::import_led_effect),
+        )
+        .route("/heatMap", get(heat_map::heat_map))
+        .route("/ledPreview", get(led_preview::led_preview))
         .route("/testFrameDat", get(frame_dat::test_frame_dat))
         .route("/testControlDat", get(control_dat::test_control_dat))
         .layer(DefaultBodyLimit::max(256 * 1024 * 1024))
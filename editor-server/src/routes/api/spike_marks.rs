@@ -0,0 +1,242 @@
+use crate::global;
+use crate::utils::motion::Pose;
+use crate::utils::position::get_dancer_tracks;
+
+use axum::{
+    body::Bytes,
+    extract::Query,
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::Json,
+};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+use super::{types::GetDataFailedResponse, utils::IntoResult};
+
+/// Pixels per unit of stage coordinates.
+const SCALE: f64 = 100.0;
+/// Margin around the marks, in stage units.
+const MARGIN: f64 = 1.0;
+/// Height of the title above every plot, in pixels.
+const TITLE_HEIGHT: f64 = 40.0;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SpikeMarksQuery {
+    /// Comma separated position frame ids; every frame if not given.
+    pub frames: Option<String>,
+    /// `svg` (default) or `csv`.
+    pub format: Option<String>,
+}
+
+struct Mark {
+    name: String,
+    pose: Pose,
+}
+
+struct Formation {
+    id: i32,
+    start: i32,
+    marks: Vec<Mark>,
+}
+
+fn bad_request(err: String) -> (StatusCode, Json<GetDataFailedResponse>) {
+    (StatusCode::BAD_REQUEST, Json(GetDataFailedResponse { err }))
+}
+
+fn format_time(ms: i32) -> String {
+    format!("{}:{:02}.{:03}", ms / 60000, ms / 1000 % 60, ms % 1000)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Facing of a dancer on the floor; dancers face -y (downstage) at rz = 0.
+fn facing(pose: &Pose) -> (f64, f64) {
+    let rz = pose.rotation[2];
+    (rz.sin(), -rz.cos())
+}
+
+fn render_csv(formations: &[Formation]) -> String {
+    let mut csv = "frame_id,start,time,dancer,x,y,z,facing_deg\n".to_string();
+
+    for formation in formations {
+        for mark in &formation.marks {
+            let _ = writeln!(
+                csv,
+                "{},{},{},\"{}\",{:.3},{:.3},{:.3},{:.1}",
+                formation.id,
+                formation.start,
+                format_time(formation.start),
+                mark.name.replace('"', "\"\""),
+                mark.pose.location[0],
+                mark.pose.location[1],
+                mark.pose.location[2],
+                mark.pose.rotation[2].to_degrees(),
+            );
+        }
+    }
+
+    csv
+}
+
+/// Render every formation as a top-down plot, stacked vertically with downstage at the bottom.
+fn render_svg(formations: &[Formation]) -> String {
+    let points = formations
+        .iter()
+        .flat_map(|formation| formation.marks.iter())
+        .map(|mark| (mark.pose.location[0], mark.pose.location[1]));
+    let (min_x, max_x) = match points.clone().map(|(x, _)| x).minmax().into_option() {
+        Some((min, max)) => (min - MARGIN, max + MARGIN),
+        None => (-MARGIN, MARGIN),
+    };
+    let (min_y, max_y) = match points.map(|(_, y)| y).minmax().into_option() {
+        Some((min, max)) => (min - MARGIN, max + MARGIN),
+        None => (-MARGIN, MARGIN),
+    };
+
+    let width = (max_x - min_x) * SCALE;
+    let plot_height = (max_y - min_y) * SCALE + TITLE_HEIGHT;
+    let height = plot_height * formations.len() as f64;
+
+    let to_px = |x: f64, y: f64| ((x - min_x) * SCALE, (max_y - y) * SCALE + TITLE_HEIGHT);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.0}" height="{height:.0}" viewBox="0 0 {width:.0} {height:.0}" font-family="sans-serif">"#
+    );
+
+    for (index, formation) in formations.iter().enumerate() {
+        let _ = writeln!(
+            svg,
+            r#"<g id="frame-{}" transform="translate(0 {:.1})">"#,
+            formation.id,
+            plot_height * index as f64
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="10" y="28" font-size="20">Frame #{} at {}</text>"#,
+            formation.id,
+            format_time(formation.start)
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect x="0" y="{TITLE_HEIGHT}" width="{width:.1}" height="{:.1}" fill="none" stroke="black"/>"#,
+            plot_height - TITLE_HEIGHT
+        );
+
+        // center lines
+        let (cx, cy) = to_px(0.0, 0.0);
+        if (0.0..=width).contains(&cx) {
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{cx:.1}" y1="{TITLE_HEIGHT}" x2="{cx:.1}" y2="{plot_height:.1}" stroke="gray" stroke-dasharray="4"/>"#
+            );
+        }
+        if (TITLE_HEIGHT..=plot_height).contains(&cy) {
+            let _ = writeln!(
+                svg,
+                r#"<line x1="0" y1="{cy:.1}" x2="{width:.1}" y2="{cy:.1}" stroke="gray" stroke-dasharray="4"/>"#
+            );
+        }
+
+        for mark in &formation.marks {
+            let (x, y) = to_px(mark.pose.location[0], mark.pose.location[1]);
+            let (dx, dy) = facing(&mark.pose);
+            let (ax, ay) = (x + dx * SCALE * 0.3, y - dy * SCALE * 0.3);
+
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{x:.1}" cy="{y:.1}" r="6" fill="black"/>"#
+            );
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{x:.1}" y1="{y:.1}" x2="{ax:.1}" y2="{ay:.1}" stroke="red" stroke-width="2"/>"#
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" font-size="12">{} ({:.2}, {:.2})</text>"#,
+                x + 8.0,
+                y - 8.0,
+                escape(&mark.name),
+                mark.pose.location[0],
+                mark.pose.location[1]
+            );
+        }
+
+        svg.push_str("</g>\n");
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub async fn spike_marks(
+    Query(query): Query<SpikeMarksQuery>,
+) -> Result<(StatusCode, (HeaderMap, Bytes)), (StatusCode, Json<GetDataFailedResponse>)> {
+    let clients = global::clients::get();
+    let mysql_pool = clients.mysql_pool();
+
+    let frame_ids = match &query.frames {
+        Some(frames) => Some(
+            frames
+                .split(',')
+                .map(|id| id.trim().parse::<i32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| bad_request(format!("Invalid frame ids: {frames}")))?,
+        ),
+        None => None,
+    };
+
+    let frames = sqlx::query!(
+        r#"
+            SELECT id, start FROM PositionFrame
+            ORDER BY start ASC;
+        "#,
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .into_result()?
+    .into_iter()
+    .filter(|frame| {
+        frame_ids
+            .as_ref()
+            .map_or(true, |ids| ids.contains(&frame.id))
+    })
+    .collect_vec();
+
+    let tracks = get_dancer_tracks(mysql_pool).await.into_result()?;
+
+    let formations = frames
+        .into_iter()
+        .map(|frame| Formation {
+            id: frame.id,
+            start: frame.start,
+            marks: tracks
+                .iter()
+                .filter_map(|track| {
+                    Some(Mark {
+                        name: track.name.clone(),
+                        pose: track.pose_at(frame.start)?,
+                    })
+                })
+                .collect_vec(),
+        })
+        .collect_vec();
+
+    let (body, content_type) = match query.format.as_deref() {
+        None | Some("svg") => (render_svg(&formations), "image/svg+xml"),
+        Some("csv") => (render_csv(&formations), "text/csv"),
+        Some(format) => return Err(bad_request(format!("Unknown format: {format}"))),
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert("content-type", HeaderValue::from_static(content_type));
+
+    Ok((StatusCode::OK, (headers, Bytes::from(body))))
+}
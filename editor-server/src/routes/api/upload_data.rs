@@ -394,11 +394,17 @@ async fn collect_position(
                 true => "POSITION",
                 false => "NO_EFFECt",
             };
+            // quaternions are optional in older exports
+            let quaternion = frame_obj
+                .quaternion
+                .get(index)
+                .filter(|_| *has_position)
+                .copied();
 
             sqlx::query!(
                 r#"
-                    INSERT INTO PositionData (dancer_id, frame_id, type, x, y, z, rx, ry, rz)
-                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);
+                    INSERT INTO PositionData (dancer_id, frame_id, type, x, y, z, rx, ry, rz, qw, qx, qy, qz)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);
                     "#,
                 dancer_id,
                 frame_id,
@@ -408,7 +414,11 @@ async fn collect_position(
                 location_data[2],
                 rotation_data[0],
                 rotation_data[1],
-                rotation_data[2]
+                rotation_data[2],
+                quaternion.map(|q| q[0]),
+                quaternion.map(|q| q[1]),
+                quaternion.map(|q| q[2]),
+                quaternion.map(|q| q[3])
             )
             .execute(&mut **tx)
             .await
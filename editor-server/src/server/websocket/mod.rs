@@ -108,6 +108,8 @@ pub async fn ws_on_connect(_connection_params: serde_json::Value) -> Result<User
         .map_err(|e| e.to_string())?;
     }
 
+    global::sessions::connect(user_id);
+
     Ok(user_context)
 }
 
@@ -121,6 +123,8 @@ pub async fn ws_on_disconnect(context: UserContext) {
 
     let user_id = context.user_id;
 
+    global::sessions::disconnect(user_id);
+
     let _ = sqlx::query!(
         r#"
             UPDATE EditingControlFrame SET frame_id = NULL
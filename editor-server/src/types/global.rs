@@ -48,6 +48,8 @@ pub struct PositionData {
     pub start: i32,
     pub location: Vec<[f64; 3]>,
     pub rotation: Vec<[f64; 3]>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quaternion: Vec<[f64; 4]>,
     pub has_position: Vec<bool>,
 }
 
@@ -127,6 +129,9 @@ pub struct RedisPosition {
     pub rev: Revision,
     pub location: Vec<[f64; 3]>,
     pub rotation: Vec<[f64; 3]>,
+    /// The rotations as `[w, x, y, z]` quaternions.
+    #[serde(default)]
+    pub quaternion: Vec<[f64; 4]>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -0,0 +1,309 @@
+//! Color utilities.
+
+use sqlx::{MySql, Transaction};
+
+/// Convert a hue in turns (0 to 1), saturation and value to RGB.
+pub fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> [i32; 3] {
+    let hue = hue.rem_euclid(1.0) * 6.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+
+    let (r, g, b) = match hue as i32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+
+    [r, g, b].map(|c| ((c + m) * 255.0).round().clamp(0.0, 255.0) as i32)
+}
+
+/// Convert RGB to a hue in turns (0 to 1), saturation and value.
+pub fn rgb_to_hsv(rgb: [i32; 3]) -> [f64; 3] {
+    let [r, g, b] = rgb.map(|c| c.clamp(0, 255) as f64 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+
+    let hue = if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+    let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+
+    [hue / 6.0, saturation, max]
+}
+
+/// Convert a hue in turns (0 to 1), saturation and lightness to RGB.
+pub fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> [i32; 3] {
+    let value = lightness + saturation * lightness.min(1.0 - lightness);
+    let saturation = if value == 0.0 {
+        0.0
+    } else {
+        2.0 * (1.0 - lightness / value)
+    };
+    hsv_to_rgb(hue, saturation, value)
+}
+
+/// Convert RGB to a hue in turns (0 to 1), saturation and lightness.
+pub fn rgb_to_hsl(rgb: [i32; 3]) -> [f64; 3] {
+    let [hue, saturation, value] = rgb_to_hsv(rgb);
+    let lightness = value * (1.0 - saturation / 2.0);
+    let saturation = if lightness == 0.0 || lightness == 1.0 {
+        0.0
+    } else {
+        (value - lightness) / lightness.min(1.0 - lightness)
+    };
+    [hue, saturation, lightness]
+}
+
+/// Hex code of a color, e.g. `#ff8000`.
+pub fn rgb_to_hex([r, g, b]: [i32; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Parse a hex code, `#ff8000` or the short `#f80`, with or without the `#`.
+pub fn hex_to_rgb(hex: &str) -> Result<[i32; 3], String> {
+    let digits = hex.trim().trim_start_matches('#');
+    let invalid = || format!("Invalid hex color: {hex}");
+
+    let digits = match digits.len() {
+        3 => digits.chars().flat_map(|c| [c, c]).collect::<String>(),
+        6 => digits.to_string(),
+        _ => return Err(invalid()),
+    };
+    if !digits.is_ascii() {
+        return Err(invalid());
+    }
+
+    let mut rgb = [0; 3];
+    for (i, value) in rgb.iter_mut().enumerate() {
+        *value = i32::from_str_radix(&digits[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(rgb)
+}
+
+/// Range of white color temperatures in Kelvin.
+pub const KELVIN_RANGE: (f64, f64) = (1000.0, 40000.0);
+
+/// Approximate the color of a black body at a temperature in Kelvin.
+pub fn kelvin_to_rgb(kelvin: f64) -> [i32; 3] {
+    let t = kelvin.clamp(KELVIN_RANGE.0, KELVIN_RANGE.1) / 100.0;
+
+    let r = if t <= 66.0 {
+        255.0
+    } else {
+        329.698727446 * (t - 60.0).powf(-0.1332047592)
+    };
+    let g = if t <= 66.0 {
+        99.4708025861 * t.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (t - 60.0).powf(-0.0755148492)
+    };
+    let b = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (t - 10.0).ln() - 305.0447927307
+    };
+
+    [r, g, b].map(|c| c.round().clamp(0.0, 255.0) as i32)
+}
+
+/// The white color temperature closest to a color, ignoring its brightness, to 100 K.
+pub fn rgb_to_kelvin(rgb: [i32; 3]) -> f64 {
+    let normalize = |rgb: [i32; 3]| {
+        let max = rgb.iter().copied().max().unwrap_or(0).max(1) as f64;
+        rgb.map(|c| c.max(0) as f64 / max)
+    };
+    let color = normalize(rgb);
+
+    let (min, max) = (KELVIN_RANGE.0 as i32, KELVIN_RANGE.1 as i32);
+    (min..=max)
+        .step_by(100)
+        .map(|kelvin| {
+            let white = normalize(kelvin_to_rgb(kelvin as f64));
+            let distance = (0..3).map(|i| (white[i] - color[i]).powi(2)).sum::<f64>();
+            (kelvin as f64, distance)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(KELVIN_RANGE.0, |(kelvin, _)| kelvin)
+}
+
+/// Find a color with the given code, or create one named after its hex code.
+///
+/// Returns the id of the color and whether it was created.
+pub async fn get_or_create_color(
+    tx: &mut Transaction<'static, MySql>,
+    rgb: [i32; 3],
+) -> Result<(i32, bool), String> {
+    let existing = sqlx::query!(
+        r#"
+            SELECT id FROM Color
+            WHERE r = ? AND g = ? AND b = ?
+            ORDER BY id ASC
+            LIMIT 1;
+        "#,
+        rgb[0],
+        rgb[1],
+        rgb[2]
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if let Some(color) = existing {
+        return Ok((color.id, false));
+    }
+
+    let id = sqlx::query!(
+        r#"
+            INSERT INTO Color (name, r, g, b)
+            VALUES (?, ?, ?, ?);
+        "#,
+        rgb_to_hex(rgb),
+        rgb[0],
+        rgb[1],
+        rgb[2]
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_id() as i32;
+
+    Ok((id, true))
+}
+
+/// The color closest to `rgb` by Euclidean distance, with the distance.
+pub fn nearest_color(colors: &[(i32, [i32; 3])], rgb: [i32; 3]) -> Option<(i32, f64)> {
+    colors
+        .iter()
+        .map(|(id, code)| {
+            let distance = (0..3)
+                .map(|i| ((code[i] - rgb[i]) as f64).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            (*id, distance)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Maps color codes to the closest existing colors, creating the ones too far from any.
+#[derive(Debug, Clone, Default)]
+pub struct ColorQuantizer {
+    colors: Vec<(i32, [i32; 3])>,
+    tolerance: f64,
+    /// Colors created so far.
+    pub created: Vec<(i32, [i32; 3])>,
+}
+
+impl ColorQuantizer {
+    pub fn new(colors: Vec<(i32, [i32; 3])>, tolerance: f64) -> Self {
+        Self {
+            colors,
+            tolerance,
+            created: Vec::new(),
+        }
+    }
+
+    /// Id of the color to use for `rgb`.
+    pub async fn quantize(
+        &mut self,
+        tx: &mut Transaction<'static, MySql>,
+        rgb: [i32; 3],
+    ) -> Result<i32, String> {
+        if let Some((id, distance)) = nearest_color(&self.colors, rgb) {
+            if distance <= self.tolerance {
+                return Ok(id);
+            }
+        }
+
+        let (id, created) = get_or_create_color(tx, rgb).await?;
+        if created {
+            self.created.push((id, rgb));
+        }
+        self.colors.push((id, rgb));
+
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLORS: [[i32; 3]; 6] = [
+        [0, 0, 0],
+        [255, 255, 255],
+        [255, 0, 0],
+        [18, 200, 77],
+        [40, 60, 250],
+        [255, 128, 0],
+    ];
+
+    #[test]
+    fn hsv_round_trip() {
+        for rgb in COLORS {
+            let [h, s, v] = rgb_to_hsv(rgb);
+            assert_eq!(hsv_to_rgb(h, s, v), rgb);
+        }
+        assert_eq!(hsv_to_rgb(1.0 / 3.0, 1.0, 1.0), [0, 255, 0]);
+        assert_eq!(hsv_to_rgb(-1.0 / 3.0, 1.0, 1.0), [0, 0, 255]);
+    }
+
+    #[test]
+    fn hsl_round_trip() {
+        for rgb in COLORS {
+            let [h, s, l] = rgb_to_hsl(rgb);
+            assert_eq!(hsl_to_rgb(h, s, l), rgb);
+        }
+        assert_eq!(hsl_to_rgb(0.0, 1.0, 0.5), [255, 0, 0]);
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        for rgb in COLORS {
+            assert_eq!(hex_to_rgb(&rgb_to_hex(rgb)), Ok(rgb));
+        }
+        assert_eq!(rgb_to_hex([255, 128, 0]), "#ff8000");
+        assert_eq!(hex_to_rgb("f80"), Ok([255, 136, 0]));
+        assert_eq!(hex_to_rgb(" #FF8000 "), Ok([255, 128, 0]));
+    }
+
+    #[test]
+    fn hex_errors() {
+        for hex in ["", "#", "#ff80", "#ff80001", "#gg8000", "#ff8é"] {
+            assert_eq!(hex_to_rgb(hex), Err(format!("Invalid hex color: {hex}")));
+        }
+    }
+
+    #[test]
+    fn kelvin_round_trip() {
+        for kelvin in [1500.0, 2700.0, 4000.0, 6500.0, 10000.0] {
+            // neighbouring temperatures can round to the same color
+            assert!((rgb_to_kelvin(kelvin_to_rgb(kelvin)) - kelvin).abs() <= 100.0);
+        }
+        assert_eq!(kelvin_to_rgb(6600.0), [255, 255, 255]);
+        assert_eq!(kelvin_to_rgb(0.0), kelvin_to_rgb(KELVIN_RANGE.0));
+    }
+
+    #[test]
+    fn nearest() {
+        let colors = [(1, [0, 0, 0]), (2, [255, 0, 0]), (3, [0, 0, 255])];
+        assert_eq!(
+            nearest_color(&colors, [250, 3, 0]).map(|(id, _)| id),
+            Some(2)
+        );
+        assert_eq!(nearest_color(&colors, [0, 0, 0]), Some((1, 0.0)));
+        assert_eq!(nearest_color(&[], [0, 0, 0]), None);
+    }
+}
@@ -1,11 +1,12 @@
 //! Database setting utilities.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::db::types::control_data::ControlType;
 // use crate::db::types::dancer;
 use crate::global;
 use crate::types::global::{PartType, RedisControl, RedisPartControlData, RedisPosition, Revision};
+use crate::utils::quaternion::from_euler;
 use crate::utils::vector::partition_by_field;
 use itertools::Itertools;
 use redis::aio::MultiplexedConnection;
@@ -224,7 +225,11 @@ pub async fn init_redis_position(
                     COALESCE(PositionData.z, 0.0) AS "z!: f64",
                     PositionData.rx,
                     PositionData.ry,
-                    PositionData.rz
+                    PositionData.rz,
+                    PositionData.qw,
+                    PositionData.qx,
+                    PositionData.qy,
+                    PositionData.qz
                 FROM Dancer
                 INNER JOIN PositionData
                 ON Dancer.id = PositionData.dancer_id
@@ -243,6 +248,7 @@ pub async fn init_redis_position(
 
         let mut location: Vec<[f64; 3]> = Vec::new();
         let mut rotation: Vec<[f64; 3]> = Vec::new();
+        let mut quaternion: Vec<[f64; 4]> = Vec::new();
         let mut has_position: Vec<bool> = Vec::new();
 
         dancer_positions.iter().for_each(|dancer_position| {
@@ -253,6 +259,10 @@ pub async fn init_redis_position(
 
             location.push([pos.x, pos.y, pos.z]);
             rotation.push([pos.rx, pos.ry, pos.rz]);
+            quaternion.push(match (pos.qw, pos.qx, pos.qy, pos.qz) {
+                (Some(w), Some(x), Some(y), Some(z)) => [w, x, y, z],
+                _ => from_euler([pos.rx, pos.ry, pos.rz]),
+            });
             has_position.push(match pos.position_type.as_str() {
                 "NO_EFFECT" => false,
                 "POSITION" => true,
@@ -272,6 +282,7 @@ pub async fn init_redis_position(
             },
             location,
             rotation,
+            quaternion,
         };
 
         result.push((redis_key, serde_json::to_string(&result_control).unwrap()));
@@ -504,7 +515,11 @@ pub async fn update_redis_position(
                     COALESCE(PositionData.z, 0) AS z,
                     PositionData.rx,
                     PositionData.ry,
-                    PositionData.rz
+                    PositionData.rz,
+                    PositionData.qw,
+                    PositionData.qx,
+                    PositionData.qy,
+                    PositionData.qz
                 FROM Dancer
                 INNER JOIN PositionData
                 ON Dancer.id = PositionData.dancer_id
@@ -524,6 +539,7 @@ pub async fn update_redis_position(
 
     let mut location: Vec<[f64; 3]> = Vec::new();
     let mut rotation: Vec<[f64; 3]> = Vec::new();
+    let mut quaternion: Vec<[f64; 4]> = Vec::new();
     let mut has_position: Vec<bool> = Vec::new();
 
     dancer_positions.iter().for_each(|dancer_position| {
@@ -534,6 +550,10 @@ pub async fn update_redis_position(
 
         location.push([pos.x, pos.y, pos.z]);
         rotation.push([pos.rx, pos.ry, pos.rz]);
+        quaternion.push(match (pos.qw, pos.qx, pos.qy, pos.qz) {
+            (Some(w), Some(x), Some(y), Some(z)) => [w, x, y, z],
+            _ => from_euler([pos.rx, pos.ry, pos.rz]),
+        });
         has_position.push(match pos.position_type.as_str() {
             "NO_EFFECT" => false,
             "POSITION" => true,
@@ -553,6 +573,7 @@ pub async fn update_redis_position(
         },
         location,
         rotation,
+        quaternion,
     };
 
     let mut conn: MultiplexedConnection = redis_client
@@ -618,6 +639,40 @@ pub async fn get_redis_position(
     }
 }
 
+/// Refresh the redis cache of several control frames and return the new caches keyed by frame id.
+pub async fn update_redis_controls(
+    mysql_pool: &Pool<MySql>,
+    redis_client: &Client,
+    frame_ids: &[i32],
+) -> Result<HashMap<String, RedisControl>, String> {
+    let mut result = HashMap::new();
+
+    for frame_id in frame_ids {
+        update_redis_control(mysql_pool, redis_client, *frame_id).await?;
+        let redis_control = get_redis_control(redis_client, *frame_id).await?;
+        result.insert(frame_id.to_string(), redis_control);
+    }
+
+    Ok(result)
+}
+
+/// Refresh the redis cache of several position frames and return the new caches keyed by frame id.
+pub async fn update_redis_positions(
+    mysql_pool: &Pool<MySql>,
+    redis_client: &Client,
+    frame_ids: &[i32],
+) -> Result<HashMap<String, RedisPosition>, String> {
+    let mut result = HashMap::new();
+
+    for frame_id in frame_ids {
+        update_redis_position(mysql_pool, redis_client, *frame_id).await?;
+        let redis_position = get_redis_position(redis_client, *frame_id).await?;
+        result.insert(frame_id.to_string(), redis_position);
+    }
+
+    Ok(result)
+}
+
 pub async fn delete_redis_control(redis_client: &Client, frame_id: i32) -> Result<(), String> {
     let mut conn: MultiplexedConnection = redis_client
         .get_multiplexed_async_connection()
@@ -649,3 +704,63 @@ pub async fn delete_redis_position(redis_client: &Client, frame_id: i32) -> Resu
 
     Ok(())
 }
+
+/// Fail if any of the given control frames is being edited by another user.
+pub async fn check_editing_control_frames(
+    mysql_pool: &Pool<MySql>,
+    user_id: i32,
+    frame_ids: &[i32],
+) -> Result<(), String> {
+    let editing_frames = sqlx::query!(
+        r#"
+            SELECT frame_id AS "frame_id!", user_id
+            FROM EditingControlFrame
+            WHERE frame_id IS NOT NULL AND user_id != ?;
+        "#,
+        user_id
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match editing_frames
+        .iter()
+        .find(|editing| frame_ids.contains(&editing.frame_id))
+    {
+        Some(editing) => Err(format!(
+            "Control frame #{} is being edited by user #{}",
+            editing.frame_id, editing.user_id
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Fail if any of the given position frames is being edited by another user.
+pub async fn check_editing_position_frames(
+    mysql_pool: &Pool<MySql>,
+    user_id: i32,
+    frame_ids: &[i32],
+) -> Result<(), String> {
+    let editing_frames = sqlx::query!(
+        r#"
+            SELECT frame_id AS "frame_id!", user_id
+            FROM EditingPositionFrame
+            WHERE frame_id IS NOT NULL AND user_id != ?;
+        "#,
+        user_id
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match editing_frames
+        .iter()
+        .find(|editing| frame_ids.contains(&editing.frame_id))
+    {
+        Some(editing) => Err(format!(
+            "Position frame #{} is being edited by user #{}",
+            editing.frame_id, editing.user_id
+        )),
+        None => Ok(()),
+    }
+}
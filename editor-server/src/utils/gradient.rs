@@ -0,0 +1,315 @@
+//! Gradient utilities.
+//!
+//! Gradients are expanded to one color per bulb when assigned to an LED part, so the
+//! Redis cache, the exports and frameDat all read the same LEDBulb rows.
+
+use crate::db::types::gradient::{GradientData, GradientEasing, GradientSpace};
+use crate::utils::color::{hsv_to_rgb, rgb_to_hsv, ColorQuantizer};
+
+use itertools::Itertools;
+use sqlx::{MySql, Pool, Transaction};
+use std::collections::HashMap;
+
+/// Number of color steps between two stops. Bulbs take the color of the nearest step, so
+/// expanding a gradient adds a bounded number of colors whatever the length of the part.
+const SEGMENT_STEPS: usize = 16;
+
+/// A stop of a gradient, at a position from 0 (first bulb) to 1 (last bulb).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stop {
+    pub position: f64,
+    pub color_id: i32,
+    pub rgb: [i32; 3],
+    pub alpha: i32,
+}
+
+/// A gradient with its stops sorted by position.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    pub data: GradientData,
+    pub stops: Vec<Stop>,
+}
+
+pub fn ease(easing: GradientEasing, t: f64) -> f64 {
+    let t = t.clamp(0.0, 1.0);
+    match easing {
+        GradientEasing::Linear => t,
+        GradientEasing::EaseIn => t * t,
+        GradientEasing::EaseOut => t * (2.0 - t),
+        GradientEasing::EaseInOut => t * t * (3.0 - 2.0 * t),
+    }
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// Color and alpha of the gradient at `t`, holding the end stops outside of them.
+pub fn sample_gradient(
+    stops: &[Stop],
+    space: GradientSpace,
+    easing: GradientEasing,
+    t: f64,
+) -> ([i32; 3], i32) {
+    let index = stops.partition_point(|stop| stop.position <= t);
+    let (from, to) = match (index.checked_sub(1).map(|i| &stops[i]), stops.get(index)) {
+        (Some(from), Some(to)) => (from, to),
+        (Some(stop), None) | (None, Some(stop)) => return (stop.rgb, stop.alpha),
+        (None, None) => return ([0, 0, 0], 0),
+    };
+
+    let u = ease(easing, (t - from.position) / (to.position - from.position));
+    let alpha = lerp(from.alpha as f64, to.alpha as f64, u).round() as i32;
+
+    let rgb = match space {
+        GradientSpace::Rgb => {
+            let mut rgb = [0; 3];
+            for (i, value) in rgb.iter_mut().enumerate() {
+                *value = lerp(from.rgb[i] as f64, to.rgb[i] as f64, u).round() as i32;
+            }
+            rgb
+        }
+        GradientSpace::Hsv => {
+            let [h0, s0, v0] = rgb_to_hsv(from.rgb);
+            let [mut h1, s1, v1] = rgb_to_hsv(to.rgb);
+            // take the shorter way around the color wheel
+            if h1 - h0 > 0.5 {
+                h1 -= 1.0;
+            } else if h0 - h1 > 0.5 {
+                h1 += 1.0;
+            }
+            hsv_to_rgb(lerp(h0, h1, u), lerp(s0, s1, u), lerp(v0, v1, u))
+        }
+    };
+
+    (rgb, alpha)
+}
+
+/// Move `t` to the nearest of the evenly spaced steps between the stops around it.
+fn snap_to_step(stops: &[Stop], t: f64) -> f64 {
+    let index = stops.partition_point(|stop| stop.position <= t);
+    match (index.checked_sub(1).map(|i| &stops[i]), stops.get(index)) {
+        (Some(from), Some(to)) => {
+            let width = to.position - from.position;
+            let step = ((t - from.position) / width * SEGMENT_STEPS as f64).round();
+            from.position + width * step / SEGMENT_STEPS as f64
+        }
+        _ => t,
+    }
+}
+
+/// Color and alpha of every bulb of a part with `length` bulbs, in steps of the gradient.
+pub fn expand_gradient(
+    stops: &[Stop],
+    space: GradientSpace,
+    easing: GradientEasing,
+    length: usize,
+) -> Vec<([i32; 3], i32)> {
+    (0..length)
+        .map(|i| {
+            let t = match length {
+                1 => 0.0,
+                _ => i as f64 / (length - 1) as f64,
+            };
+            sample_gradient(stops, space, easing, snap_to_step(stops, t))
+        })
+        .collect_vec()
+}
+
+/// Check the stops of a gradient, sorting them by position.
+pub fn validate_stops(stops: &mut [Stop]) -> Result<(), String> {
+    if stops.is_empty() {
+        return Err("Gradient must have at least one stop".to_string());
+    }
+    if let Some(stop) = stops
+        .iter()
+        .find(|stop| !(0.0..=1.0).contains(&stop.position))
+    {
+        return Err(format!(
+            "Position {} of stop is not in [0, 1]",
+            stop.position
+        ));
+    }
+    if let Some(stop) = stops.iter().find(|stop| !(0..=255).contains(&stop.alpha)) {
+        return Err(format!("Alpha {} of stop is not in [0, 255]", stop.alpha));
+    }
+
+    stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+    if stops
+        .windows(2)
+        .any(|pair| pair[0].position == pair[1].position)
+    {
+        return Err("Stops must have distinct positions".to_string());
+    }
+
+    Ok(())
+}
+
+/// Load a gradient with the color codes of its stops.
+pub async fn get_gradient(mysql_pool: &Pool<MySql>, id: i32) -> Result<Gradient, String> {
+    let data = sqlx::query_as!(
+        GradientData,
+        r#"
+            SELECT
+                id,
+                name,
+                space AS "space: GradientSpace",
+                easing AS "easing: GradientEasing"
+            FROM Gradient
+            WHERE id = ?;
+        "#,
+        id
+    )
+    .fetch_optional(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or(format!("Gradient #{id} not found"))?;
+
+    let stops = sqlx::query!(
+        r#"
+            SELECT
+                GradientStop.position,
+                GradientStop.color_id,
+                GradientStop.alpha,
+                Color.r,
+                Color.g,
+                Color.b
+            FROM GradientStop
+            INNER JOIN Color ON GradientStop.color_id = Color.id
+            WHERE GradientStop.gradient_id = ?
+            ORDER BY GradientStop.position ASC;
+        "#,
+        id
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .into_iter()
+    .map(|stop| Stop {
+        position: stop.position,
+        color_id: stop.color_id,
+        rgb: [stop.r, stop.g, stop.b],
+        alpha: stop.alpha,
+    })
+    .collect_vec();
+
+    Ok(Gradient { data, stops })
+}
+
+/// Load every gradient, ordered by id.
+pub async fn get_gradients(mysql_pool: &Pool<MySql>) -> Result<Vec<Gradient>, String> {
+    let gradients = sqlx::query_as!(
+        GradientData,
+        r#"
+            SELECT
+                id,
+                name,
+                space AS "space: GradientSpace",
+                easing AS "easing: GradientEasing"
+            FROM Gradient
+            ORDER BY id ASC;
+        "#,
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut stops: HashMap<i32, Vec<Stop>> = HashMap::new();
+    sqlx::query!(
+        r#"
+            SELECT
+                GradientStop.gradient_id,
+                GradientStop.position,
+                GradientStop.color_id,
+                GradientStop.alpha,
+                Color.r,
+                Color.g,
+                Color.b
+            FROM GradientStop
+            INNER JOIN Color ON GradientStop.color_id = Color.id
+            ORDER BY GradientStop.gradient_id ASC, GradientStop.position ASC;
+        "#,
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .into_iter()
+    .for_each(|stop| {
+        stops.entry(stop.gradient_id).or_default().push(Stop {
+            position: stop.position,
+            color_id: stop.color_id,
+            rgb: [stop.r, stop.g, stop.b],
+            alpha: stop.alpha,
+        })
+    });
+
+    Ok(gradients
+        .into_iter()
+        .map(|data| Gradient {
+            stops: stops.remove(&data.id).unwrap_or_default(),
+            data,
+        })
+        .collect_vec())
+}
+
+/// Replace the bulbs of a control data with the expanded gradient and link it to the gradient.
+///
+/// The bulb colors are quantized to the existing colors, creating the missing steps.
+pub async fn write_gradient_bulbs(
+    tx: &mut Transaction<'static, MySql>,
+    quantizer: &mut ColorQuantizer,
+    control_id: i32,
+    gradient: &Gradient,
+    length: usize,
+) -> Result<(), String> {
+    let bulbs = expand_gradient(
+        &gradient.stops,
+        gradient.data.space,
+        gradient.data.easing,
+        length,
+    );
+
+    sqlx::query!(
+        r#"
+            DELETE FROM LEDBulb
+            WHERE control_id = ?;
+        "#,
+        control_id
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    for (position, (rgb, bulb_alpha)) in bulbs.into_iter().enumerate() {
+        let color_id = quantizer.quantize(tx, rgb).await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO LEDBulb (control_id, position, color_id, alpha)
+                VALUES (?, ?, ?, ?);
+            "#,
+            control_id,
+            position as i32,
+            color_id,
+            bulb_alpha
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    sqlx::query!(
+        r#"
+            UPDATE ControlData
+            SET type = "LED_BULBS", effect_id = NULL, color_id = NULL, gradient_id = ?
+            WHERE id = ?;
+        "#,
+        gradient.data.id,
+        control_id
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
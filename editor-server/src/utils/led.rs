@@ -0,0 +1,428 @@
+//! LED effect utilities.
+
+use crate::graphql::types::led::{LEDEffectData, LEDEffectFrame};
+
+use itertools::Itertools;
+use sqlx::{MySql, Pool, Transaction};
+use std::collections::{HashMap, HashSet};
+
+/// Load an LED effect with its states, in the shape published to subscribers.
+pub async fn get_led_effect_data(
+    mysql_pool: &Pool<MySql>,
+    effect_id: i32,
+) -> Result<LEDEffectData, String> {
+    let effect = sqlx::query!(
+        r#"
+            SELECT
+                LEDEffect.id,
+                LEDEffect.name,
+                Model.name AS "model_name",
+                Part.name AS "part_name"
+            FROM LEDEffect
+            INNER JOIN Model ON LEDEffect.model_id = Model.id
+            INNER JOIN Part ON LEDEffect.part_id = Part.id
+            WHERE LEDEffect.id = ?;
+        "#,
+        effect_id
+    )
+    .fetch_optional(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or(format!("LEDEffect Id {effect_id} not found"))?;
+
+    let leds = sqlx::query!(
+        r#"
+            SELECT color_id, alpha
+            FROM LEDEffectState
+            WHERE effect_id = ?
+            ORDER BY position ASC;
+        "#,
+        effect_id
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .into_iter()
+    .map(|state| [state.color_id, state.alpha])
+    .collect_vec();
+
+    Ok(LEDEffectData {
+        id: effect.id,
+        name: effect.name,
+        model_name: effect.model_name,
+        part_name: effect.part_name,
+        repeat: 0,
+        frames: vec![LEDEffectFrame {
+            leds,
+            fade: false,
+            start: 0,
+        }],
+    })
+}
+
+/// Create a single-frame LED effect with its states, returning the id of the effect.
+pub async fn insert_led_effect(
+    tx: &mut Transaction<'static, MySql>,
+    name: &str,
+    model_id: i32,
+    part_id: i32,
+    leds: &[[i32; 2]],
+) -> Result<i32, String> {
+    let effect_id = sqlx::query!(
+        r#"
+            INSERT INTO LEDEffect (name, model_id, part_id)
+            VALUES (?, ?, ?);
+        "#,
+        name,
+        model_id,
+        part_id
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_id() as i32;
+
+    for (position, led) in leds.iter().enumerate() {
+        sqlx::query!(
+            r#"
+                INSERT INTO LEDEffectState (effect_id, position, color_id, alpha)
+                VALUES (?, ?, ?, ?);
+            "#,
+            effect_id,
+            position as i32,
+            led[0],
+            led[1]
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(effect_id)
+}
+
+/// The LED effect states recolored by `recolor_led_effect`.
+pub struct RecoloredEffect {
+    pub state_count: i32,
+    /// The copy made for the control data in scope, when the effect is also used outside of it.
+    pub copy_id: Option<i32>,
+}
+
+/// Remap the colors of an LED effect for the control data in `scope_ids`.
+///
+/// An effect only used in scope is recolored in place; one also used outside
+/// of it is copied as "name (suffix)" and the control data in scope switch to
+/// the copy, their frames being added to `touched_frame_ids`.
+pub async fn recolor_led_effect(
+    tx: &mut Transaction<'static, MySql>,
+    effect_id: i32,
+    mapping: &HashMap<i32, i32>,
+    scope_ids: &HashSet<i32>,
+    suffix: &str,
+    touched_frame_ids: &mut Vec<i32>,
+) -> Result<Option<RecoloredEffect>, String> {
+    let states = sqlx::query!(
+        r#"
+            SELECT id, color_id, alpha FROM LEDEffectState
+            WHERE effect_id = ?
+            ORDER BY position ASC;
+        "#,
+        effect_id
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let state_count = states
+        .iter()
+        .filter(|state| mapping.contains_key(&state.color_id))
+        .count() as i32;
+    if state_count == 0 {
+        return Ok(None);
+    }
+
+    let users = sqlx::query!(
+        r#"
+            SELECT id, frame_id FROM ControlData
+            WHERE effect_id = ?;
+        "#,
+        effect_id
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    let (in_scope, out_of_scope): (Vec<_>, Vec<_>) = users
+        .into_iter()
+        .partition(|data| scope_ids.contains(&data.id));
+
+    if out_of_scope.is_empty() {
+        for state in &states {
+            if let Some(color_id) = mapping.get(&state.color_id) {
+                sqlx::query!(
+                    r#"
+                        UPDATE LEDEffectState
+                        SET color_id = ?
+                        WHERE id = ?;
+                    "#,
+                    color_id,
+                    state.id
+                )
+                .execute(&mut **tx)
+                .await
+                .map_err(|e| e.to_string())?;
+            }
+        }
+
+        return Ok(Some(RecoloredEffect {
+            state_count,
+            copy_id: None,
+        }));
+    }
+
+    if in_scope.is_empty() {
+        return Ok(None);
+    }
+
+    let effect = sqlx::query!(
+        r#"
+            SELECT name, model_id, part_id FROM LEDEffect
+            WHERE id = ?;
+        "#,
+        effect_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let existing = sqlx::query!(
+        r#"
+            SELECT name FROM LEDEffect
+            WHERE model_id = ? AND part_id = ?;
+        "#,
+        effect.model_id,
+        effect.part_id
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    let base = format!("{} ({})", effect.name, suffix);
+    let mut name = base.clone();
+    for index in 2.. {
+        if !existing.iter().any(|effect| effect.name == name) {
+            break;
+        }
+        name = format!("{base} {index}");
+    }
+
+    let leds = states
+        .iter()
+        .map(|state| {
+            [
+                *mapping.get(&state.color_id).unwrap_or(&state.color_id),
+                state.alpha,
+            ]
+        })
+        .collect_vec();
+    let copy_id = insert_led_effect(tx, &name, effect.model_id, effect.part_id, &leds).await?;
+
+    for data in &in_scope {
+        sqlx::query!(
+            r#"
+                UPDATE ControlData
+                SET effect_id = ?
+                WHERE id = ?;
+            "#,
+            copy_id,
+            data.id
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        if !touched_frame_ids.contains(&data.frame_id) {
+            touched_frame_ids.push(data.frame_id);
+        }
+    }
+
+    Ok(Some(RecoloredEffect {
+        state_count,
+        copy_id: Some(copy_id),
+    }))
+}
+
+/// Scale a list of LED states over a new length (nearest neighbour).
+pub fn resample_leds<T: Clone>(leds: &[T], length: usize) -> Vec<T> {
+    if leds.is_empty() {
+        return Vec::new();
+    }
+
+    (0..length)
+        .map(|i| leds[i * leds.len() / length].clone())
+        .collect_vec()
+}
+
+/// Keep a list of LED states in place over a new length, truncating it or padding it with `off`.
+pub fn pad_leds<T: Clone>(leds: &[T], length: usize, off: T) -> Vec<T> {
+    (0..length)
+        .map(|i| leds.get(i).cloned().unwrap_or_else(|| off.clone()))
+        .collect_vec()
+}
+
+/// Repeat a list of LED states over a new length.
+pub fn tile_leds<T: Clone>(leds: &[T], length: usize) -> Vec<T> {
+    if leds.is_empty() {
+        return Vec::new();
+    }
+
+    (0..length)
+        .map(|i| leds[i % leds.len()].clone())
+        .collect_vec()
+}
+
+/// Scale a list of values over a new length, blending neighbours linearly.
+///
+/// The first and last values stay at the ends of the list.
+pub fn blend_leds<const N: usize>(leds: &[[f64; N]], length: usize) -> Vec<[f64; N]> {
+    if leds.is_empty() || length == 0 {
+        return Vec::new();
+    }
+    if length == 1 || leds.len() == 1 {
+        return vec![leds[0]; length];
+    }
+
+    (0..length)
+        .map(|i| {
+            let x = i as f64 * (leds.len() - 1) as f64 / (length - 1) as f64;
+            let index = (x.floor() as usize).min(leds.len() - 2);
+            let t = x - index as f64;
+
+            let (a, b) = (leds[index], leds[index + 1]);
+            let mut value = [0.0; N];
+            for (k, v) in value.iter_mut().enumerate() {
+                *v = a[k] + (b[k] - a[k]) * t;
+            }
+            value
+        })
+        .collect_vec()
+}
+
+/// Hues swept by one rainbow, as colors along the strip shifting by one strip per cycle.
+pub fn rainbow(colors: &[i32], length: usize, phase: f64) -> Vec<[i32; 2]> {
+    (0..length)
+        .map(|i| {
+            let hue = (i as f64 / length as f64 + phase).rem_euclid(1.0);
+            let index = ((hue * colors.len() as f64) as usize).min(colors.len() - 1);
+            [colors[index], 255]
+        })
+        .collect_vec()
+}
+
+/// Every `spacing`-th LED lit, cycling through the colors, moving one strip per cycle.
+pub fn chase(colors: &[i32], length: usize, spacing: usize, phase: f64) -> Vec<[i32; 2]> {
+    let shift = (phase * length as f64).floor() as usize;
+    (0..length)
+        .map(|i| {
+            let offset = (i + length - shift % length) % length;
+            let color = colors[offset / spacing % colors.len()];
+            [color, if offset % spacing == 0 { 255 } else { 0 }]
+        })
+        .collect_vec()
+}
+
+/// The whole strip fading in and out once per cycle.
+pub fn breathe(color: i32, length: usize, phase: f64) -> Vec<[i32; 2]> {
+    let alpha = (255.0 * (1.0 - (2.0 * std::f64::consts::PI * phase).cos()) / 2.0).round();
+    vec![[color, alpha as i32]; length]
+}
+
+/// Random LEDs lit with random colors, about `density` of the strip at a time.
+pub fn sparkle(colors: &[i32], length: usize, density: f64, seed: &mut u64) -> Vec<[i32; 2]> {
+    (0..length)
+        .map(|_| {
+            let lit = next_random(seed) < density;
+            let color = colors[(next_random(seed) * colors.len() as f64) as usize % colors.len()];
+            [color, if lit { 255 } else { 0 }]
+        })
+        .collect_vec()
+}
+
+/// A head crossing the strip once per cycle, followed by a tail fading over `tail` LEDs.
+pub fn comet(color: i32, length: usize, tail: usize, phase: f64) -> Vec<[i32; 2]> {
+    let head = phase * (length + tail) as f64;
+    (0..length)
+        .map(|i| {
+            let distance = head - i as f64;
+            let alpha = if (0.0..tail as f64).contains(&distance) {
+                255.0 * (1.0 - distance / tail as f64)
+            } else {
+                0.0
+            };
+            [color, alpha.round() as i32]
+        })
+        .collect_vec()
+}
+
+/// Deterministic xorshift random number in [0, 1).
+fn next_random(seed: &mut u64) -> f64 {
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 7;
+    *seed ^= *seed << 17;
+    (*seed >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resample_stretches() {
+        assert_eq!(resample_leds(&[1, 2], 4), vec![1, 1, 2, 2]);
+        assert_eq!(resample_leds(&[1, 2, 3, 4], 2), vec![1, 3]);
+        assert_eq!(resample_leds(&[1, 2, 3], 3), vec![1, 2, 3]);
+        assert_eq!(resample_leds(&[1, 2, 3], 0), Vec::<i32>::new());
+        assert_eq!(resample_leds(&[] as &[i32], 3), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn pad_keeps_positions() {
+        assert_eq!(pad_leds(&[1, 2], 4, 0), vec![1, 2, 0, 0]);
+        assert_eq!(pad_leds(&[1, 2, 3], 2, 0), vec![1, 2]);
+        assert_eq!(pad_leds(&[], 2, 0), vec![0, 0]);
+    }
+
+    #[test]
+    fn tile_repeats() {
+        assert_eq!(tile_leds(&[1, 2], 5), vec![1, 2, 1, 2, 1]);
+        assert_eq!(tile_leds(&[] as &[i32], 3), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn blend_keeps_ends() {
+        assert_eq!(
+            blend_leds(&[[0.0], [10.0]], 5),
+            vec![[0.0], [2.5], [5.0], [7.5], [10.0]]
+        );
+        assert_eq!(blend_leds(&[[0.0], [5.0], [10.0]], 2), vec![[0.0], [10.0]]);
+        assert_eq!(blend_leds(&[[3.0]], 3), vec![[3.0]; 3]);
+    }
+
+    #[test]
+    fn patterns_fill_the_strip() {
+        assert_eq!(
+            chase(&[7], 6, 3, 0.0),
+            vec![[7, 255], [7, 0], [7, 0], [7, 255], [7, 0], [7, 0]]
+        );
+        assert_eq!(breathe(7, 2, 0.5), vec![[7, 255]; 2]);
+        assert_eq!(
+            comet(7, 4, 2, 0.5),
+            vec![[7, 0], [7, 0], [7, 128], [7, 255]]
+        );
+
+        let mut seed = 1;
+        let lit = sparkle(&[7, 8], 1000, 0.25, &mut seed)
+            .iter()
+            .filter(|[_, alpha]| *alpha == 255)
+            .count();
+        assert!((200..300).contains(&lit));
+    }
+}
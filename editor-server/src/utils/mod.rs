@@ -1,7 +1,14 @@
 //! Helper functions for the application.
 
 pub mod authentication;
+pub mod color;
 pub mod data;
+pub mod gradient;
 pub mod graphiql;
+pub mod led;
+pub mod motion;
+pub mod palette;
+pub mod position;
+pub mod quaternion;
 pub mod revision;
 pub mod vector;
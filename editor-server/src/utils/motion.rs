@@ -0,0 +1,307 @@
+//! Procedural motion primitives for generating position frames.
+
+use crate::utils::quaternion::{from_axis_angle, from_euler, multiply, to_euler, Quaternion};
+
+use std::f64::consts::PI;
+
+/// Location and rotation (in radians) of a dancer.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pose {
+    pub location: [f64; 3],
+    pub rotation: [f64; 3],
+    /// The stored quaternion of the rotation, which takes precedence over `rotation`.
+    pub quaternion: Option<Quaternion>,
+}
+
+impl Pose {
+    /// The rotation as a quaternion.
+    pub fn orientation(&self) -> Quaternion {
+        self.quaternion.unwrap_or_else(|| from_euler(self.rotation))
+    }
+}
+
+/// Angle in radians turned after `elapsed` milliseconds at `rpm`.
+fn turned(rpm: f64, elapsed: i32) -> f64 {
+    2.0 * PI * rpm * elapsed as f64 / 60000.0
+}
+
+/// The two axes spanning the plane perpendicular to `axis`, in right-handed order.
+fn plane_of(axis: usize) -> (usize, usize) {
+    ((axis + 1) % 3, (axis + 2) % 3)
+}
+
+/// Spin in place about the world `axis`.
+pub fn rotate(base: Pose, axis: usize, rpm: f64, elapsed: i32) -> Pose {
+    let orientation = multiply(
+        from_axis_angle(axis, turned(rpm, elapsed)),
+        base.orientation(),
+    );
+
+    let mut pose = base;
+    pose.rotation = to_euler(orientation);
+    pose.quaternion = Some(orientation);
+    pose
+}
+
+/// Circle around `center` in the plane perpendicular to `axis`, starting at angle `phase`.
+pub fn orbit(
+    base: Pose,
+    center: [f64; 3],
+    radius: f64,
+    axis: usize,
+    rpm: f64,
+    phase: f64,
+    elapsed: i32,
+) -> Pose {
+    let angle = phase + turned(rpm, elapsed);
+    let (u, v) = plane_of(axis);
+
+    let mut pose = base;
+    pose.location[u] = center[u] + radius * angle.cos();
+    pose.location[v] = center[v] + radius * angle.sin();
+    pose.location[axis] = center[axis];
+    pose
+}
+
+/// Move along a straight line, `progress` going from 0 to 1.
+pub fn linear(base: Pose, from: [f64; 3], to: [f64; 3], progress: f64) -> Pose {
+    let mut pose = base;
+    for i in 0..3 {
+        pose.location[i] = from[i] + (to[i] - from[i]) * progress;
+    }
+    pose
+}
+
+/// Move along a parabola peaking `height` above the straight line, upwards being `axis`.
+pub fn arc(
+    base: Pose,
+    from: [f64; 3],
+    to: [f64; 3],
+    height: f64,
+    axis: usize,
+    progress: f64,
+) -> Pose {
+    let mut pose = linear(base, from, to, progress);
+    pose.location[axis] += 4.0 * height * progress * (1.0 - progress);
+    pose
+}
+
+/// An affine transform of a formation, applied as mirror, scale, rotate, then translate.
+///
+/// Rotations are about world axes through the pivot.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Transform {
+    pub pivot: [f64; 3],
+    /// Axis normal to the mirror plane through the pivot.
+    pub mirror: Option<usize>,
+    /// Positive factor; mirroring is done with `mirror`.
+    pub scale: f64,
+    /// Angle in radians and the axis through the pivot to rotate about.
+    pub rotate: Option<(f64, usize)>,
+    pub translate: [f64; 3],
+}
+
+impl Transform {
+    pub fn apply(&self, pose: Pose) -> Pose {
+        let mut location = [0.0; 3];
+        for (i, value) in location.iter_mut().enumerate() {
+            *value = pose.location[i] - self.pivot[i];
+        }
+        let mut rotation = pose.rotation;
+        let mut quaternion = pose.quaternion;
+
+        // a reflection keeps the rotation about its normal and flips the others
+        if let Some(axis) = self.mirror {
+            location[axis] = -location[axis];
+            let (u, v) = plane_of(axis);
+            rotation[u] = -rotation[u];
+            rotation[v] = -rotation[v];
+            if let Some(q) = quaternion.as_mut() {
+                q[u + 1] = -q[u + 1];
+                q[v + 1] = -q[v + 1];
+            }
+        }
+
+        for value in location.iter_mut() {
+            *value *= self.scale;
+        }
+
+        if let Some((angle, axis)) = self.rotate {
+            let (u, v) = plane_of(axis);
+            let (sin, cos) = angle.sin_cos();
+            let (a, b) = (location[u], location[v]);
+            location[u] = a * cos - b * sin;
+            location[v] = a * sin + b * cos;
+            let orientation = multiply(
+                from_axis_angle(axis, angle),
+                quaternion.unwrap_or_else(|| from_euler(rotation)),
+            );
+            rotation = to_euler(orientation);
+            quaternion = Some(orientation);
+        }
+
+        for (i, value) in location.iter_mut().enumerate() {
+            *value += self.pivot[i] + self.translate[i];
+        }
+
+        Pose {
+            location,
+            rotation,
+            quaternion,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::quaternion::dot;
+
+    fn assert_close(a: [f64; 3], b: [f64; 3]) {
+        assert!((0..3).all(|i| (a[i] - b[i]).abs() < 1e-9), "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn rotate_turns_with_time() {
+        let pose = rotate(Pose::default(), 2, 60.0, 250);
+        assert_close(pose.rotation, [0.0, 0.0, PI / 2.0]);
+        assert_close(pose.location, [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn rotate_spins_stored_quaternion() {
+        let base = Pose {
+            quaternion: Some(from_axis_angle(0, PI / 2.0)),
+            ..Default::default()
+        };
+        let pose = rotate(base, 2, 60.0, 250);
+
+        let expected = multiply(from_axis_angle(2, PI / 2.0), from_axis_angle(0, PI / 2.0));
+        assert!((dot(pose.orientation(), expected).abs() - 1.0).abs() < 1e-9);
+        assert!((dot(from_euler(pose.rotation), expected).abs() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn orbit_circles_the_center() {
+        let center = [1.0, 2.0, 3.0];
+        let pose = orbit(Pose::default(), center, 2.0, 2, 60.0, 0.0, 0);
+        assert_close(pose.location, [3.0, 2.0, 3.0]);
+
+        let pose = orbit(Pose::default(), center, 2.0, 2, 60.0, 0.0, 250);
+        assert_close(pose.location, [1.0, 4.0, 3.0]);
+    }
+
+    #[test]
+    fn linear_and_arc() {
+        let (from, to) = ([0.0, 0.0, 0.0], [2.0, 4.0, 0.0]);
+        assert_close(
+            linear(Pose::default(), from, to, 0.5).location,
+            [1.0, 2.0, 0.0],
+        );
+        assert_close(
+            arc(Pose::default(), from, to, 1.0, 2, 0.5).location,
+            [1.0, 2.0, 1.0],
+        );
+        assert_close(arc(Pose::default(), from, to, 1.0, 2, 1.0).location, to);
+    }
+
+    #[test]
+    fn transform_scales_and_translates_about_pivot() {
+        let transform = Transform {
+            pivot: [1.0, 1.0, 0.0],
+            scale: 2.0,
+            translate: [0.0, 0.0, 1.0],
+            ..Default::default()
+        };
+        let pose = Pose {
+            location: [2.0, 3.0, 0.0],
+            ..Default::default()
+        };
+        assert_close(transform.apply(pose).location, [3.0, 5.0, 1.0]);
+    }
+
+    #[test]
+    fn transform_mirrors() {
+        let transform = Transform {
+            mirror: Some(0),
+            scale: 1.0,
+            ..Default::default()
+        };
+        let pose = Pose {
+            location: [2.0, 3.0, 0.0],
+            rotation: [0.1, 0.2, 0.3],
+            ..Default::default()
+        };
+        let pose = transform.apply(pose);
+        assert_close(pose.location, [-2.0, 3.0, 0.0]);
+        assert_close(pose.rotation, [0.1, -0.2, -0.3]);
+    }
+
+    #[test]
+    fn transform_rotates_about_world_axes() {
+        let transform = Transform {
+            scale: 1.0,
+            rotate: Some((PI / 2.0, 0)),
+            ..Default::default()
+        };
+        let pose = Pose {
+            location: [0.0, 1.0, 0.0],
+            rotation: [0.0, 0.0, PI / 2.0],
+            ..Default::default()
+        };
+        let pose = transform.apply(pose);
+        assert_close(pose.location, [0.0, 0.0, 1.0]);
+
+        // the rotation about the world X axis follows the existing one
+        let expected = multiply(from_axis_angle(0, PI / 2.0), from_axis_angle(2, PI / 2.0));
+        let q = from_euler(pose.rotation);
+        let dot: f64 = (0..4).map(|i| q[i] * expected[i]).sum();
+        assert!((dot.abs() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn transform_keeps_stored_quaternion() {
+        let stored = from_axis_angle(1, 0.4);
+
+        let transform = Transform {
+            scale: 2.0,
+            translate: [1.0, 0.0, 0.0],
+            ..Default::default()
+        };
+        let pose = Pose {
+            quaternion: Some(stored),
+            ..Default::default()
+        };
+        assert_eq!(transform.apply(pose).quaternion, Some(stored));
+
+        let transform = Transform {
+            scale: 1.0,
+            mirror: Some(0),
+            rotate: Some((PI / 2.0, 2)),
+            ..Default::default()
+        };
+        let pose = transform.apply(pose);
+
+        // mirroring about X turns the rotation about Y the other way
+        let expected = multiply(from_axis_angle(2, PI / 2.0), from_axis_angle(1, -0.4));
+        assert!((dot(pose.orientation(), expected).abs() - 1.0).abs() < 1e-9);
+        assert!((dot(from_euler(pose.rotation), expected).abs() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn transform_rotates_about_pivot() {
+        let transform = Transform {
+            pivot: [1.0, 0.0, 0.0],
+            scale: 1.0,
+            rotate: Some((PI / 2.0, 2)),
+            ..Default::default()
+        };
+        let pose = Pose {
+            location: [2.0, 0.0, 0.0],
+            ..Default::default()
+        };
+        let pose = transform.apply(pose);
+        assert_close(pose.location, [1.0, 1.0, 0.0]);
+        assert_close(pose.rotation, [0.0, 0.0, PI / 2.0]);
+    }
+}
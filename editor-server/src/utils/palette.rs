@@ -0,0 +1,166 @@
+//! Palette utilities.
+
+use crate::graphql::types::palette::{Palette, PaletteColor, PaletteSection};
+
+use itertools::Itertools;
+use sqlx::{MySql, Pool};
+use std::collections::HashMap;
+
+/// Load the palettes with their colors and sections, every palette if `id` is not given.
+pub async fn get_palettes(
+    mysql_pool: &Pool<MySql>,
+    id: Option<i32>,
+) -> Result<Vec<Palette>, String> {
+    let palettes = sqlx::query!(
+        r#"
+            SELECT id, name FROM Palette
+            WHERE ? IS NULL OR id = ?
+            ORDER BY id ASC;
+        "#,
+        id,
+        id
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut colors: HashMap<i32, Vec<PaletteColor>> = HashMap::new();
+    sqlx::query!(
+        r#"
+            SELECT palette_id, role, color_id FROM PaletteColor
+            ORDER BY palette_id ASC, position ASC;
+        "#,
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .into_iter()
+    .for_each(|color| {
+        colors
+            .entry(color.palette_id)
+            .or_default()
+            .push(PaletteColor {
+                role: color.role,
+                color_id: color.color_id,
+            })
+    });
+
+    let mut sections: HashMap<i32, Vec<PaletteSection>> = HashMap::new();
+    sqlx::query!(
+        r#"
+            SELECT id, palette_id, start, end FROM PaletteSection
+            ORDER BY start ASC;
+        "#,
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .into_iter()
+    .for_each(|section| {
+        sections
+            .entry(section.palette_id)
+            .or_default()
+            .push(PaletteSection {
+                id: section.id,
+                start: section.start,
+                end: section.end,
+            })
+    });
+
+    Ok(palettes
+        .into_iter()
+        .map(|palette| Palette {
+            id: palette.id,
+            name: palette.name,
+            colors: colors.remove(&palette.id).unwrap_or_default(),
+            sections: sections.remove(&palette.id).unwrap_or_default(),
+        })
+        .collect_vec())
+}
+
+/// Load a palette with its colors and sections.
+pub async fn get_palette(mysql_pool: &Pool<MySql>, id: i32) -> Result<Palette, String> {
+    get_palettes(mysql_pool, Some(id))
+        .await?
+        .pop()
+        .ok_or(format!("Palette #{id} not found"))
+}
+
+/// Map the colors of `from` to the colors of `to` with the same role.
+///
+/// Roles missing in either palette are left out. A color having several roles in `from`
+/// must map to a single color.
+pub fn role_mapping(
+    from: &[PaletteColor],
+    to: &[PaletteColor],
+) -> Result<HashMap<i32, i32>, String> {
+    let mut mapping = HashMap::new();
+
+    for color in from {
+        let target = match to.iter().find(|target| target.role == color.role) {
+            Some(target) => target.color_id,
+            None => continue,
+        };
+
+        match mapping.insert(color.color_id, target) {
+            Some(previous) if previous != target => {
+                return Err(format!(
+                    "Color #{} has several roles mapping to different colors",
+                    color.color_id
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    mapping.retain(|from, to| from != to);
+
+    Ok(mapping)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette(colors: &[(&str, i32)]) -> Vec<PaletteColor> {
+        colors
+            .iter()
+            .map(|(role, color_id)| PaletteColor {
+                role: role.to_string(),
+                color_id: *color_id,
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn maps_by_role() {
+        let from = palette(&[("primary", 1), ("accent", 2), ("background", 3)]);
+        let to = palette(&[("accent", 12), ("primary", 11), ("highlight", 14)]);
+
+        let mapping = role_mapping(&from, &to).unwrap();
+        assert_eq!(mapping, HashMap::from([(1, 11), (2, 12)]));
+    }
+
+    #[test]
+    fn skips_unchanged_colors() {
+        let from = palette(&[("primary", 1), ("accent", 2)]);
+        let to = palette(&[("primary", 1), ("accent", 3)]);
+
+        let mapping = role_mapping(&from, &to).unwrap();
+        assert_eq!(mapping, HashMap::from([(2, 3)]));
+    }
+
+    #[test]
+    fn color_with_several_roles() {
+        let from = palette(&[("primary", 1), ("accent", 1)]);
+
+        let same = palette(&[("primary", 5), ("accent", 5)]);
+        assert_eq!(role_mapping(&from, &same), Ok(HashMap::from([(1, 5)])));
+
+        let different = palette(&[("primary", 5), ("accent", 6)]);
+        assert_eq!(
+            role_mapping(&from, &different),
+            Err("Color #1 has several roles mapping to different colors".to_string())
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//! Position timeline utilities.
+
+use crate::utils::motion::Pose;
+use crate::utils::quaternion::{dot, from_euler, slerp, to_euler, Quaternion};
+use crate::utils::vector::partition_by_field;
+
+use itertools::Itertools;
+use sqlx::{MySql, Pool};
+
+/// The keyframes of a dancer, skipping the frames where the dancer has no position.
+#[derive(Debug, Clone, Default)]
+pub struct DancerTrack {
+    pub dancer_id: i32,
+    pub name: String,
+    pub keys: Vec<(i32, Pose)>,
+    /// Starts of the NO_EFFECT frames of the dancer, splines do not reach across them.
+    pub gaps: Vec<i32>,
+}
+
+/// Load the position keyframes of every dancer, ordered by dancer id and start.
+pub async fn get_dancer_tracks(mysql_pool: &Pool<MySql>) -> Result<Vec<DancerTrack>, String> {
+    let dancers = sqlx::query!(
+        r#"
+            SELECT id, name FROM Dancer
+            ORDER BY id ASC;
+        "#,
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let keys = sqlx::query!(
+        r#"
+            SELECT
+                PositionData.dancer_id,
+                PositionFrame.start,
+                PositionData.x AS "x!",
+                PositionData.y AS "y!",
+                PositionData.z AS "z!",
+                PositionData.rx,
+                PositionData.ry,
+                PositionData.rz,
+                PositionData.qw,
+                PositionData.qx,
+                PositionData.qy,
+                PositionData.qz
+            FROM PositionData
+            INNER JOIN PositionFrame ON PositionData.frame_id = PositionFrame.id
+            WHERE PositionData.type = 'POSITION'
+            ORDER BY PositionData.dancer_id ASC, PositionFrame.start ASC;
+        "#,
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut gaps = sqlx::query!(
+        r#"
+            SELECT PositionData.dancer_id, PositionFrame.start
+            FROM PositionData
+            INNER JOIN PositionFrame ON PositionData.frame_id = PositionFrame.id
+            WHERE PositionData.type = 'NO_EFFECT'
+            ORDER BY PositionData.dancer_id ASC, PositionFrame.start ASC;
+        "#,
+    )
+    .fetch_all(mysql_pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .into_iter()
+    .map(|gap| (gap.dancer_id, gap.start))
+    .into_group_map();
+
+    let mut keys = partition_by_field(|key| key.dancer_id, keys).into_iter();
+    let mut current = keys.next();
+
+    let mut tracks = Vec::new();
+    for dancer in dancers {
+        let mut track = DancerTrack {
+            dancer_id: dancer.id,
+            name: dancer.name,
+            keys: Vec::new(),
+            gaps: gaps.remove(&dancer.id).unwrap_or_default(),
+        };
+
+        if current
+            .as_ref()
+            .is_some_and(|keys| keys[0].dancer_id == dancer.id)
+        {
+            track.keys = current
+                .take()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|key| {
+                    (
+                        key.start,
+                        Pose {
+                            location: [key.x, key.y, key.z],
+                            rotation: [key.rx, key.ry, key.rz],
+                            quaternion: match (key.qw, key.qx, key.qy, key.qz) {
+                                (Some(w), Some(x), Some(y), Some(z)) => Some([w, x, y, z]),
+                                _ => None,
+                            },
+                        },
+                    )
+                })
+                .collect_vec();
+            current = keys.next();
+        }
+
+        tracks.push(track);
+    }
+
+    Ok(tracks)
+}
+
+fn lerp(a: [f64; 3], b: [f64; 3], t: f64) -> [f64; 3] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+    ]
+}
+
+/// Interpolate the rotation between two keys, turning the way the keys are authored.
+///
+/// Quaternions keep the direction of turns up to a full turn; a full turn between the keys is
+/// only visible in the Euler angles, so it is interpolated there.
+fn interpolate_rotation(from: &Pose, to: &Pose, t: f64) -> Quaternion {
+    let (a, b) = (from.orientation(), to.orientation());
+
+    if dot(a, b) < -0.9995 {
+        from_euler(lerp(from.rotation, to.rotation, t))
+    } else {
+        slerp(a, b, t)
+    }
+}
+
+/// A pose at the interpolated location and rotation.
+fn interpolated_pose(location: [f64; 3], quaternion: Quaternion) -> Pose {
+    Pose {
+        location,
+        rotation: to_euler(quaternion),
+        quaternion: Some(quaternion),
+    }
+}
+
+fn catmull_rom(p0: [f64; 3], p1: [f64; 3], p2: [f64; 3], p3: [f64; 3], t: f64) -> [f64; 3] {
+    let (t2, t3) = (t * t, t * t * t);
+    let mut result = [0.0; 3];
+    for (i, value) in result.iter_mut().enumerate() {
+        *value = 0.5
+            * (2.0 * p1[i]
+                + (p2[i] - p0[i]) * t
+                + (2.0 * p0[i] - 5.0 * p1[i] + 4.0 * p2[i] - p3[i]) * t2
+                + (3.0 * p1[i] - p0[i] - 3.0 * p2[i] + p3[i]) * t3);
+    }
+    result
+}
+
+impl DancerTrack {
+    /// Index of the last key at or before `time`, if any.
+    fn key_before(&self, time: i32) -> Option<usize> {
+        match self.keys.partition_point(|(start, _)| *start <= time) {
+            0 => None,
+            index => Some(index - 1),
+        }
+    }
+
+    /// The last NO_EFFECT frame strictly between the keys at `a` and `b`, if any.
+    fn last_gap(&self, a: usize, b: usize) -> Option<i32> {
+        let (from, to) = (self.keys[a].0, self.keys[b].0);
+        self.gaps
+            .iter()
+            .copied()
+            .filter(|gap| from < *gap && *gap < to)
+            .max()
+    }
+
+    /// Whether a NO_EFFECT frame lies strictly between the keys at `a` and `b`.
+    fn has_gap(&self, a: usize, b: usize) -> bool {
+        self.last_gap(a, b).is_some()
+    }
+
+    /// Linearly interpolated location and slerped rotation at `time`, holding the first and last keys.
+    ///
+    /// NO_EFFECT frames keep the pose of the key before them, the move to the next key starting
+    /// from the last of them.
+    pub fn pose_at(&self, time: i32) -> Option<Pose> {
+        let first = self.keys.first()?;
+
+        let index = match self.key_before(time) {
+            Some(index) => index,
+            None => return Some(first.1),
+        };
+
+        let (mut start, from) = self.keys[index];
+        match self.keys.get(index + 1) {
+            Some((end, to)) => {
+                if let Some(gap) = self.last_gap(index, index + 1) {
+                    if time <= gap {
+                        return Some(from);
+                    }
+                    start = gap;
+                }
+
+                let t = (time - start) as f64 / (end - start) as f64;
+                Some(interpolated_pose(
+                    lerp(from.location, to.location, t),
+                    interpolate_rotation(&from, to, t),
+                ))
+            }
+            None => Some(from),
+        }
+    }
+
+    /// Catmull-Rom interpolated location and slerped rotation at `time`, holding the first and last keys.
+    ///
+    /// The outer control points are clamped to the segment at the ends of the track and at
+    /// NO_EFFECT frames, which hold the pose as in `pose_at`. Only the location follows the
+    /// spline, the rotation is interpolated between the two keys of the segment.
+    pub fn spline_pose_at(&self, time: i32) -> Option<Pose> {
+        let first = self.keys.first()?;
+
+        let index = match self.key_before(time) {
+            Some(index) => index,
+            None => return Some(first.1),
+        };
+
+        let (mut start, p1) = self.keys[index];
+        let (end, p2) = match self.keys.get(index + 1) {
+            Some(key) => *key,
+            None => return Some(p1),
+        };
+
+        let gap = self.last_gap(index, index + 1);
+        if let Some(gap) = gap {
+            if time <= gap {
+                return Some(p1);
+            }
+            start = gap;
+        }
+
+        let p0 = match index {
+            0 => p1,
+            _ if gap.is_some() || self.has_gap(index - 1, index) => p1,
+            _ => self.keys[index - 1].1,
+        };
+        let p3 = match self.keys.get(index + 2) {
+            Some(key) if !self.has_gap(index + 1, index + 2) => key.1,
+            _ => p2,
+        };
+
+        let t = (time - start) as f64 / (end - start) as f64;
+        Some(interpolated_pose(
+            catmull_rom(p0.location, p1.location, p2.location, p3.location, t),
+            interpolate_rotation(&p1, &p2, t),
+        ))
+    }
+}
+
+/// Whether a point lies inside a polygon, by ray casting.
+pub fn polygon_contains(polygon: &[[f64; 2]], point: [f64; 2]) -> bool {
+    let mut inside = false;
+    let mut j = polygon.len().wrapping_sub(1);
+
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[j]);
+        if (a[1] > point[1]) != (b[1] > point[1])
+            && point[0] < (b[0] - a[0]) * (point[1] - a[1]) / (b[1] - a[1]) + a[0]
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: [f64; 3], b: [f64; 3]) {
+        assert!((0..3).all(|i| (a[i] - b[i]).abs() < 1e-9), "{a:?} != {b:?}");
+    }
+
+    fn at(location: [f64; 3]) -> Pose {
+        Pose {
+            location,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn polygon_contains_points() {
+        let square = [[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]];
+        assert!(polygon_contains(&square, [1.0, 1.0]));
+        assert!(!polygon_contains(&square, [3.0, 1.0]));
+        assert!(!polygon_contains(&square, [1.0, -1.0]));
+
+        // the notch of a U shape is outside
+        let u = [
+            [0.0, 0.0],
+            [3.0, 0.0],
+            [3.0, 3.0],
+            [2.0, 3.0],
+            [2.0, 1.0],
+            [1.0, 1.0],
+            [1.0, 3.0],
+            [0.0, 3.0],
+        ];
+        assert!(polygon_contains(&u, [0.5, 2.0]));
+        assert!(!polygon_contains(&u, [1.5, 2.0]));
+
+        assert!(!polygon_contains(&[], [0.0, 0.0]));
+    }
+
+    #[test]
+    fn pose_at_interpolates_and_holds() {
+        let track = DancerTrack {
+            keys: vec![(0, at([0.0, 0.0, 0.0])), (100, at([2.0, 0.0, 0.0]))],
+            ..Default::default()
+        };
+        assert_close(track.pose_at(-50).unwrap().location, [0.0, 0.0, 0.0]);
+        assert_close(track.pose_at(50).unwrap().location, [1.0, 0.0, 0.0]);
+        assert_close(track.pose_at(200).unwrap().location, [2.0, 0.0, 0.0]);
+        assert!(DancerTrack::default().pose_at(0).is_none());
+    }
+
+    #[test]
+    fn pose_at_holds_across_gaps() {
+        let track = DancerTrack {
+            keys: vec![(0, at([0.0, 0.0, 0.0])), (100, at([2.0, 0.0, 0.0]))],
+            gaps: vec![50],
+            ..Default::default()
+        };
+
+        // the NO_EFFECT frame keeps the first pose, the move starting from there
+        assert_close(track.pose_at(25).unwrap().location, [0.0, 0.0, 0.0]);
+        assert_close(track.pose_at(50).unwrap().location, [0.0, 0.0, 0.0]);
+        assert_close(track.pose_at(75).unwrap().location, [1.0, 0.0, 0.0]);
+        assert_close(track.spline_pose_at(25).unwrap().location, [0.0, 0.0, 0.0]);
+        assert_close(track.spline_pose_at(75).unwrap().location, [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_keys() {
+        let (p0, p1, p2, p3) = ([0.0; 3], [1.0, 2.0, 0.0], [3.0, 1.0, 0.0], [4.0, 4.0, 0.0]);
+        assert_close(catmull_rom(p0, p1, p2, p3, 0.0), p1);
+        assert_close(catmull_rom(p0, p1, p2, p3, 1.0), p2);
+
+        // evenly spaced points on a line stay on it
+        let line = |x: f64| [x, 2.0 * x, 0.0];
+        assert_close(
+            catmull_rom(line(0.0), line(1.0), line(2.0), line(3.0), 0.25),
+            line(1.25),
+        );
+    }
+
+    #[test]
+    fn spline_does_not_reach_across_gaps() {
+        let keys = vec![
+            (0, at([0.0, 5.0, 0.0])),
+            (100, at([1.0, 0.0, 0.0])),
+            (200, at([2.0, 0.0, 0.0])),
+            (300, at([3.0, 5.0, 0.0])),
+        ];
+        let track = DancerTrack {
+            keys: keys.clone(),
+            ..Default::default()
+        };
+        let gapped = DancerTrack {
+            keys,
+            gaps: vec![50, 250],
+            ..Default::default()
+        };
+
+        // the outer keys bend the curve unless a NO_EFFECT frame lies between
+        assert!(track.spline_pose_at(150).unwrap().location[1] < 0.0);
+        assert_close(
+            gapped.spline_pose_at(150).unwrap().location,
+            [1.5, 0.0, 0.0],
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Quaternion helpers for rotations.
+//!
+//! Quaternions are stored as `[w, x, y, z]`, and Euler angles follow the XYZ order
+//! used by Blender, i.e. the rotation matrix is `Rz * Ry * Rx`.
+
+pub type Quaternion = [f64; 4];
+
+pub fn normalize(q: Quaternion) -> Quaternion {
+    let norm = q.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if norm == 0.0 {
+        return [1.0, 0.0, 0.0, 0.0];
+    }
+    q.map(|v| v / norm)
+}
+
+pub fn from_euler([rx, ry, rz]: [f64; 3]) -> Quaternion {
+    let (sx, cx) = (rx / 2.0).sin_cos();
+    let (sy, cy) = (ry / 2.0).sin_cos();
+    let (sz, cz) = (rz / 2.0).sin_cos();
+
+    [
+        cx * cy * cz + sx * sy * sz,
+        sx * cy * cz - cx * sy * sz,
+        cx * sy * cz + sx * cy * sz,
+        cx * cy * sz - sx * sy * cz,
+    ]
+}
+
+pub fn to_euler(q: Quaternion) -> [f64; 3] {
+    let [w, x, y, z] = normalize(q);
+
+    [
+        (2.0 * (w * x + y * z)).atan2(1.0 - 2.0 * (x * x + y * y)),
+        (2.0 * (w * y - z * x)).clamp(-1.0, 1.0).asin(),
+        (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z)),
+    ]
+}
+
+/// Rotation by `angle` radians about the X, Y or Z axis.
+pub fn from_axis_angle(axis: usize, angle: f64) -> Quaternion {
+    let (sin, cos) = (angle / 2.0).sin_cos();
+    let mut q = [cos, 0.0, 0.0, 0.0];
+    q[axis + 1] = sin;
+    q
+}
+
+/// The rotation `b` followed by `a`.
+pub fn multiply([aw, ax, ay, az]: Quaternion, [bw, bx, by, bz]: Quaternion) -> Quaternion {
+    [
+        aw * bw - ax * bx - ay * by - az * bz,
+        aw * bx + ax * bw + ay * bz - az * by,
+        aw * by - ax * bz + ay * bw + az * bx,
+        aw * bz + ax * by - ay * bx + az * bw,
+    ]
+}
+
+pub fn dot(a: Quaternion, b: Quaternion) -> f64 {
+    (0..4).map(|i| a[i] * b[i]).sum()
+}
+
+/// Spherical linear interpolation keeping the winding given by the signs of `a` and `b`.
+///
+/// `b` and `-b` are the same orientation reached by turning opposite ways, so the turn
+/// from `a` to `b` may be longer than half a turn, up to (but not including) a full turn.
+pub fn slerp(a: Quaternion, b: Quaternion, t: f64) -> Quaternion {
+    let (a, b) = (normalize(a), normalize(b));
+    let dot = dot(a, b).clamp(-1.0, 1.0);
+
+    // nearly parallel, fall back to linear interpolation
+    if dot > 0.9995 {
+        return normalize([0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t));
+    }
+    // a full turn has no axis to turn about
+    if dot < -0.9995 {
+        return a;
+    }
+
+    let theta = dot.acos();
+    let (wa, wb) = (
+        ((1.0 - t) * theta).sin() / theta.sin(),
+        (t * theta).sin() / theta.sin(),
+    );
+    [0, 1, 2, 3].map(|i| a[i] * wa + b[i] * wb)
+}
+
+/// Split a `[x, y, z, rx, ry, rz]` or `[x, y, z, qw, qx, qy, qz]` row of position input
+/// into the location, the Euler rotation and the quaternion if one was given.
+pub fn split_position_row(row: &[f64]) -> Option<([f64; 3], [f64; 3], Option<Quaternion>)> {
+    match row.len() {
+        6 => Some(([row[0], row[1], row[2]], [row[3], row[4], row[5]], None)),
+        7 => {
+            let q = normalize([row[3], row[4], row[5], row[6]]);
+            Some(([row[0], row[1], row[2]], to_euler(q), Some(q)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::f64::consts::PI;
+
+    fn assert_close<const N: usize>(a: [f64; N], b: [f64; N]) {
+        assert!((0..N).all(|i| (a[i] - b[i]).abs() < 1e-9), "{a:?} != {b:?}");
+    }
+
+    /// Rotate a vector by a quaternion.
+    fn rotate(q: Quaternion, [x, y, z]: [f64; 3]) -> [f64; 3] {
+        let [w, qx, qy, qz] = q;
+        let conjugate = [w, -qx, -qy, -qz];
+        let [_, x, y, z] = multiply(multiply(q, [0.0, x, y, z]), conjugate);
+        [x, y, z]
+    }
+
+    #[test]
+    fn euler_round_trip() {
+        for rotation in [[0.0, 0.0, 0.0], [0.3, -0.5, 1.2], [-2.0, 1.0, 3.0]] {
+            assert_close(to_euler(from_euler(rotation)), rotation);
+        }
+    }
+
+    #[test]
+    fn euler_order_is_xyz() {
+        // X first: the Y axis turns to Z, then Y turns Z to X
+        let q = from_euler([PI / 2.0, PI / 2.0, 0.0]);
+        assert_close(rotate(q, [0.0, 1.0, 0.0]), [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn multiply_applies_right_first() {
+        let x = from_axis_angle(0, PI / 2.0);
+        let z = from_axis_angle(2, PI / 2.0);
+        assert_close(rotate(multiply(z, x), [0.0, 1.0, 0.0]), [0.0, 0.0, 1.0]);
+        assert_close(rotate(multiply(x, z), [0.0, 1.0, 0.0]), [-1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn slerp_ends_and_midpoint() {
+        let a = from_euler([0.0, 0.0, 0.0]);
+        let b = from_euler([0.0, 0.0, PI / 2.0]);
+        assert_close(slerp(a, b, 0.0), a);
+        assert_close(slerp(a, b, 1.0), b);
+        assert_close(slerp(a, b, 0.5), from_euler([0.0, 0.0, PI / 4.0]));
+    }
+
+    #[test]
+    fn slerp_keeps_winding() {
+        // 270 degrees about Z is not taken as -90 degrees
+        let a = from_axis_angle(2, 0.0);
+        let b = from_axis_angle(2, 1.5 * PI);
+        assert!(dot(a, b) < 0.0);
+        assert_close(slerp(a, b, 0.5), from_axis_angle(2, 0.75 * PI));
+    }
+
+    #[test]
+    fn split_rows() {
+        let (location, rotation, quaternion) =
+            split_position_row(&[1.0, 2.0, 3.0, 0.1, 0.2, 0.3]).unwrap();
+        assert_close(location, [1.0, 2.0, 3.0]);
+        assert_close(rotation, [0.1, 0.2, 0.3]);
+        assert!(quaternion.is_none());
+
+        let (_, rotation, quaternion) =
+            split_position_row(&[0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0]).unwrap();
+        assert_close(rotation, [0.0, 0.0, 0.0]);
+        assert_close(quaternion.unwrap(), [1.0, 0.0, 0.0, 0.0]);
+
+        assert!(split_position_row(&[0.0; 5]).is_none());
+    }
+}
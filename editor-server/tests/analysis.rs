@@ -0,0 +1,191 @@
+#[cfg(test)]
+mod analysis_tests {
+    use serde_json::Value;
+
+    use editor_server::build_graphql;
+    use editor_server::graphql::schema::AppSchema;
+
+    async fn execute(schema: &AppSchema, query: String) -> Value {
+        let response = schema.execute(query).await;
+        assert!(response.is_ok(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    async fn execute_error(schema: &AppSchema, query: String) -> String {
+        let response = schema.execute(query).await;
+        assert!(response.is_err());
+        response.errors[0].message.clone()
+    }
+
+    #[tokio::test]
+    async fn check_positions_without_threshold() {
+        let schema = build_graphql().await;
+
+        let data = execute(
+            &schema,
+            r#"
+            {
+                checkPositions(input: { threshold: 0.0 }) {
+                    kind
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(data["checkPositions"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn check_positions_outside_stage() {
+        let schema = build_graphql().await;
+
+        let data = execute(
+            &schema,
+            r#"
+            {
+                checkPositions(input: {
+                    threshold: 0.0,
+                    stage: [[1000.0, 1000.0], [1001.0, 1000.0], [1001.0, 1001.0]],
+                    start: 0,
+                    end: 0
+                }) {
+                    kind
+                    dancerIds
+                    start
+                    end
+                    minDistance
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        // every placed dancer is off the far away stage
+        for issue in data["checkPositions"].as_array().unwrap() {
+            assert_eq!(issue["kind"], "OUT_OF_BOUNDS");
+            assert_eq!(issue["dancerIds"].as_array().unwrap().len(), 1);
+            assert_eq!(issue["start"], 0);
+            assert_eq!(issue["end"], 0);
+            assert_eq!(issue["minDistance"], Value::Null);
+        }
+    }
+
+    #[tokio::test]
+    async fn check_positions_rejects_bad_step() {
+        let schema = build_graphql().await;
+
+        let message = execute_error(
+            &schema,
+            r#"
+            {
+                checkPositions(input: { threshold: 1.0, step: 0 }) {
+                    kind
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(message, "Step must be positive");
+    }
+
+    #[tokio::test]
+    async fn check_positions_rejects_reversed_range() {
+        let schema = build_graphql().await;
+
+        let message = execute_error(
+            &schema,
+            r#"
+            {
+                checkPositions(input: { threshold: 1.0, start: 200, end: 100 }) {
+                    kind
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(message, "Start must not be larger than end");
+    }
+
+    #[tokio::test]
+    async fn speed_report_under_limit() {
+        let schema = build_graphql().await;
+
+        let data = execute(
+            &schema,
+            r#"
+            {
+                speedReport(input: { maxSpeed: 1e300 }) {
+                    dancerId
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(data["speedReport"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn speed_report_all_segments() {
+        let schema = build_graphql().await;
+
+        let data = execute(
+            &schema,
+            r#"
+            {
+                speedReport(input: { maxSpeed: 1e300, onlyFlagged: false }) {
+                    start
+                    end
+                    speed
+                    angularSpeed
+                    severity
+                    flagged
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        let segments = data["speedReport"].as_array().unwrap();
+        for segment in segments {
+            assert_eq!(segment["flagged"], false);
+            assert!(segment["start"].as_i64() < segment["end"].as_i64());
+            assert!(segment["speed"].as_f64().unwrap() >= 0.0);
+            assert!(segment["angularSpeed"].as_f64().unwrap() >= 0.0);
+        }
+
+        // the most severe segments come first
+        for (a, b) in segments.iter().zip(segments.iter().skip(1)) {
+            assert!(a["severity"].as_f64().unwrap() >= b["severity"].as_f64().unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn speed_report_rejects_bad_limit() {
+        let schema = build_graphql().await;
+
+        let message = execute_error(
+            &schema,
+            r#"
+            {
+                speedReport(input: { maxSpeed: 1.0, maxAngularSpeed: 0.0 }) {
+                    dancerId
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(message, "Speed limits must be positive");
+    }
+}
@@ -0,0 +1,337 @@
+#[cfg(test)]
+mod api_tests {
+    use axum::{
+        body::{to_bytes, Body, Bytes},
+        http::{Request, StatusCode},
+        Router,
+    };
+    use image::{ImageFormat, Rgba, RgbaImage};
+    use serde_json::Value;
+    use std::io::Cursor;
+    use tower::{Service, ServiceExt};
+
+    use editor_server::graphql::schema::AppSchema;
+    use editor_server::{build_app, build_graphql};
+
+    /// Send a GET request, returning the status, content type and body.
+    async fn get(app: &mut Router, uri: &str) -> (StatusCode, String, Bytes) {
+        let response = ServiceExt::<Request<Body>>::ready(app)
+            .await
+            .unwrap()
+            .call(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .map(|value| value.to_str().unwrap().to_string())
+            .unwrap_or_default();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (status, content_type, body)
+    }
+
+    /// Post a file as multipart form data, returning the status and body.
+    async fn post_file(app: &mut Router, uri: &str, file: &[u8]) -> (StatusCode, Bytes) {
+        let boundary = "----test-boundary";
+        let mut body = format!(
+            "--{boundary}\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"effect.png\"\r\n\
+             Content-Type: application/octet-stream\r\n\
+             \r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(file);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+        let request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header(
+                "Content-Type",
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .unwrap();
+
+        let response = ServiceExt::<Request<Body>>::ready(app)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (status, body)
+    }
+
+    async fn execute(schema: &AppSchema, query: String) -> Value {
+        let response = schema.execute(query).await;
+        assert!(response.is_ok(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    /// Create a model with an LED part named "led" of `length` bulbs, returning the model name.
+    async fn add_led_part(schema: &AppSchema, length: usize) -> String {
+        let model_name = format!("test-{}", uuid::Uuid::new_v4());
+
+        execute(
+            schema,
+            format!(
+                r#"
+                mutation {{
+                    addModel(input: {{ name: "{model_name}" }}) {{
+                        ok
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        execute(
+            schema,
+            format!(
+                r#"
+                mutation {{
+                    addPart(input: {{
+                        name: "led",
+                        partType: LED,
+                        modelName: "{model_name}",
+                        length: {length}
+                    }}) {{
+                        partData {{
+                            id
+                        }}
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+
+        model_name
+    }
+
+    fn encode_png(image: &RgbaImage) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    #[tokio::test]
+    async fn spike_marks_formats() {
+        let mut app = build_app().await;
+
+        let (status, content_type, body) = get(&mut app, "/api/spikeMarks").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "image/svg+xml");
+        assert!(body.starts_with(b"<svg"));
+        assert!(body.ends_with(b"</svg>\n"));
+
+        // no frame has a negative id
+        let (status, content_type, body) =
+            get(&mut app, "/api/spikeMarks?format=csv&frames=-1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "text/csv");
+        assert_eq!(
+            String::from_utf8(body.to_vec()).unwrap(),
+            "frame_id,start,time,dancer,x,y,z,facing_deg\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn spike_marks_rejects_bad_query() {
+        let mut app = build_app().await;
+
+        let (status, _, _) = get(&mut app, "/api/spikeMarks?format=pdf").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _, _) = get(&mut app, "/api/spikeMarks?frames=1,x").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn import_led_effect_from_png() {
+        let mut app = build_app().await;
+        let schema = build_graphql().await;
+        let model = add_led_part(&schema, 4).await;
+
+        // a red row and a blue row
+        let image = RgbaImage::from_fn(4, 2, |_, y| match y {
+            0 => Rgba([255, 0, 0, 255]),
+            _ => Rgba([0, 0, 255, 128]),
+        });
+        let uri = format!("/api/importLEDEffect?model={model}&part=led&name=img&interval=200");
+
+        let (status, body) = post_file(&mut app, &uri, &encode_png(&image)).await;
+        assert_eq!(status, StatusCode::OK);
+        let response: Value = serde_json::from_slice(&body).unwrap();
+        let effects = response["effects"].as_array().unwrap();
+        assert_eq!(effects.len(), 2);
+        assert_eq!(effects[0]["name"], "img_0");
+        assert_eq!(effects[0]["start"], 0);
+        assert_eq!(effects[1]["name"], "img_1");
+        assert_eq!(effects[1]["start"], 200);
+
+        let data = execute(&schema, "{ LEDMap { LEDMap } }".to_string()).await;
+        let leds = &data["LEDMap"]["LEDMap"][&model]["led"]["img_1"]["frames"][0]["leds"];
+        assert_eq!(leds.as_array().unwrap().len(), 4);
+        assert_eq!(leds[0][1], 128);
+
+        // the names are taken now
+        let (status, _) = post_file(&mut app, &uri, &encode_png(&image)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn import_led_effect_rejects_bad_file() {
+        let mut app = build_app().await;
+        let schema = build_graphql().await;
+        let model = add_led_part(&schema, 4).await;
+
+        let uri = format!("/api/importLEDEffect?model={model}&part=led&name=text");
+        let (status, _) = post_file(&mut app, &uri, b"not an image").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn import_led_effect_caps_created_colors() {
+        let mut app = build_app().await;
+        let schema = build_graphql().await;
+        let model = add_led_part(&schema, 400).await;
+
+        let image = RgbaImage::from_fn(400, 1, |x, _| {
+            Rgba([(x % 256) as u8, (x / 256) as u8 + 17, 211, 255])
+        });
+        let uri = format!("/api/importLEDEffect?model={model}&part=led&name=many&tolerance=0");
+
+        let (status, body) = post_file(&mut app, &uri, &encode_png(&image)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let response: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            response["err"],
+            "Cannot create more than 256 colors, use a larger tolerance"
+        );
+    }
+
+    #[tokio::test]
+    async fn led_preview_of_effect() {
+        let mut app = build_app().await;
+        let schema = build_graphql().await;
+        let model = add_led_part(&schema, 4).await;
+
+        let image = RgbaImage::from_pixel(4, 1, Rgba([255, 0, 0, 255]));
+        let uri = format!("/api/importLEDEffect?model={model}&part=led&name=red");
+        let (status, body) = post_file(&mut app, &uri, &encode_png(&image)).await;
+        assert_eq!(status, StatusCode::OK);
+        let response: Value = serde_json::from_slice(&body).unwrap();
+        let effect_id = &response["effects"][0]["id"];
+
+        // the strip, a 2 pixels square per bulb
+        let (status, content_type, body) = get(
+            &mut app,
+            &format!("/api/ledPreview?effects={effect_id}&scale=2"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "image/gif");
+        let preview = image::load_from_memory(&body).unwrap().to_rgb8();
+        assert_eq!(preview.dimensions(), (8, 2));
+        assert_eq!(preview.get_pixel(0, 0).0, [255, 0, 0]);
+
+        // the timeline, a column per sample and a separator row
+        let (status, content_type, body) = get(
+            &mut app,
+            &format!("/api/ledPreview?effects={effect_id}&scale=2&format=png"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "image/png");
+        let preview = image::load_from_memory(&body).unwrap().to_rgb8();
+        assert_eq!(preview.dimensions(), (1, 9));
+        assert_eq!(preview.get_pixel(0, 0).0, [255, 0, 0]);
+    }
+
+    #[tokio::test]
+    async fn led_preview_rejects_bad_query() {
+        let mut app = build_app().await;
+        let schema = build_graphql().await;
+
+        let (status, _, _) = get(&mut app, "/api/ledPreview").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _, _) = get(&mut app, "/api/ledPreview?effects=1,x").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let data = execute(&schema, "{ dancers { name } }".to_string()).await;
+        let dancer = data["dancers"][0]["name"].as_str().unwrap().to_string();
+
+        let (status, _, _) =
+            get(&mut app, &format!("/api/ledPreview?dancer={dancer}&step=0")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // the whole range of times, one sample each
+        let (status, _, body) = get(
+            &mut app,
+            &format!(
+                "/api/ledPreview?dancer={dancer}&start=0&end={}&step=1",
+                i32::MAX
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let response: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response["err"], "Cannot render more than 20000 frames");
+    }
+
+    #[tokio::test]
+    async fn heat_map_of_dancer() {
+        let mut app = build_app().await;
+        let schema = build_graphql().await;
+
+        let data = execute(&schema, "{ dancers { name } }".to_string()).await;
+        let dancer = data["dancers"][0]["name"].as_str().unwrap().to_string();
+
+        // a column every 100 ms
+        let (status, content_type, body) = get(
+            &mut app,
+            &format!(
+                "/api/heatMap?dancer={dancer}&start=0&end=1000&pixelsPerSecond=10&rowHeight=2"
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "image/png");
+        let image = image::load_from_memory(&body).unwrap();
+        assert_eq!(image.width(), 10);
+    }
+
+    #[tokio::test]
+    async fn heat_map_rejects_bad_query() {
+        let mut app = build_app().await;
+
+        let (status, _, _) = get(&mut app, "/api/heatMap?start=200&end=100").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _, body) = get(&mut app, "/api/heatMap?pixelsPerSecond=inf").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let response: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response["err"], "pixelsPerSecond must be positive");
+
+        let (status, _, body) = get(
+            &mut app,
+            &format!(
+                "/api/heatMap?start=0&end={}&pixelsPerSecond=1e300",
+                i32::MAX
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let response: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response["err"], "Image cannot be wider than 20000 pixels");
+    }
+}
@@ -0,0 +1,219 @@
+#[cfg(test)]
+mod bulk_edit_tests {
+    use serde_json::Value;
+    use tokio::sync::OnceCell;
+
+    use editor_server::build_graphql;
+    use editor_server::graphql::schema::AppSchema;
+
+    static SCHEMA: OnceCell<AppSchema> = OnceCell::const_new();
+
+    async fn get_schema() -> &'static AppSchema {
+        SCHEMA.get_or_init(build_graphql).await
+    }
+
+    async fn execute(query: String) -> Value {
+        let response = get_schema().await.execute(query).await;
+        assert!(response.is_ok(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    async fn execute_error(query: String) -> String {
+        let response = get_schema().await.execute(query).await;
+        assert!(response.is_err());
+        response.errors[0].message.clone()
+    }
+
+    /// Create a color with a unique name, returning its id.
+    async fn add_color(code: [i32; 3]) -> i64 {
+        let name = format!("test-{}", uuid::Uuid::new_v4());
+        let data = execute(format!(
+            r#"
+            mutation {{
+                addColor(color: {{ color: "{name}", colorCode: {{ set: {code:?} }} }}) {{
+                    id
+                }}
+            }}
+            "#
+        ))
+        .await;
+        data["addColor"]["id"].as_i64().unwrap()
+    }
+
+    /// The first control frame and the first dancer of the show.
+    async fn first_frame_and_dancer() -> (i64, i64) {
+        let data = execute("{ controlFrameIds dancers { id } }".to_string()).await;
+        let frame_id = data["controlFrameIds"][0].as_i64().unwrap();
+        let dancer_id = data["dancers"][0]["id"].as_i64().unwrap();
+        (frame_id, dancer_id)
+    }
+
+    #[tokio::test]
+    async fn copy_part_onto_source_frame() {
+        let (frame_id, dancer_id) = first_frame_and_dancer().await;
+
+        let data = execute(format!(
+            r#"
+            mutation {{
+                copyPart(input: {{ sourceFrameId: {frame_id}, sourceDancerId: {dancer_id} }}) {{
+                    ok
+                    frameIds
+                }}
+            }}
+            "#
+        ))
+        .await;
+
+        assert_eq!(data["copyPart"]["ok"], true);
+        assert_eq!(data["copyPart"]["frameIds"], serde_json::json!([frame_id]));
+    }
+
+    #[tokio::test]
+    async fn copy_part_rejects_half_range() {
+        let (frame_id, dancer_id) = first_frame_and_dancer().await;
+
+        let message = execute_error(format!(
+            r#"
+            mutation {{
+                copyPart(input: {{ sourceFrameId: {frame_id}, sourceDancerId: {dancer_id}, start: 0 }}) {{
+                    ok
+                }}
+            }}
+            "#
+        ))
+        .await;
+
+        assert_eq!(message, "Start and end must be given together");
+    }
+
+    #[tokio::test]
+    async fn copy_part_rejects_unknown_target_dancer() {
+        let (frame_id, dancer_id) = first_frame_and_dancer().await;
+
+        let message = execute_error(format!(
+            r#"
+            mutation {{
+                copyPart(input: {{
+                    sourceFrameId: {frame_id},
+                    sourceDancerId: {dancer_id},
+                    targetDancerIds: [-1]
+                }}) {{
+                    ok
+                }}
+            }}
+            "#
+        ))
+        .await;
+
+        assert!(message.contains("Dancer #-1 not found"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn recolor_unused_color() {
+        let from = add_color([1, 2, 3]).await;
+        let to = add_color([4, 5, 6]).await;
+
+        let data = execute(format!(
+            r#"
+            mutation {{
+                recolor(input: {{
+                    fromColorId: {from},
+                    toColorId: {to},
+                    scope: {{}},
+                    includeLedEffects: true
+                }}) {{
+                    ok
+                    controlCount
+                    bulbCount
+                    effectStateCount
+                }}
+            }}
+            "#
+        ))
+        .await;
+
+        assert_eq!(data["recolor"]["ok"], true);
+        assert_eq!(data["recolor"]["controlCount"], 0);
+        assert_eq!(data["recolor"]["bulbCount"], 0);
+        assert_eq!(data["recolor"]["effectStateCount"], 0);
+    }
+
+    #[tokio::test]
+    async fn recolor_rejects_unknown_color() {
+        let to = add_color([4, 5, 6]).await;
+
+        let message = execute_error(format!(
+            r#"
+            mutation {{
+                recolor(input: {{ fromColorId: -1, toColorId: {to}, scope: {{}} }}) {{
+                    ok
+                }}
+            }}
+            "#
+        ))
+        .await;
+
+        assert_eq!(message, "Color #-1 not found");
+    }
+
+    #[tokio::test]
+    async fn recolor_rejects_reversed_range() {
+        let from = add_color([1, 2, 3]).await;
+        let to = add_color([4, 5, 6]).await;
+
+        let message = execute_error(format!(
+            r#"
+            mutation {{
+                recolor(input: {{
+                    fromColorId: {from},
+                    toColorId: {to},
+                    scope: {{ start: 200, end: 100 }}
+                }}) {{
+                    ok
+                }}
+            }}
+            "#
+        ))
+        .await;
+
+        assert_eq!(message, "Start must not be larger than end");
+    }
+
+    #[tokio::test]
+    async fn edit_alpha_by_one_changes_nothing() {
+        let data = execute(
+            r#"
+            mutation {
+                editAlpha(input: { mode: MULTIPLY, value: 1.0, scope: {} }) {
+                    ok
+                    controlCount
+                    bulbCount
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(data["editAlpha"]["ok"], true);
+        assert_eq!(data["editAlpha"]["controlCount"], 0);
+        assert_eq!(data["editAlpha"]["bulbCount"], 0);
+    }
+
+    #[tokio::test]
+    async fn edit_alpha_rejects_negative_value() {
+        let message = execute_error(
+            r#"
+            mutation {
+                editAlpha(input: { mode: SET, value: -1.0, scope: {} }) {
+                    ok
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(message, "Value must be a non-negative number");
+    }
+}
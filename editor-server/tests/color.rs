@@ -1,6 +1,40 @@
 #[cfg(test)]
 mod graphql_tests {
+    use serde_json::Value;
+
     use editor_server::build_graphql;
+    use editor_server::graphql::schema::AppSchema;
+
+    async fn execute(schema: &AppSchema, query: String) -> Value {
+        let response = schema.execute(query).await;
+        assert!(response.is_ok(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    async fn execute_error(schema: &AppSchema, query: String) -> String {
+        let response = schema.execute(query).await;
+        assert!(response.is_err());
+        response.errors[0].message.clone()
+    }
+
+    /// Create a color with a unique name, returning its id.
+    async fn add_color(schema: &AppSchema, code: [i32; 3]) -> i64 {
+        let name = format!("test-{}", uuid::Uuid::new_v4());
+        let data = execute(
+            schema,
+            format!(
+                r#"
+                mutation {{
+                    addColor(color: {{ color: "{name}", colorCode: {{ set: {code:?} }} }}) {{
+                        id
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        data["addColor"]["id"].as_i64().unwrap()
+    }
 
     #[tokio::test]
     pub async fn get_color() {
@@ -20,4 +54,595 @@ mod graphql_tests {
 
         assert!(data.is_ok());
     }
+
+    #[tokio::test]
+    async fn add_edit_and_delete_gradient() {
+        let schema = build_graphql().await;
+        let red = add_color(&schema, [255, 0, 0]).await;
+        let blue = add_color(&schema, [0, 0, 255]).await;
+        let name = format!("test-{}", uuid::Uuid::new_v4());
+
+        // the stops are sorted by position
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    addGradient(input: {{
+                        name: "{name}",
+                        space: HSV,
+                        stops: [
+                            {{ position: 1.0, colorId: {blue} }},
+                            {{ position: 0.0, colorId: {red}, alpha: 128 }}
+                        ]
+                    }}) {{
+                        id
+                        space
+                        easing
+                        stops {{
+                            position
+                            colorId
+                            alpha
+                        }}
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        let gradient = &data["addGradient"];
+        let id = gradient["id"].as_i64().unwrap();
+        assert_eq!(gradient["space"], "HSV");
+        assert_eq!(gradient["easing"], "LINEAR");
+        assert_eq!(
+            gradient["stops"],
+            serde_json::json!([
+                { "position": 0.0, "colorId": red, "alpha": 128 },
+                { "position": 1.0, "colorId": blue, "alpha": 255 },
+            ])
+        );
+
+        let message = execute_error(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    addGradient(input: {{ name: "{name}", stops: [{{ position: 0.0, colorId: {red} }}] }}) {{
+                        id
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        assert_eq!(message, format!("Gradient {name} already exists"));
+
+        // not assigned to any control data yet
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    editGradient(input: {{
+                        id: {id},
+                        easing: EASE_IN,
+                        stops: [{{ position: 0.5, colorId: {blue} }}]
+                    }}) {{
+                        ok
+                        controlCount
+                        gradient {{
+                            easing
+                            stops {{
+                                colorId
+                            }}
+                        }}
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        assert_eq!(data["editGradient"]["ok"], true);
+        assert_eq!(data["editGradient"]["controlCount"], 0);
+        assert_eq!(data["editGradient"]["gradient"]["easing"], "EASE_IN");
+
+        let data = execute(
+            &schema,
+            format!("{{ gradient(id: {id}) {{ stops {{ colorId }} }} }}"),
+        )
+        .await;
+        assert_eq!(
+            data["gradient"]["stops"],
+            serde_json::json!([{ "colorId": blue }])
+        );
+
+        let data = execute(
+            &schema,
+            format!("mutation {{ deleteGradient(id: {id}) {{ ok }} }}"),
+        )
+        .await;
+        assert_eq!(data["deleteGradient"]["ok"], true);
+
+        let message = execute_error(&schema, format!("{{ gradient(id: {id}) {{ name }} }}")).await;
+        assert_eq!(message, format!("Gradient #{id} not found"));
+    }
+
+    #[tokio::test]
+    async fn gradient_rejects_bad_stops() {
+        let schema = build_graphql().await;
+        let red = add_color(&schema, [255, 0, 0]).await;
+
+        for (stops, expected) in [
+            ("[]".to_string(), "Gradient must have at least one stop"),
+            (
+                format!("[{{ position: 1.5, colorId: {red} }}]"),
+                "Position 1.5 of stop is not in [0, 1]",
+            ),
+            (
+                format!("[{{ position: 0.0, colorId: {red}, alpha: 256 }}]"),
+                "Alpha 256 of stop is not in [0, 255]",
+            ),
+            (
+                format!(
+                    "[{{ position: 0.0, colorId: {red} }}, {{ position: 0.0, colorId: {red} }}]"
+                ),
+                "Stops must have distinct positions",
+            ),
+            (
+                "[{ position: 0.0, colorId: -1 }]".to_string(),
+                "Color #-1 not found",
+            ),
+        ] {
+            let name = format!("test-{}", uuid::Uuid::new_v4());
+            let message = execute_error(
+                &schema,
+                format!(
+                    r#"
+                    mutation {{
+                        addGradient(input: {{ name: "{name}", stops: {stops} }}) {{
+                            id
+                        }}
+                    }}
+                    "#
+                ),
+            )
+            .await;
+            assert_eq!(message, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn apply_gradient_outside_show() {
+        let schema = build_graphql().await;
+        let red = add_color(&schema, [255, 0, 0]).await;
+        let name = format!("test-{}", uuid::Uuid::new_v4());
+
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    addGradient(input: {{ name: "{name}", stops: [{{ position: 0.0, colorId: {red} }}] }}) {{
+                        id
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        let id = data["addGradient"]["id"].as_i64().unwrap();
+
+        // no control frame starts that late
+        let message = execute_error(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    applyGradient(input: {{
+                        gradientId: {id},
+                        scope: {{ start: 2000000000, end: 2000000100 }}
+                    }}) {{
+                        ok
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        assert_eq!(message, "No LED part in scope");
+
+        execute(
+            &schema,
+            format!("mutation {{ deleteGradient(id: {id}) {{ ok }} }}"),
+        )
+        .await;
+    }
+
+    /// Create a palette with a unique name, returning its id.
+    async fn add_palette(schema: &AppSchema, colors: &[(&str, i64)]) -> i64 {
+        let name = format!("test-{}", uuid::Uuid::new_v4());
+        let colors = colors
+            .iter()
+            .map(|(role, id)| format!(r#"{{ role: "{role}", colorId: {id} }}"#))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let data = execute(
+            schema,
+            format!(
+                r#"
+                mutation {{
+                    addPalette(input: {{ name: "{name}", colors: [{colors}] }}) {{
+                        id
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        data["addPalette"]["id"].as_i64().unwrap()
+    }
+
+    #[tokio::test]
+    async fn assign_and_swap_palette() {
+        let schema = build_graphql().await;
+        // a section after every frame of the show
+        let (start, end) = (2_000_020_000, 2_000_020_100);
+
+        let from = add_palette(
+            &schema,
+            &[
+                ("primary", add_color(&schema, [10, 20, 30]).await),
+                ("accent", add_color(&schema, [40, 50, 60]).await),
+            ],
+        )
+        .await;
+        let to = add_palette(
+            &schema,
+            &[
+                ("accent", add_color(&schema, [70, 80, 90]).await),
+                ("primary", add_color(&schema, [100, 110, 120]).await),
+            ],
+        )
+        .await;
+
+        let assign = format!(
+            r#"
+            mutation {{
+                assignPalette(input: {{ paletteId: {from}, start: {start}, end: {end} }}) {{
+                    sections {{
+                        id
+                        start
+                        end
+                    }}
+                }}
+            }}
+            "#
+        );
+        let data = execute(&schema, assign.clone()).await;
+        let section = &data["assignPalette"]["sections"][0];
+        let section_id = section["id"].as_i64().unwrap();
+        assert_eq!(section["start"], start);
+        assert_eq!(section["end"], end);
+
+        let message = execute_error(&schema, assign).await;
+        assert_eq!(
+            message,
+            format!("Section overlaps section #{section_id} of palette #{from}")
+        );
+
+        let palette_at = format!("{{ paletteAt(time: {}) {{ id }} }}", start + 50);
+        let data = execute(&schema, palette_at.clone()).await;
+        assert_eq!(data["paletteAt"]["id"], from);
+
+        // nothing uses the colors, only the section moves
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    swapPalette(input: {{
+                        fromPaletteId: {from},
+                        toPaletteId: {to},
+                        scope: {{ start: {start}, end: {end} }},
+                        includeLedEffects: true
+                    }}) {{
+                        ok
+                        controlCount
+                        bulbCount
+                        effectStateCount
+                        sectionCount
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        let swapped = &data["swapPalette"];
+        assert_eq!(swapped["ok"], true);
+        assert_eq!(swapped["controlCount"], 0);
+        assert_eq!(swapped["bulbCount"], 0);
+        assert_eq!(swapped["effectStateCount"], 0);
+        assert_eq!(swapped["sectionCount"], 1);
+
+        let data = execute(&schema, palette_at).await;
+        assert_eq!(data["paletteAt"]["id"], to);
+
+        let data = execute(
+            &schema,
+            format!(
+                "mutation {{ unassignPalette(sectionId: {section_id}) {{ sections {{ id }} }} }}"
+            ),
+        )
+        .await;
+        assert_eq!(data["unassignPalette"]["sections"], serde_json::json!([]));
+
+        for id in [from, to] {
+            let data = execute(
+                &schema,
+                format!("mutation {{ deletePalette(id: {id}) {{ ok }} }}"),
+            )
+            .await;
+            assert_eq!(data["deletePalette"]["ok"], true);
+        }
+    }
+
+    #[tokio::test]
+    async fn palette_rejects_repeated_role() {
+        let schema = build_graphql().await;
+        let color = add_color(&schema, [10, 20, 30]).await;
+        let name = format!("test-{}", uuid::Uuid::new_v4());
+
+        let message = execute_error(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    addPalette(input: {{
+                        name: "{name}",
+                        colors: [
+                            {{ role: "primary", colorId: {color} }},
+                            {{ role: "primary", colorId: {color} }}
+                        ]
+                    }}) {{
+                        id
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+
+        assert_eq!(message, "Role primary is used more than once");
+    }
+
+    #[tokio::test]
+    async fn color_usage_and_merge_colors() {
+        let schema = build_graphql().await;
+        let from = add_color(&schema, [1, 1, 1]).await;
+        let into = add_color(&schema, [2, 2, 2]).await;
+
+        let name = format!("test-{}", uuid::Uuid::new_v4());
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    addGradient(input: {{ name: "{name}", stops: [{{ position: 0.0, colorId: {from} }}] }}) {{
+                        id
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        let gradient = data["addGradient"]["id"].as_i64().unwrap();
+        let palette = add_palette(&schema, &[("primary", from)]).await;
+
+        let usage = |id: i64| {
+            format!(
+                r#"
+                {{
+                    colorUsage(id: {id}) {{
+                        id
+                        controlData {{
+                            frameId
+                        }}
+                        LEDEffects {{
+                            id
+                        }}
+                        gradientIds
+                        paletteIds
+                    }}
+                }}
+                "#
+            )
+        };
+
+        let data = execute(&schema, usage(from)).await;
+        assert_eq!(
+            data["colorUsage"],
+            serde_json::json!({
+                "id": from,
+                "controlData": [],
+                "LEDEffects": [],
+                "gradientIds": [gradient],
+                "paletteIds": [palette],
+            })
+        );
+
+        // nothing of the show uses the color, only the gradient and palette
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    mergeColors(from: {from}, into: {into}) {{
+                        ok
+                        controlCount
+                        bulbCount
+                        effectStateCount
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        let merged = &data["mergeColors"];
+        assert_eq!(merged["ok"], true);
+        assert_eq!(merged["controlCount"], 0);
+        assert_eq!(merged["bulbCount"], 0);
+        assert_eq!(merged["effectStateCount"], 0);
+
+        let data = execute(&schema, usage(into)).await;
+        assert_eq!(
+            data["colorUsage"]["gradientIds"],
+            serde_json::json!([gradient])
+        );
+        assert_eq!(
+            data["colorUsage"]["paletteIds"],
+            serde_json::json!([palette])
+        );
+
+        let message = execute_error(&schema, usage(from)).await;
+        assert_eq!(message, format!("Color #{from} not found"));
+
+        execute(
+            &schema,
+            format!("mutation {{ deleteGradient(id: {gradient}) {{ ok }} }}"),
+        )
+        .await;
+        execute(
+            &schema,
+            format!("mutation {{ deletePalette(id: {palette}) {{ ok }} }}"),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn merge_color_into_itself() {
+        let schema = build_graphql().await;
+        let color = add_color(&schema, [3, 3, 3]).await;
+
+        let message = execute_error(
+            &schema,
+            format!("mutation {{ mergeColors(from: {color}, into: {color}) {{ ok }} }}"),
+        )
+        .await;
+
+        assert_eq!(message, "Cannot merge a color into itself");
+    }
+
+    #[tokio::test]
+    async fn add_color_in_other_spaces() {
+        let schema = build_graphql().await;
+
+        for (code, expected) in [
+            ("hsv: { h: 120.0, s: 1.0, v: 1.0 }", [0, 255, 0]),
+            ("hsl: { h: 240.0, s: 1.0, l: 0.5 }", [0, 0, 255]),
+            (r##"hex: "#f80""##, [255, 136, 0]),
+            ("kelvin: 6600.0", [255, 255, 255]),
+        ] {
+            let name = format!("test-{}", uuid::Uuid::new_v4());
+            let data = execute(
+                &schema,
+                format!(
+                    r#"
+                    mutation {{
+                        addColor(color: {{ color: "{name}", colorCode: {{ {code} }} }}) {{
+                            colorCode
+                        }}
+                    }}
+                    "#
+                ),
+            )
+            .await;
+            assert_eq!(
+                data["addColor"]["colorCode"],
+                serde_json::json!(expected),
+                "{code}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn color_in_other_spaces() {
+        let schema = build_graphql().await;
+        let name = format!("test-{}", uuid::Uuid::new_v4());
+
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    addColor(color: {{ color: "{name}", colorCode: {{ set: [0, 255, 0] }} }}) {{
+                        hex
+                        hsv {{
+                            h
+                            s
+                            v
+                        }}
+                        hsl {{
+                            h
+                            s
+                            l
+                        }}
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+
+        let color = &data["addColor"];
+        assert_eq!(color["hex"], "#00ff00");
+        for (value, expected) in [
+            (&color["hsv"]["h"], 120.0),
+            (&color["hsv"]["s"], 1.0),
+            (&color["hsv"]["v"], 1.0),
+            (&color["hsl"]["h"], 120.0),
+            (&color["hsl"]["s"], 1.0),
+            (&color["hsl"]["l"], 0.5),
+        ] {
+            assert!((value.as_f64().unwrap() - expected).abs() < 1e-9);
+        }
+    }
+
+    #[tokio::test]
+    async fn add_color_rejects_bad_code() {
+        let schema = build_graphql().await;
+
+        for (code, expected) in [
+            (
+                r##"set: [1, 2, 3], hex: "#010203""##,
+                "Exactly one of set, hsv, hsl, hex and kelvin must be given",
+            ),
+            (
+                "set: [1, 2, 256]",
+                "Color code must be 3 values in [0, 255]",
+            ),
+            (
+                "hsv: { h: 0.0, s: 2.0, v: 1.0 }",
+                "Saturation must be in [0, 1]",
+            ),
+            (r##"hex: "#12""##, "Invalid hex color: #12"),
+            ("kelvin: 100.0", "Kelvin must be in [1000, 40000]"),
+        ] {
+            let name = format!("test-{}", uuid::Uuid::new_v4());
+            let message = execute_error(
+                &schema,
+                format!(
+                    r#"
+                    mutation {{
+                        addColor(color: {{ color: "{name}", colorCode: {{ {code} }} }}) {{
+                            id
+                        }}
+                    }}
+                    "#
+                ),
+            )
+            .await;
+            assert_eq!(message, expected, "{code}");
+        }
+    }
 }
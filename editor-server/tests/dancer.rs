@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod dancer_tests {
+    use serde_json::Value;
+
+    use editor_server::build_graphql;
+    use editor_server::graphql::schema::AppSchema;
+
+    async fn execute(schema: &AppSchema, query: String) -> Value {
+        let response = schema.execute(query).await;
+        assert!(response.is_ok(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    #[tokio::test]
+    async fn clone_dancer_with_offset() {
+        let schema = build_graphql().await;
+
+        let data = execute(&schema, "{ dancers { id name parts { id } } }".to_string()).await;
+        let source = &data["dancers"][0];
+        let source_id = source["id"].as_i64().unwrap();
+        let name = format!("test-{}", uuid::Uuid::new_v4());
+
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    cloneDancer(input: {{ id: {source_id}, name: "{name}", offsetX: 1.5 }}) {{
+                        ok
+                        msg
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        assert_eq!(
+            data["cloneDancer"]["ok"], true,
+            "{}",
+            data["cloneDancer"]["msg"]
+        );
+
+        let data = execute(
+            &schema,
+            format!(r#"{{ dancer(dancerName: "{name}") {{ id parts {{ id }} }} }}"#),
+        )
+        .await;
+        let clone = &data["dancer"];
+        assert_eq!(clone["parts"], source["parts"]);
+
+        // the clone is the last dancer, moved along x in the frames where the source has a position
+        let data = execute(&schema, "{ PosMap { frameIds } }".to_string()).await;
+        for frame in data["PosMap"]["frameIds"].as_object().unwrap().values() {
+            let last = frame["location"].as_array().unwrap().len() - 1;
+            assert_eq!(frame["has_position"][last], frame["has_position"][0]);
+
+            let (from, to) = (&frame["location"][0], &frame["location"][last]);
+            let offset = if frame["has_position"][0] == true {
+                1.5
+            } else {
+                0.0
+            };
+            assert!((to[0].as_f64().unwrap() - from[0].as_f64().unwrap() - offset).abs() < 1e-9);
+            assert_eq!(to[1], from[1]);
+            assert_eq!(to[2], from[2]);
+        }
+
+        execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    deleteDancer(input: {{ id: {} }}) {{
+                        ok
+                    }}
+                }}
+                "#,
+                clone["id"]
+            ),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn clone_dancer_rejects_unknown_dancer() {
+        let schema = build_graphql().await;
+
+        let data = execute(
+            &schema,
+            r#"
+            mutation {
+                cloneDancer(input: { id: -1, name: "missing" }) {
+                    ok
+                    msg
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(data["cloneDancer"]["ok"], false);
+        assert_eq!(data["cloneDancer"]["msg"], "Dancer not found.");
+    }
+}
@@ -0,0 +1,243 @@
+#[cfg(test)]
+mod frame_tests {
+    use serde_json::Value;
+
+    use editor_server::build_graphql;
+    use editor_server::graphql::schema::AppSchema;
+
+    /// A time after every frame of the show.
+    const LATE: i32 = 2_000_000_000;
+
+    async fn execute(schema: &AppSchema, query: String) -> Value {
+        let response = schema.execute(query).await;
+        assert!(response.is_ok(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    async fn execute_error(schema: &AppSchema, query: String) -> String {
+        let response = schema.execute(query).await;
+        assert!(response.is_err());
+        response.errors[0].message.clone()
+    }
+
+    #[tokio::test]
+    async fn delete_frames_in_empty_range() {
+        let schema = build_graphql().await;
+
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    deleteFrames(start: {LATE}, end: {}, deleteControl: true, deletePosition: true) {{
+                        ok
+                        controlFrameIds
+                        positionFrameIds
+                    }}
+                }}
+                "#,
+                LATE + 100
+            ),
+        )
+        .await;
+
+        assert_eq!(data["deleteFrames"]["ok"], true);
+        assert_eq!(
+            data["deleteFrames"]["controlFrameIds"],
+            serde_json::json!([])
+        );
+        assert_eq!(
+            data["deleteFrames"]["positionFrameIds"],
+            serde_json::json!([])
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_frames_keeps_first_frame() {
+        let schema = build_graphql().await;
+
+        let message = execute_error(
+            &schema,
+            r#"
+            mutation {
+                deleteFrames(start: 0, end: 100, deleteControl: true, deletePosition: true) {
+                    ok
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(message, "The first frame can not be deleted");
+    }
+
+    #[tokio::test]
+    async fn stretch_empty_range() {
+        let schema = build_graphql().await;
+
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    stretch(
+                        start: {LATE},
+                        end: {},
+                        newStart: {LATE},
+                        newEnd: {},
+                        stretchControl: true,
+                        stretchPosition: true
+                    ) {{
+                        ok
+                    }}
+                }}
+                "#,
+                LATE + 100,
+                LATE + 200
+            ),
+        )
+        .await;
+
+        assert_eq!(data["stretch"]["ok"], true);
+    }
+
+    #[tokio::test]
+    async fn stretch_keeps_first_frame() {
+        let schema = build_graphql().await;
+
+        let message = execute_error(
+            &schema,
+            r#"
+            mutation {
+                stretch(
+                    start: 0,
+                    end: 100,
+                    newStart: 50,
+                    newEnd: 200,
+                    stretchControl: true,
+                    stretchPosition: true
+                ) {
+                    ok
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(message, "The first frame can not be moved");
+    }
+
+    #[tokio::test]
+    async fn stretch_rejects_empty_target() {
+        let schema = build_graphql().await;
+
+        let message = execute_error(
+            &schema,
+            r#"
+            mutation {
+                stretch(
+                    start: 100,
+                    end: 200,
+                    newStart: 300,
+                    newEnd: 300,
+                    stretchControl: true,
+                    stretchPosition: true
+                ) {
+                    ok
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(message, "Start must smaller than end");
+    }
+
+    #[tokio::test]
+    async fn duplicate_frames_and_delete_copies() {
+        let schema = build_graphql().await;
+        let target = LATE + 1000;
+
+        let duplicate = format!(
+            r#"
+            mutation {{
+                duplicateFrames(
+                    start: 0,
+                    end: 0,
+                    targetStart: {target},
+                    duplicateControl: true,
+                    duplicatePosition: true
+                ) {{
+                    ok
+                    controlFrameIds
+                    positionFrameIds
+                }}
+            }}
+            "#
+        );
+
+        let data = execute(&schema, duplicate.clone()).await;
+        let created = &data["duplicateFrames"];
+        assert_eq!(created["ok"], true);
+        assert!(
+            !created["controlFrameIds"].as_array().unwrap().is_empty()
+                || !created["positionFrameIds"].as_array().unwrap().is_empty()
+        );
+
+        // the copies are refused a second time
+        let message = execute_error(&schema, duplicate).await;
+        assert_eq!(message, format!("Duplicated frame start at {target}"));
+
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    deleteFrames(start: {target}, end: {target}, deleteControl: true, deletePosition: true) {{
+                        controlFrameIds
+                        positionFrameIds
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        assert_eq!(
+            data["deleteFrames"]["controlFrameIds"],
+            created["controlFrameIds"]
+        );
+        assert_eq!(
+            data["deleteFrames"]["positionFrameIds"],
+            created["positionFrameIds"]
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicate_frames_rejects_overlap() {
+        let schema = build_graphql().await;
+
+        let message = execute_error(
+            &schema,
+            r#"
+            mutation {
+                duplicateFrames(
+                    start: 100,
+                    end: 300,
+                    targetStart: 200,
+                    duplicateControl: true,
+                    duplicatePosition: true
+                ) {
+                    ok
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(message, "Target interval overlaps the source interval");
+    }
+}
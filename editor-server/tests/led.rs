@@ -2,6 +2,8 @@
 mod combined_mutation_tests {
     use async_graphql::Response;
     use editor_server::build_graphql;
+    use editor_server::graphql::schema::AppSchema;
+    use serde_json::Value;
 
     #[tokio::test]
     async fn test_add_model_part_and_led() {
@@ -70,4 +72,450 @@ mod combined_mutation_tests {
         let response: Response = schema.execute(mutation).await;
         assert!(response.is_ok());
     }
+
+    async fn execute(schema: &AppSchema, query: String) -> Value {
+        let response = schema.execute(query).await;
+        assert!(response.is_ok(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    async fn execute_error(schema: &AppSchema, query: String) -> String {
+        let response = schema.execute(query).await;
+        assert!(response.is_err());
+        response.errors[0].message.clone()
+    }
+
+    /// A new model with an LED part of `length` bulbs lit by an effect in a new color.
+    struct LEDPart {
+        model_name: String,
+        part_name: String,
+        part_id: i64,
+        effect_id: i64,
+        effect_name: String,
+    }
+
+    async fn add_led_part(schema: &AppSchema, length: usize) -> LEDPart {
+        let model_name = format!("test-{}", uuid::Uuid::new_v4());
+        let part_name = "led".to_string();
+        let effect_name = "lit".to_string();
+
+        execute(
+            schema,
+            format!(
+                r#"
+                mutation {{
+                    addModel(input: {{ name: "{model_name}" }}) {{
+                        ok
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+
+        let data = execute(
+            schema,
+            format!(
+                r#"
+                mutation {{
+                    addPart(input: {{
+                        name: "{part_name}",
+                        partType: LED,
+                        modelName: "{model_name}",
+                        length: {length}
+                    }}) {{
+                        partData {{
+                            id
+                        }}
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        let part_id = data["addPart"]["partData"]["id"].as_i64().unwrap();
+
+        let data = execute(
+            schema,
+            format!(
+                r#"
+                mutation {{
+                    addColor(color: {{ color: "{model_name}", colorCode: {{ set: [255, 0, 0] }} }}) {{
+                        id
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        let color_id = data["addColor"]["id"].as_i64().unwrap();
+
+        let leds = vec![[color_id, 255]; length];
+        let data = execute(
+            schema,
+            format!(
+                r#"
+                mutation {{
+                    addLEDEffect(input: {{
+                        name: "{effect_name}",
+                        modelName: "{model_name}",
+                        partName: "{part_name}",
+                        repeat: 0,
+                        frames: [{{ leds: {leds:?}, fade: false, start: 0 }}]
+                    }}) {{
+                        id
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+        let effect_id = data["addLEDEffect"]["id"].as_i64().unwrap();
+
+        LEDPart {
+            model_name,
+            part_name,
+            part_id,
+            effect_id,
+            effect_name,
+        }
+    }
+
+    /// The states of the first frame of an effect.
+    async fn effect_leds(schema: &AppSchema, part: &LEDPart, effect_name: &str) -> Vec<Value> {
+        let data = execute(schema, "{ LEDMap { LEDMap } }".to_string()).await;
+        data["LEDMap"]["LEDMap"][&part.model_name][&part.part_name][effect_name]["frames"][0]
+            ["leds"]
+            .as_array()
+            .unwrap()
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn test_resize_led_part() {
+        let schema = build_graphql().await;
+        let part = add_led_part(&schema, 4).await;
+
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    resizeLEDPart(input: {{ id: {}, length: 6, mode: PAD }}) {{
+                        ok
+                        partData {{
+                            length
+                        }}
+                    }}
+                }}
+                "#,
+                part.part_id
+            ),
+        )
+        .await;
+        assert_eq!(data["resizeLEDPart"]["ok"], true);
+        assert_eq!(data["resizeLEDPart"]["partData"]["length"], 6);
+
+        // the padded bulbs are turned off
+        let leds = effect_leds(&schema, &part, &part.effect_name).await;
+        assert_eq!(leds.len(), 6);
+        assert_eq!(leds[3][1], 255);
+        assert_eq!(leds[4][1], 0);
+        assert_eq!(leds[5][1], 0);
+
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    resizeLEDPart(input: {{ id: {}, length: 8, mode: TRUNCATE }}) {{
+                        ok
+                        msg
+                    }}
+                }}
+                "#,
+                part.part_id
+            ),
+        )
+        .await;
+        assert_eq!(data["resizeLEDPart"]["ok"], false);
+        assert_eq!(
+            data["resizeLEDPart"]["msg"],
+            "truncate can't lengthen the part"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_led_effect() {
+        let schema = build_graphql().await;
+        let part = add_led_part(&schema, 6).await;
+
+        let generate = format!(
+            r#"
+            mutation {{
+                generateLEDEffect(input: {{
+                    name: "chase",
+                    modelName: "{}",
+                    partName: "{}",
+                    pattern: CHASE,
+                    colorIds: [],
+                    speed: 1.0,
+                    duration: 1000,
+                    interval: 250
+                }}) {{
+                    ok
+                    effects {{
+                        id
+                        name
+                    }}
+                    frames {{
+                        start
+                    }}
+                    frameEffectIds
+                    repeat
+                    controlFrameIds
+                }}
+            }}
+            "#,
+            part.model_name, part.part_name
+        );
+
+        let data = execute(&schema, generate.clone()).await;
+        let generated = &data["generateLEDEffect"];
+        assert_eq!(generated["ok"], true);
+        assert_eq!(generated["repeat"], 0);
+        assert_eq!(generated["controlFrameIds"], serde_json::json!([]));
+
+        let starts = generated["frames"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|frame| frame["start"].as_i64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(starts, vec![0, 250, 500, 750]);
+
+        // every frame shows one of the created effects
+        let effects = generated["effects"].as_array().unwrap();
+        for effect in effects {
+            assert!(effect["name"].as_str().unwrap().starts_with("chase_"));
+        }
+        for id in generated["frameEffectIds"].as_array().unwrap() {
+            assert!(effects.iter().any(|effect| effect["id"] == *id));
+        }
+        for effect in effects {
+            let leds = effect_leds(&schema, &part, effect["name"].as_str().unwrap()).await;
+            assert_eq!(leds.len(), 6);
+        }
+
+        // the names are taken now
+        let message = execute_error(&schema, generate).await;
+        assert_eq!(message, "effectName chase_0 exists.");
+    }
+
+    #[tokio::test]
+    async fn test_generate_led_effect_rejects_bad_input() {
+        let schema = build_graphql().await;
+        let part = add_led_part(&schema, 6).await;
+
+        let message = execute_error(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    generateLEDEffect(input: {{
+                        name: "still",
+                        modelName: "{}",
+                        partName: "{}",
+                        pattern: RAINBOW,
+                        colorIds: [],
+                        speed: 0.0,
+                        duration: 1000
+                    }}) {{
+                        ok
+                    }}
+                }}
+                "#,
+                part.model_name, part.part_name
+            ),
+        )
+        .await;
+        assert_eq!(message, "Speed must be positive");
+
+        let message = execute_error(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    generateLEDEffect(input: {{
+                        name: "missing",
+                        modelName: "{}",
+                        partName: "missing",
+                        pattern: RAINBOW,
+                        colorIds: [],
+                        speed: 1.0,
+                        duration: 1000
+                    }}) {{
+                        ok
+                    }}
+                }}
+                "#,
+                part.model_name
+            ),
+        )
+        .await;
+        assert_eq!(message, "No corresponding LED part.");
+    }
+
+    #[tokio::test]
+    async fn test_retarget_led_effect() {
+        let schema = build_graphql().await;
+        let source = add_led_part(&schema, 4).await;
+        let target = add_led_part(&schema, 10).await;
+
+        let retarget = |name: &str| {
+            format!(
+                r#"
+                mutation {{
+                    retargetLEDEffect(input: {{
+                        id: {},
+                        modelName: "{}",
+                        partName: "{}",
+                        name: "{name}",
+                        mode: TILE
+                    }}) {{
+                        ok
+                        msg
+                        effects {{
+                            LEDs
+                        }}
+                    }}
+                }}
+                "#,
+                source.effect_id, target.model_name, target.part_name
+            )
+        };
+
+        let data = execute(&schema, retarget("tiled")).await;
+        assert_eq!(data["retargetLEDEffect"]["ok"], true);
+        let leds = effect_leds(&schema, &target, "tiled").await;
+        assert_eq!(leds.len(), 10);
+        assert_eq!(
+            data["retargetLEDEffect"]["effects"][0]["LEDs"],
+            Value::Array(leds)
+        );
+
+        // the target part already has an effect of this name
+        let data = execute(&schema, retarget(&target.effect_name)).await;
+        assert_eq!(data["retargetLEDEffect"]["ok"], false);
+        assert_eq!(data["retargetLEDEffect"]["msg"], "effectName exists.");
+
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    retargetLEDEffect(input: {{
+                        id: {},
+                        modelName: "{}",
+                        partName: "missing"
+                    }}) {{
+                        ok
+                        msg
+                    }}
+                }}
+                "#,
+                source.effect_id, target.model_name
+            ),
+        )
+        .await;
+        assert_eq!(data["retargetLEDEffect"]["ok"], false);
+        assert_eq!(
+            data["retargetLEDEffect"]["msg"],
+            "No corresponding LED part."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_show() {
+        let schema = build_graphql().await;
+        let part = add_led_part(&schema, 4).await;
+        let color_id = effect_leds(&schema, &part, &part.effect_name).await[0][0].clone();
+
+        // too few states, one too bright
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    addLEDEffect(input: {{
+                        name: "broken",
+                        modelName: "{}",
+                        partName: "{}",
+                        repeat: 0,
+                        frames: [{{ leds: [[{color_id}, 255], [{color_id}, 300]], fade: false, start: 0 }}]
+                    }}) {{
+                        id
+                    }}
+                }}
+                "#,
+                part.model_name, part.part_name
+            ),
+        )
+        .await;
+        let effect_id = data["addLEDEffect"]["id"].as_i64().unwrap();
+
+        let data = execute(
+            &schema,
+            r#"
+            {
+                validateShow {
+                    ok
+                    errorCount
+                    warningCount
+                    issues {
+                        kind
+                        severity
+                        effectId
+                        partId
+                    }
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+        let validation = &data["validateShow"];
+        let issues = validation["issues"].as_array().unwrap();
+
+        let count = |severity: &str| {
+            issues
+                .iter()
+                .filter(|issue| issue["severity"] == severity)
+                .count()
+        };
+        assert_eq!(validation["errorCount"], count("ERROR"));
+        assert_eq!(validation["warningCount"], count("WARNING"));
+        assert_eq!(validation["ok"], count("ERROR") == 0);
+
+        let effect_issues = issues
+            .iter()
+            .filter(|issue| issue["effectId"] == effect_id)
+            .collect::<Vec<_>>();
+        assert!(effect_issues
+            .iter()
+            .all(|issue| issue["partId"] == part.part_id));
+        assert!(effect_issues.iter().any(
+            |issue| issue["kind"] == "EFFECT_LENGTH_MISMATCH" && issue["severity"] == "WARNING"
+        ));
+        assert!(effect_issues
+            .iter()
+            .any(|issue| issue["kind"] == "ALPHA_OUT_OF_RANGE" && issue["severity"] == "ERROR"));
+        assert_eq!(validation["ok"], false);
+
+        execute(
+            &schema,
+            format!("mutation {{ deleteLEDEffect(id: {effect_id}) {{ ok }} }}"),
+        )
+        .await;
+    }
 }
@@ -0,0 +1,455 @@
+#[cfg(test)]
+mod motion_tests {
+    use serde_json::Value;
+
+    use editor_server::build_graphql;
+    use editor_server::graphql::schema::AppSchema;
+
+    /// A time after every frame of the show.
+    const LATE: i32 = 2_000_010_000;
+
+    async fn execute(schema: &AppSchema, query: String) -> Value {
+        let response = schema.execute(query).await;
+        assert!(response.is_ok(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    async fn execute_error(schema: &AppSchema, query: String) -> String {
+        let response = schema.execute(query).await;
+        assert!(response.is_err());
+        response.errors[0].message.clone()
+    }
+
+    async fn delete_frames(schema: &AppSchema, start: i32, end: i32) {
+        execute(
+            schema,
+            format!(
+                r#"
+                mutation {{
+                    deleteFrames(start: {start}, end: {end}, deleteControl: false, deletePosition: true) {{
+                        ok
+                    }}
+                }}
+                "#
+            ),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn generate_linear_motion() {
+        let schema = build_graphql().await;
+
+        let data = execute(&schema, "{ dancers { id } }".to_string()).await;
+        let dancer_id = data["dancers"][0]["id"].as_i64().unwrap();
+
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    generateMotion(input: {{
+                        dancerIds: [{dancer_id}],
+                        start: {LATE},
+                        end: {},
+                        interval: 50,
+                        kind: LINEAR,
+                        from: [0.0, 0.0, 0.0],
+                        to: [3.0, 0.0, 0.0]
+                    }}) {{
+                        ok
+                        frameIds
+                    }}
+                }}
+                "#,
+                LATE + 100
+            ),
+        )
+        .await;
+        assert_eq!(data["generateMotion"]["ok"], true);
+        let frame_ids = data["generateMotion"]["frameIds"]
+            .as_array()
+            .unwrap()
+            .clone();
+        assert_eq!(frame_ids.len(), 3);
+
+        // the dancer moves evenly along x
+        let data = execute(&schema, "{ PosMap { frameIds } }".to_string()).await;
+        let frames = &data["PosMap"]["frameIds"];
+        for ((frame_id, start), x) in frame_ids.iter().zip([0, 50, 100]).zip([0.0, 1.5, 3.0]) {
+            let frame = &frames[frame_id.to_string()];
+            assert_eq!(frame["start"], LATE + start);
+            assert_eq!(frame["has_position"][0], true);
+            assert!((frame["location"][0][0].as_f64().unwrap() - x).abs() < 1e-9);
+        }
+
+        delete_frames(&schema, LATE, LATE + 100).await;
+    }
+
+    #[tokio::test]
+    async fn generate_motion_requires_parameters() {
+        let schema = build_graphql().await;
+
+        let message = execute_error(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    generateMotion(input: {{
+                        dancerIds: [],
+                        start: {LATE},
+                        end: {},
+                        interval: 50,
+                        kind: ROTATE
+                    }}) {{
+                        ok
+                    }}
+                }}
+                "#,
+                LATE + 100
+            ),
+        )
+        .await;
+
+        assert_eq!(message, "Rotate requires rpm");
+    }
+
+    #[tokio::test]
+    async fn generate_motion_rejects_unknown_dancer() {
+        let schema = build_graphql().await;
+
+        let message = execute_error(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    generateMotion(input: {{
+                        dancerIds: [-1],
+                        start: {LATE},
+                        end: {},
+                        interval: 50,
+                        kind: LINEAR,
+                        to: [1.0, 0.0, 0.0]
+                    }}) {{
+                        ok
+                    }}
+                }}
+                "#,
+                LATE + 100
+            ),
+        )
+        .await;
+
+        assert_eq!(message, "Dancer #-1 not found");
+    }
+
+    #[tokio::test]
+    async fn generate_motion_rejects_bad_interval() {
+        let schema = build_graphql().await;
+
+        let message = execute_error(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    generateMotion(input: {{
+                        dancerIds: [],
+                        start: {LATE},
+                        end: {},
+                        interval: 0,
+                        kind: LINEAR,
+                        to: [1.0, 0.0, 0.0]
+                    }}) {{
+                        ok
+                    }}
+                }}
+                "#,
+                LATE + 100
+            ),
+        )
+        .await;
+
+        assert_eq!(message, "Interval must be positive");
+    }
+
+    #[tokio::test]
+    async fn transform_formation_scales_and_translates() {
+        let schema = build_graphql().await;
+        let start = LATE + 1000;
+
+        let data = execute(&schema, "{ dancers { id } }".to_string()).await;
+        let dancer_id = data["dancers"][0]["id"].as_i64().unwrap();
+
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    generateMotion(input: {{
+                        dancerIds: [{dancer_id}],
+                        start: {start},
+                        end: {},
+                        interval: 10,
+                        kind: LINEAR,
+                        from: [1.0, 0.0, 0.0],
+                        to: [1.0, 0.0, 0.0]
+                    }}) {{
+                        frameIds
+                    }}
+                }}
+                "#,
+                start + 10
+            ),
+        )
+        .await;
+        let frame_ids = data["generateMotion"]["frameIds"].clone();
+
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    transformFormation(input: {{
+                        dancerIds: [{dancer_id}],
+                        start: {start},
+                        end: {},
+                        scale: 2.0,
+                        translate: [0.0, 1.0, 0.0]
+                    }}) {{
+                        ok
+                        frameIds
+                    }}
+                }}
+                "#,
+                start + 10
+            ),
+        )
+        .await;
+        assert_eq!(data["transformFormation"]["ok"], true);
+        let mut transformed = data["transformFormation"]["frameIds"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|id| id.as_i64().unwrap())
+            .collect::<Vec<_>>();
+        transformed.sort();
+        let mut generated = frame_ids
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|id| id.as_i64().unwrap())
+            .collect::<Vec<_>>();
+        generated.sort();
+        assert_eq!(transformed, generated);
+
+        let data = execute(&schema, "{ PosMap { frameIds } }".to_string()).await;
+        for frame_id in frame_ids.as_array().unwrap() {
+            let location = &data["PosMap"]["frameIds"][frame_id.to_string()]["location"][0];
+            for (value, expected) in location.as_array().unwrap().iter().zip([2.0, 1.0, 0.0]) {
+                assert!((value.as_f64().unwrap() - expected).abs() < 1e-9);
+            }
+        }
+
+        delete_frames(&schema, start, start + 10).await;
+    }
+
+    #[tokio::test]
+    async fn transform_formation_without_dancers() {
+        let schema = build_graphql().await;
+
+        let data = execute(
+            &schema,
+            r#"
+            mutation {
+                transformFormation(input: { dancerIds: [], scale: 2.0 }) {
+                    ok
+                    frameIds
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(data["transformFormation"]["ok"], true);
+        assert_eq!(
+            data["transformFormation"]["frameIds"],
+            serde_json::json!([])
+        );
+    }
+
+    #[tokio::test]
+    async fn transform_formation_rejects_bad_scale() {
+        let schema = build_graphql().await;
+
+        let message = execute_error(
+            &schema,
+            r#"
+            mutation {
+                transformFormation(input: { dancerIds: [], scale: 0.0 }) {
+                    ok
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .await;
+
+        assert_eq!(message, "Scale must be a positive number");
+    }
+
+    #[tokio::test]
+    async fn interpolated_positions_between_frames() {
+        let schema = build_graphql().await;
+        let start = LATE + 2000;
+
+        let data = execute(&schema, "{ dancers { id } }".to_string()).await;
+        let dancer_id = data["dancers"][0]["id"].as_i64().unwrap();
+
+        execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    generateMotion(input: {{
+                        dancerIds: [{dancer_id}],
+                        start: {start},
+                        end: {},
+                        interval: 100,
+                        kind: LINEAR,
+                        from: [0.0, 0.0, 0.0],
+                        to: [2.0, 0.0, 0.0]
+                    }}) {{
+                        ok
+                    }}
+                }}
+                "#,
+                start + 100
+            ),
+        )
+        .await;
+
+        for method in ["LINEAR", "CATMULL_ROM"] {
+            let data = execute(
+                &schema,
+                format!(
+                    r#"
+                    {{
+                        interpolatedPositions(timestamps: [{start}, {}, {}], method: {method}) {{
+                            time
+                            location
+                            hasPosition
+                        }}
+                    }}
+                    "#,
+                    start + 50,
+                    start + 100
+                ),
+            )
+            .await;
+
+            let positions = data["interpolatedPositions"].as_array().unwrap();
+            assert_eq!(positions.len(), 3);
+            for (position, (time, x)) in positions.iter().zip([(0, 0.0), (50, 1.0), (100, 2.0)]) {
+                assert_eq!(position["time"], start + time);
+                assert_eq!(position["hasPosition"][0], true);
+                // the spline midpoint also depends on the frames before
+                if method == "CATMULL_ROM" && time == 50 {
+                    continue;
+                }
+                assert!((position["location"][0][0].as_f64().unwrap() - x).abs() < 1e-9);
+            }
+        }
+
+        delete_frames(&schema, start, start + 100).await;
+    }
+
+    #[tokio::test]
+    async fn interpolated_positions_without_timestamps() {
+        let schema = build_graphql().await;
+
+        let data = execute(
+            &schema,
+            "{ interpolatedPositions(timestamps: []) { time } }".to_string(),
+        )
+        .await;
+
+        assert_eq!(data["interpolatedPositions"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn quaternion_round_trip() {
+        let schema = build_graphql().await;
+        let start = LATE + 3000;
+
+        // a quarter turn about z
+        let half = std::f64::consts::FRAC_PI_4;
+        let quaternion = [half.cos(), 0.0, 0.0, half.sin()];
+
+        let data = execute(&schema, "{ dancers { id } }".to_string()).await;
+        let count = data["dancers"].as_array().unwrap().len();
+        let rows = vec![
+            format!(
+                "[1.0, 2.0, 0.0, {}, 0.0, 0.0, {}]",
+                quaternion[0], quaternion[3]
+            );
+            count
+        ];
+
+        let data = execute(
+            &schema,
+            format!(
+                r#"
+                mutation {{
+                    addPositionFrame(input: {{
+                        start: {start},
+                        positionData: [{}],
+                        hasPosition: {:?}
+                    }}) {{
+                        id
+                    }}
+                }}
+                "#,
+                rows.join(", "),
+                vec![true; count]
+            ),
+        )
+        .await;
+        let frame_id = data["addPositionFrame"]["id"].as_i64().unwrap();
+
+        let assert_close = |value: &Value, expected: &[f64]| {
+            let value = value.as_array().unwrap();
+            assert_eq!(value.len(), expected.len());
+            for (value, expected) in value.iter().zip(expected) {
+                assert!(
+                    (value.as_f64().unwrap() - expected).abs() < 1e-9,
+                    "{value} != {expected}"
+                );
+            }
+        };
+
+        let data = execute(
+            &schema,
+            format!("{{ PosMap(select: {{ frameIds: [{{ id: {frame_id} }}] }}) {{ frameIds }} }}"),
+        )
+        .await;
+        let frame = &data["PosMap"]["frameIds"][frame_id.to_string()];
+        assert_close(&frame["quaternion"][0], &quaternion);
+        assert_close(
+            &frame["rotation"][0],
+            &[0.0, 0.0, std::f64::consts::FRAC_PI_2],
+        );
+
+        let data = execute(
+            &schema,
+            format!("{{ interpolatedPositions(timestamps: [{start}]) {{ quaternion }} }}"),
+        )
+        .await;
+        assert_close(
+            &data["interpolatedPositions"][0]["quaternion"][0],
+            &quaternion,
+        );
+
+        delete_frames(&schema, start, start).await;
+    }
+}